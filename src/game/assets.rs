@@ -3,6 +3,9 @@ use bevy::{
     render::texture::{ImageLoaderSettings, ImageSampler},
     utils::HashMap,
 };
+use serde::{Deserialize, Serialize};
+
+use super::spawn::layout::{Level, LevelLoader};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<HandleMap<ImageKey>>();
@@ -19,6 +22,11 @@ pub(super) fn plugin(app: &mut App) {
 
     app.register_type::<HandleMap<FontKey>>();
     app.init_resource::<HandleMap<FontKey>>();
+
+    app.init_asset::<Level>();
+    app.init_asset_loader::<LevelLoader>();
+    app.register_type::<HandleMap<LevelKey>>();
+    app.init_resource::<HandleMap<LevelKey>>();
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
@@ -178,7 +186,7 @@ impl FromWorld for HandleMap<SoundtrackKey> {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect, Debug, Serialize, Deserialize)]
 pub enum SceneKey {
     Pc,
     Kitchen,
@@ -269,6 +277,28 @@ impl FromWorld for HandleMap<SceneKey> {
     }
 }
 
+/// Level layouts, see [`layout`](super::spawn::layout)
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect, Debug, Default)]
+pub enum LevelKey {
+    #[default]
+    Apartment,
+}
+
+impl AssetKey for LevelKey {
+    type Asset = Level;
+}
+
+impl FromWorld for HandleMap<LevelKey> {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        [(
+            LevelKey::Apartment,
+            asset_server.load("levels/apartment.level.ron"),
+        )]
+        .into()
+    }
+}
+
 pub trait AssetKey: Sized {
     type Asset: Asset;
 }
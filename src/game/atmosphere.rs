@@ -0,0 +1,396 @@
+//! Per-room atmosphere.
+//! The [`ShipMap`] walls and doors split the floor into rooms, each with its
+//! own oxygen and carbon dioxide. Every floor has its own rooms. Devices,
+//! fires and characters change the air of the room they are in through
+//! [`AirExchange`], and the gases spread through open doors and the elevator
+//! shaft. The global [`Oxygen`] and [`CarbonDioxide`] resources
+//! are kept as the average over the whole apartment, for the HUD.
+
+use bevy::prelude::*;
+
+use crate::screen::Screen;
+
+use super::{
+    daycycle::{GameOver, GameTime, PlayerState, TimeSpeed},
+    difficult::{BREATH_RATE, RES_LIMIT},
+    map::{navigation::world_to_tile, storey_of, ShipMap, Tile},
+    resources::{collect_generations, CarbonDioxide, GameResource, Generate, Oxygen},
+    spawn::player::Player,
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<Atmosphere>();
+    app.add_event::<AirExchange>();
+    app.add_systems(
+        Update,
+        (
+            update_rooms,
+            breathe,
+            apply_air_exchange,
+            diffuse,
+            suffocate,
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(
+        PostUpdate,
+        (
+            sync_aggregates
+                .after(collect_generations::<Oxygen>)
+                .after(collect_generations::<CarbonDioxide>),
+            reset_atmosphere,
+        )
+            .chain(),
+    );
+
+    #[cfg(feature = "dev")]
+    app.add_plugins(dev::plugin);
+}
+
+/// Part of the difference between two rooms that crosses an open door every second
+const DOOR_FLOW: f32 = 0.5;
+
+const NEIGHBOURS: [IVec2; 4] = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+/// Gas produced (positive) or used (negative) at a place, per second.
+/// Amounts are on the scale of the global resources: producing `1.0` raises
+/// the apartment average by one per second, whatever the room size.
+#[derive(Event, Clone, Copy)]
+pub struct AirExchange {
+    pub position: Vec3,
+    pub oxygen: f32,
+    pub co2: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RoomAir {
+    /// Floor tiles of the room
+    pub volume: f32,
+    pub oxygen: f32,
+    pub co2: f32,
+}
+
+/// Rooms of one floor
+struct StoreyRooms {
+    storey: usize,
+    width: usize,
+    /// Room of every tile, doors and walls belong to none
+    tile_rooms: Vec<Option<usize>>,
+}
+
+impl StoreyRooms {
+    fn room_of_tile(&self, tile: IVec2) -> Option<usize> {
+        if tile.x < 0 || tile.y < 0 || tile.x as usize >= self.width {
+            return None;
+        }
+        let index = tile.y as usize * self.width + tile.x as usize;
+        self.tile_rooms.get(index).copied().flatten()
+    }
+
+    /// Standing in a doorway counts as the first room next to it
+    fn room_near(&self, tile: IVec2) -> Option<usize> {
+        self.room_of_tile(tile).or_else(|| {
+            NEIGHBOURS
+                .iter()
+                .find_map(|direction| self.room_of_tile(tile + *direction))
+        })
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Atmosphere {
+    storeys: Vec<StoreyRooms>,
+    /// Rooms of every floor
+    rooms: Vec<RoomAir>,
+    /// Rooms joined by an open door or the elevator shaft
+    open_doors: Vec<(usize, usize)>,
+}
+
+impl Atmosphere {
+    fn storey(&self, storey: usize) -> Option<&StoreyRooms> {
+        self.storeys.iter().find(|rooms| rooms.storey == storey)
+    }
+
+    /// Room at the position. Standing in a doorway counts as the first room next to it.
+    pub fn room_at(&self, position: Vec3) -> Option<usize> {
+        self.storey(storey_of(position))?
+            .room_near(world_to_tile(position))
+    }
+
+    /// Air breathed at the position
+    pub fn air_at(&self, position: Vec3) -> Option<&RoomAir> {
+        self.room_at(position).map(|room| &self.rooms[room])
+    }
+
+    fn total_volume(&self) -> f32 {
+        self.rooms.iter().map(|room| room.volume).sum()
+    }
+
+    /// Average oxygen and carbon dioxide over the apartment
+    fn aggregate(&self) -> Option<(f32, f32)> {
+        let volume = self.total_volume();
+        if volume <= 0.0 {
+            return None;
+        }
+
+        let (oxygen, co2) = self.rooms.iter().fold((0.0, 0.0), |(oxygen, co2), room| {
+            (
+                oxygen + room.oxygen * room.volume,
+                co2 + room.co2 * room.volume,
+            )
+        });
+        Some((oxygen / volume, co2 / volume))
+    }
+}
+
+/// Finds the rooms again when walls or doors change, or a floor is added.
+/// New rooms start with the air of the tiles they are made of.
+fn update_rooms(
+    mut atmosphere: ResMut<Atmosphere>,
+    q_maps: Query<&ShipMap>,
+    q_changed: Query<(), Changed<ShipMap>>,
+    mut removed: RemovedComponents<ShipMap>,
+    oxygen: Res<Oxygen>,
+    co2: Res<CarbonDioxide>,
+) {
+    if q_changed.is_empty() && removed.read().count() == 0 {
+        return;
+    }
+
+    let mut storeys = Vec::new();
+    let mut rooms = Vec::new();
+    let mut open_doors = Vec::new();
+    // Room next to the elevator of every floor
+    let mut shafts: Vec<(usize, usize)> = Vec::new();
+
+    for map in q_maps.iter() {
+        let old = atmosphere.storey(map.storey());
+        let (width, height) = (map.width(), map.height());
+        let mut tile_rooms = vec![None; width * height];
+
+        for start in 0..width * height {
+            let start_tile = IVec2::new((start % width) as i32, (start / width) as i32);
+            if tile_rooms[start].is_some() || !matches!(map.get(start_tile), Some(Tile::Floor)) {
+                continue;
+            }
+
+            // Flood fill the floor, mixing the air the tiles had before
+            let room = rooms.len();
+            let mut air = RoomAir {
+                volume: 0.0,
+                oxygen: 0.0,
+                co2: 0.0,
+            };
+            let mut stack = vec![start_tile];
+            tile_rooms[start] = Some(room);
+            while let Some(tile) = stack.pop() {
+                let (old_oxygen, old_co2) = old
+                    .and_then(|old| old.room_of_tile(tile))
+                    .map(|old| (atmosphere.rooms[old].oxygen, atmosphere.rooms[old].co2))
+                    .unwrap_or((oxygen.amount(), co2.amount()));
+                air.volume += 1.0;
+                air.oxygen += old_oxygen;
+                air.co2 += old_co2;
+
+                for direction in NEIGHBOURS {
+                    let next = tile + direction;
+                    if !matches!(map.get(next), Some(Tile::Floor)) {
+                        continue;
+                    }
+                    let index = next.y as usize * width + next.x as usize;
+                    if tile_rooms[index].is_none() {
+                        tile_rooms[index] = Some(room);
+                        stack.push(next);
+                    }
+                }
+            }
+            air.oxygen /= air.volume;
+            air.co2 /= air.volume;
+            rooms.push(air);
+        }
+
+        let storey = StoreyRooms {
+            storey: map.storey(),
+            width,
+            tile_rooms,
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let tile = IVec2::new(x as i32, y as i32);
+                if !matches!(map.get(tile), Some(Tile::Door { open: true })) {
+                    continue;
+                }
+                let mut sides: Vec<usize> = NEIGHBOURS
+                    .iter()
+                    .filter_map(|direction| storey.room_of_tile(tile + *direction))
+                    .collect();
+                sides.sort_unstable();
+                sides.dedup();
+                if let [a, b] = sides[..] {
+                    open_doors.push((a, b));
+                }
+            }
+        }
+
+        if let Some(room) = map.elevator().and_then(|shaft| storey.room_near(shaft)) {
+            shafts.push((map.storey(), room));
+        }
+        storeys.push(storey);
+    }
+
+    // The shaft joins every floor to the one above
+    shafts.sort_unstable();
+    for pair in shafts.windows(2) {
+        if pair[1].0 == pair[0].0 + 1 {
+            open_doors.push((pair[0].1, pair[1].1));
+        }
+    }
+
+    *atmosphere = Atmosphere {
+        storeys,
+        rooms,
+        open_doors,
+    };
+}
+
+/// Every crew member breathes the air of the room they stand in
+fn breathe(q_crew: Query<&GlobalTransform, With<Player>>, mut exchanges: EventWriter<AirExchange>) {
+    // The whole crew breathes as much as before rooms existed
+    let rate = BREATH_RATE / q_crew.iter().count().max(1) as f32;
+    for transform in q_crew.iter() {
+        exchanges.send(AirExchange {
+            position: transform.translation(),
+            oxygen: -rate,
+            co2: rate,
+        });
+    }
+}
+
+fn apply_air_exchange(
+    mut atmosphere: ResMut<Atmosphere>,
+    mut exchanges: EventReader<AirExchange>,
+    mut oxygen: EventWriter<Generate<Oxygen>>,
+    mut co2: EventWriter<Generate<CarbonDioxide>>,
+    time: Res<GameTime>,
+) {
+    let total_volume = atmosphere.total_volume();
+    let dt = time.delta_seconds();
+
+    for exchange in exchanges.read() {
+        // Keeps the generation rate of the global resources for the HUD
+        oxygen.send(Generate::new(exchange.oxygen));
+        co2.send(Generate::new(exchange.co2));
+
+        let Some(room) = atmosphere.room_at(exchange.position) else {
+            continue;
+        };
+        let air = &mut atmosphere.rooms[room];
+        // The same gas raises the level of a small room faster
+        let scale = total_volume / air.volume * dt;
+        air.oxygen = (air.oxygen + exchange.oxygen * scale).clamp(0.0, RES_LIMIT);
+        air.co2 = (air.co2 + exchange.co2 * scale).clamp(0.0, RES_LIMIT);
+    }
+}
+
+fn diffuse(mut atmosphere: ResMut<Atmosphere>, time: Res<GameTime>) {
+    let flow = (DOOR_FLOW * time.delta_seconds()).min(0.5);
+    let Atmosphere {
+        rooms, open_doors, ..
+    } = &mut *atmosphere;
+
+    for (a, b) in open_doors.iter().copied() {
+        let (air_a, air_b) = (rooms[a], rooms[b]);
+        // Gas moved from `a` to `b`, the total amount doesn't change
+        let shared = air_a.volume * air_b.volume / (air_a.volume + air_b.volume);
+        let oxygen = (air_a.oxygen - air_b.oxygen) * shared * flow;
+        let co2 = (air_a.co2 - air_b.co2) * shared * flow;
+
+        rooms[a].oxygen -= oxygen / air_a.volume;
+        rooms[a].co2 -= co2 / air_a.volume;
+        rooms[b].oxygen += oxygen / air_b.volume;
+        rooms[b].co2 += co2 / air_b.volume;
+    }
+}
+
+/// A crew member in a room without oxygen, or full of carbon dioxide, dies
+/// even if the apartment average is fine
+fn suffocate(
+    atmosphere: Res<Atmosphere>,
+    q_crew: Query<&GlobalTransform, With<Player>>,
+    mut time_speed: ResMut<TimeSpeed>,
+    mut death: EventWriter<GameOver>,
+    mut next_state: ResMut<NextState<PlayerState>>,
+) {
+    if *time_speed == TimeSpeed::Pause {
+        return;
+    }
+
+    for transform in q_crew.iter() {
+        let Some(air) = atmosphere.air_at(transform.translation()) else {
+            continue;
+        };
+
+        let reason = if air.oxygen <= 0.0 {
+            Oxygen::default().death_reason(true)
+        } else if air.co2 >= RES_LIMIT {
+            CarbonDioxide::default().death_reason(false)
+        } else {
+            continue;
+        };
+
+        death.send(GameOver::died(
+            reason.unwrap_or("Capitalism Won".to_string()),
+        ));
+        *time_speed = TimeSpeed::Pause;
+        next_state.set(PlayerState::Dead);
+        return;
+    }
+}
+
+fn sync_aggregates(
+    atmosphere: Res<Atmosphere>,
+    mut oxygen: ResMut<Oxygen>,
+    mut co2: ResMut<CarbonDioxide>,
+) {
+    if let Some((average_oxygen, average_co2)) = atmosphere.aggregate() {
+        oxygen.set_amount(average_oxygen);
+        co2.set_amount(average_co2);
+    }
+}
+
+fn reset_atmosphere(mut resets: EventReader<ResetGame>, mut atmosphere: ResMut<Atmosphere>) {
+    for _ in resets.read() {
+        let (oxygen, co2) = (Oxygen::default(), CarbonDioxide::default());
+        for room in atmosphere.rooms.iter_mut() {
+            room.oxygen = oxygen.amount();
+            room.co2 = co2.amount();
+        }
+    }
+}
+
+#[cfg(feature = "dev")]
+mod dev {
+    use crate::dev_tools::DebugPanel;
+    use bevy::prelude::*;
+
+    use super::*;
+
+    pub fn plugin(app: &mut App) {
+        app.add_systems(Update, debug_panel);
+    }
+
+    fn debug_panel(mut debug_panel: ResMut<DebugPanel>, atmosphere: Res<Atmosphere>) {
+        for (i, room) in atmosphere.rooms.iter().enumerate() {
+            debug_panel.add(
+                format!("Room {}", i),
+                format!(
+                    "Room {} ({} tiles): O2 {:.0} / CO2 {:.0}",
+                    i, room.volume, room.oxygen, room.co2
+                ),
+            );
+        }
+    }
+}
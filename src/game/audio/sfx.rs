@@ -1,7 +1,10 @@
 use bevy::{audio::PlaybackMode, prelude::*};
 use rand::seq::SliceRandom;
 
-use crate::game::assets::{HandleMap, SfxKey};
+use crate::game::{
+    assets::{HandleMap, SfxKey},
+    rng::GameRng,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.observe(play_sfx);
@@ -11,10 +14,11 @@ fn play_sfx(
     trigger: Trigger<PlaySfx>,
     mut commands: Commands,
     sfx_handles: Res<HandleMap<SfxKey>>,
+    mut rng: ResMut<GameRng>,
 ) {
     let sfx_key = match trigger.event() {
         PlaySfx::Key(key) => *key,
-        PlaySfx::RandomStep => random_step(),
+        PlaySfx::RandomStep => random_step(&mut rng),
     };
     commands.spawn(AudioSourceBundle {
         source: sfx_handles[&sfx_key].clone_weak(),
@@ -32,9 +36,9 @@ pub enum PlaySfx {
     RandomStep,
 }
 
-fn random_step() -> SfxKey {
+fn random_step(rng: &mut GameRng) -> SfxKey {
     [SfxKey::Step1, SfxKey::Step2, SfxKey::Step3, SfxKey::Step4]
-        .choose(&mut rand::thread_rng())
+        .choose(rng.cosmetic())
         .copied()
         .unwrap()
 }
@@ -0,0 +1,394 @@
+//! Utility-AI autopilot that plays the game on its own.
+//! Every think tick it scores, for every crew member, the devices it could use
+//! with simple utility curves and sends it to the best one through
+//! [`RequestInteract`], so the resulting
+//! [`NewActionSequence`](super::sequence::NewActionSequence) is built by the
+//! same handlers a real mouse click goes through.
+
+use bevy::{prelude::*, utils::HashMap};
+use node_tree::{styling::Styling, tree::NodeTree, InsertNodumEntity};
+
+use crate::screen::Screen;
+
+use super::{
+    character::{CharState, CharacterStates},
+    components::{
+        fire::InFire, hydroponic::HydroponicState, kitchen::Kitchen,
+        oxygen_recycler::OxygenRegeneratorState, pc::Pc,
+    },
+    daycycle::GameTime,
+    difficult::RACION_SIZE,
+    input::{ActionState, InputAction},
+    needs::Need,
+    resources::{self, BadWater, Food, GameResource, Hungry, Oxygen, Thirst, Water},
+    selectable::{RequestInteract, Selectable},
+    sequence::Sequence,
+    spawn::{
+        player::Player,
+        spawn_commands::{MetalTrashPile, Toilet, WaterCleaner, WaterDispenser},
+    },
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<Autopilot>();
+    app.add_systems(Startup, |mut cmds: Commands| {
+        cmds.spawn(AutopilotMarker);
+    });
+    app.add_systems(Update, toggle_autopilot.run_if(in_state(Screen::Playing)));
+    app.add_systems(
+        PostUpdate,
+        (reset_autopilot, think.run_if(in_state(Screen::Playing))).chain(),
+    );
+    app.add_systems(Update, show_autopilot);
+}
+
+/// How often (in game seconds) the autopilot reconsiders what to do
+const THINK_PERIOD: f32 = 0.5;
+/// A new candidate must beat the current one by this much to interrupt it
+const SWITCH_MARGIN: f32 = 0.2;
+/// Candidates below this score are not worth walking to
+const MIN_SCORE: f32 = 0.05;
+/// Bonus for a candidate that answers the character's most important state
+const STATE_BONUS: f32 = 0.25;
+
+#[derive(Resource)]
+pub struct Autopilot {
+    pub enabled: bool,
+    think_timer: Timer,
+    /// Device the autopilot sent each crew member to and the score it had
+    current: HashMap<Entity, (Entity, f32)>,
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            think_timer: Timer::from_seconds(THINK_PERIOD, TimerMode::Repeating),
+            current: HashMap::default(),
+        }
+    }
+}
+
+/// Utility curves. All of them map the input into `0.0..=1.0`.
+mod curve {
+    /// Grows linearly from `lo` to `hi`
+    pub fn linear(x: f32, lo: f32, hi: f32) -> f32 {
+        ((x - lo) / (hi - lo)).clamp(0.0, 1.0)
+    }
+
+    /// Slow start, sharp finish. Good for needs that only matter when urgent
+    pub fn quadratic(x: f32, lo: f32, hi: f32) -> f32 {
+        linear(x, lo, hi).powi(2)
+    }
+
+    /// S-shaped transition between `lo` and `hi`
+    pub fn smoothstep(x: f32, lo: f32, hi: f32) -> f32 {
+        let t = linear(x, lo, hi);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+struct Candidate {
+    device: Entity,
+    score: f32,
+}
+
+/// Whether the crew member should leave what it is doing for `best`.
+/// `current` is the device the autopilot picked last time, with its
+/// `current_score` now, if it is still a candidate.
+fn should_switch(
+    busy: bool,
+    current: Option<(Entity, f32)>,
+    current_score: Option<f32>,
+    best: &Candidate,
+) -> bool {
+    match (busy, current, current_score) {
+        (false, _, _) => true,
+        (true, Some((current, _)), Some(score)) => {
+            current != best.device && best.score > score + SWITCH_MARGIN
+        }
+        // The crew member is busy with something the autopilot didn't pick
+        (true, None, _) => best.score > SWITCH_MARGIN,
+        (true, Some(_), None) => true,
+    }
+}
+
+fn toggle_autopilot(actions: Res<ActionState>, mut autopilot: ResMut<Autopilot>) {
+    if actions.just_pressed(InputAction::ToggleAutopilot) {
+        autopilot.enabled = !autopilot.enabled;
+        autopilot.current.clear();
+        info!("Autopilot enabled: {}", autopilot.enabled);
+    }
+}
+
+fn reset_autopilot(mut resets: EventReader<ResetGame>, mut autopilot: ResMut<Autopilot>) {
+    for _ in resets.read() {
+        autopilot.current.clear();
+        autopilot.think_timer.reset();
+    }
+}
+
+fn think(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut autopilot: ResMut<Autopilot>,
+    q_crew: Query<
+        (
+            Entity,
+            &Sequence,
+            &CharacterStates,
+            &Need<Thirst>,
+            &Need<Hungry>,
+            &Need<resources::Toilet>,
+        ),
+        With<Player>,
+    >,
+    q_devices: Query<
+        (
+            Entity,
+            Has<InFire>,
+            Has<Pc>,
+            Has<Kitchen>,
+            Has<WaterDispenser>,
+            Has<WaterCleaner>,
+            Has<Toilet>,
+            Has<MetalTrashPile>,
+            Option<&HydroponicState>,
+            Option<&OxygenRegeneratorState>,
+        ),
+        With<Selectable>,
+    >,
+    oxygen: Res<Oxygen>,
+    bad_water: Res<BadWater>,
+    water: Res<Water>,
+    food: Res<Food>,
+) {
+    if !autopilot.enabled {
+        return;
+    }
+
+    if !autopilot.think_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    // Forget crew members that don't exist anymore
+    autopilot.current.retain(|crew, _| q_crew.contains(*crew));
+
+    for (crew, sequence, states, thirst, hungry, toilet) in q_crew.iter() {
+        let state = states.get_importantest_state();
+        let bonus = |wanted: CharState| if state == wanted { STATE_BONUS } else { 0.0 };
+
+        // Devices another crew member is already on its way to
+        let taken: Vec<Entity> = autopilot
+            .current
+            .iter()
+            .filter(|(other, _)| **other != crew)
+            .map(|(_, (device, _))| *device)
+            .collect();
+
+        let mut candidates = Vec::new();
+        for (
+            device,
+            in_fire,
+            is_pc,
+            is_kitchen,
+            is_dispenser,
+            is_cleaner,
+            is_toilet,
+            is_trash,
+            hydroponic,
+            recycler,
+        ) in q_devices.iter()
+        {
+            if taken.contains(&device) {
+                continue;
+            }
+
+            let score = if in_fire {
+                // A burning device is destroyed soon, nothing is more important
+                1.0 + STATE_BONUS
+            } else if let Some(recycler) = recycler {
+                match recycler {
+                    OxygenRegeneratorState::Idle => {
+                        1.0 - curve::smoothstep(oxygen.amount(), 20.0, 50.0)
+                            + bonus(CharState::WantOxigen)
+                    }
+                    OxygenRegeneratorState::Work => {
+                        curve::smoothstep(oxygen.amount(), 60.0, 80.0)
+                            + bonus(CharState::TooManyOxigen)
+                    }
+                    OxygenRegeneratorState::InFire(_) => 0.0,
+                }
+            } else if let Some(hydroponic) = hydroponic {
+                match hydroponic {
+                    HydroponicState::NeedWater(_) if water.amount() > 0.0 => 0.8,
+                    HydroponicState::Growed => 0.5,
+                    HydroponicState::Dead if water.amount() > 0.0 => 0.3,
+                    _ => 0.0,
+                }
+            } else if is_dispenser && water.amount() > 0.0 {
+                curve::quadratic(thirst.amount(), 20.0, 80.0) + bonus(CharState::WantDrink)
+            } else if is_kitchen && food.amount() > RACION_SIZE {
+                curve::quadratic(hungry.amount(), 20.0, 80.0) + bonus(CharState::WantEat)
+            } else if is_toilet {
+                curve::quadratic(toilet.amount(), 10.0, 90.0) * 1.1 + bonus(CharState::WantPee)
+            } else if is_cleaner {
+                0.6 * curve::linear(bad_water.amount(), 10.0, 90.0)
+            } else if is_pc {
+                // Paying the debt is the default activity
+                0.2
+            } else if is_trash {
+                0.1
+            } else {
+                0.0
+            };
+
+            candidates.push(Candidate { device, score });
+        }
+
+        let Some(best) = candidates
+            .iter()
+            .max_by(|a, b| a.score.total_cmp(&b.score))
+            .filter(|best| best.score >= MIN_SCORE)
+        else {
+            autopilot.current.remove(&crew);
+            continue;
+        };
+
+        let current = autopilot.current.get(&crew).copied();
+
+        // The current job is finished once its device isn't worth anything anymore
+        let current_score = current.and_then(|(current, _)| {
+            candidates
+                .iter()
+                .find(|c| c.device == current)
+                .map(|c| c.score)
+        });
+
+        let busy = !sequence.actions.is_empty();
+        if should_switch(busy, current, current_score, best) {
+            info!(
+                "Autopilot sends {} to {} ({:.2})",
+                crew, best.device, best.score
+            );
+            autopilot.current.insert(crew, (best.device, best.score));
+            commands.trigger_targets(
+                RequestInteract {
+                    actor: crew,
+                    direct: false,
+                    append: false,
+                },
+                best.device,
+            );
+        }
+    }
+}
+
+#[derive(Component)]
+struct AutopilotMarker;
+
+fn show_autopilot(
+    mut commands: Commands,
+    autopilot: Res<Autopilot>,
+    q_show: Query<Entity, With<AutopilotMarker>>,
+) {
+    let mut tree = NodeTree::default();
+
+    if autopilot.enabled {
+        tree = tree
+            .with_bundle(TextBundle::from_section(
+                "Autopilot (P)",
+                TextStyle::default(),
+            ))
+            .with_top(Val::Px(30.0))
+            .with_right(Val::Px(10.0))
+            .with_position_type(PositionType::Absolute);
+    }
+
+    if let Ok(entity) = q_show.get_single() {
+        commands.add(InsertNodumEntity {
+            entity,
+            nodum: tree,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_are_clamped_to_unit_range() {
+        for curve in [curve::linear, curve::quadratic, curve::smoothstep] {
+            assert_eq!(curve(0.0, 20.0, 80.0), 0.0);
+            assert_eq!(curve(20.0, 20.0, 80.0), 0.0);
+            assert_eq!(curve(80.0, 20.0, 80.0), 1.0);
+            assert_eq!(curve(100.0, 20.0, 80.0), 1.0);
+        }
+    }
+
+    #[test]
+    fn curves_shape_between_bounds() {
+        assert_eq!(curve::linear(50.0, 0.0, 100.0), 0.5);
+        assert_eq!(curve::quadratic(50.0, 0.0, 100.0), 0.25);
+        assert_eq!(curve::smoothstep(50.0, 0.0, 100.0), 0.5);
+        // Smoothstep starts slower than linear and finishes faster
+        assert!(curve::smoothstep(25.0, 0.0, 100.0) < curve::linear(25.0, 0.0, 100.0));
+        assert!(curve::smoothstep(75.0, 0.0, 100.0) > curve::linear(75.0, 0.0, 100.0));
+    }
+
+    #[test]
+    fn curves_grow_monotonically() {
+        for curve in [curve::linear, curve::quadratic, curve::smoothstep] {
+            let mut last = 0.0;
+            for x in 0..=100 {
+                let value = curve(x as f32, 10.0, 90.0);
+                assert!(value >= last);
+                last = value;
+            }
+        }
+    }
+
+    #[test]
+    fn idle_crew_always_takes_the_best_candidate() {
+        let best = Candidate {
+            device: Entity::from_raw(1),
+            score: MIN_SCORE,
+        };
+        assert!(should_switch(false, None, None, &best));
+    }
+
+    #[test]
+    fn busy_crew_switches_only_for_a_clearly_better_candidate() {
+        let current = Entity::from_raw(1);
+        let other = Entity::from_raw(2);
+        let slightly_better = Candidate {
+            device: other,
+            score: 0.5 + SWITCH_MARGIN / 2.0,
+        };
+        let much_better = Candidate {
+            device: other,
+            score: 0.5 + SWITCH_MARGIN * 2.0,
+        };
+        assert!(!should_switch(
+            true,
+            Some((current, 0.5)),
+            Some(0.5),
+            &slightly_better
+        ));
+        assert!(should_switch(
+            true,
+            Some((current, 0.5)),
+            Some(0.5),
+            &much_better
+        ));
+        // The picked device isn't worth anything anymore, the job is done
+        assert!(should_switch(
+            true,
+            Some((current, 0.5)),
+            None,
+            &slightly_better
+        ));
+    }
+}
@@ -0,0 +1,795 @@
+//! Build mode.
+//! The palette places device blueprints on the [`ShipMap`] grid, moves
+//! devices or marks them for demolition. Every order becomes a
+//! [`ConstructionSite`], worked on by the crew as a [`WorkType::Building`]
+//! job. Building pays [`Metal`] when the blueprint is placed, demolishing
+//! gives part of it back. The trash pile of a destroyed device can be rebuilt
+//! into that device, for [`Metal`] or gathered [`MetalTrash`].
+//!
+//! [`WorkType::Building`]: super::jobs::WorkType::Building
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_mod_picking::prelude::*;
+
+use crate::{screen::Screen, ui::prelude::*};
+
+use super::{
+    character::{CharState, CharacterStates, GoToAction, IgnoreJustMoving},
+    components::device_kind::DeviceKind,
+    daycycle::GameTime,
+    input::{ActionState, InputAction},
+    map::{
+        navigation::{world_to_tile, NavBlockers},
+        on_storey, storey_height, storey_of, ShipMap, Tile,
+    },
+    resources::{Metal, MetalTrash},
+    selectable::{OnInteract, OnMouseClick, Selectable},
+    sequence::{ActionGroup, CharacterAction, NewActionSequence, NewMode, NextAction},
+    spawn::{
+        layout::{DevicePlacement, SpawnDevice, StartState},
+        player::Player,
+    },
+    storeys::ViewedStorey,
+    trouble_planner::Wreckage,
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<BuildMode>();
+    app.observe(pick_device);
+    app.observe(start_construction);
+    app.add_systems(
+        Update,
+        (
+            toggle_build_mode,
+            update_palette,
+            update_ghost,
+            place_blueprint,
+            update_construction,
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(PostUpdate, reset_build);
+}
+
+/// Devices the palette offers
+const BUILDABLE: [DeviceKind; 8] = [
+    DeviceKind::Pc,
+    DeviceKind::Kitchen,
+    DeviceKind::Hydroponic,
+    DeviceKind::OxygenRecycler,
+    DeviceKind::Toilet,
+    DeviceKind::WaterDispenser,
+    DeviceKind::WaterCleaner,
+    DeviceKind::Sensor,
+];
+/// Part of the building cost returned by a demolition
+const DEMOLISH_REFUND: f32 = 0.5;
+/// Game seconds to take a device apart
+const DEMOLISH_TIME: f32 = 3.0;
+/// Part of the building cost paid to rebuild a destroyed device
+const REBUILD_COST: f32 = 0.5;
+const SITE_GROUP: &str = "construction";
+
+const VALID_GHOST: Color = Color::srgba(0.2, 0.9, 0.3, 0.5);
+const INVALID_GHOST: Color = Color::srgba(0.9, 0.2, 0.2, 0.5);
+const BUILD_SITE: Color = Color::srgba(0.3, 0.6, 0.9, 0.4);
+const DEMOLISH_SITE: Color = Color::srgba(0.9, 0.4, 0.1, 0.4);
+
+/// Metal paid to build the device
+pub const fn build_cost(kind: DeviceKind) -> f32 {
+    match kind {
+        DeviceKind::Pc => 30.0,
+        DeviceKind::Kitchen => 40.0,
+        DeviceKind::Hydroponic => 25.0,
+        DeviceKind::OxygenRecycler => 60.0,
+        DeviceKind::Toilet => 20.0,
+        DeviceKind::WaterDispenser => 15.0,
+        DeviceKind::WaterCleaner => 35.0,
+        DeviceKind::Sensor => 10.0,
+        DeviceKind::MetalTrashPile => 0.0,
+    }
+}
+
+/// Game seconds of work to build the device
+const fn build_time(kind: DeviceKind) -> f32 {
+    build_cost(kind) / 5.0
+}
+
+/// Metal, or metal trash, paid to rebuild the device from its trash pile
+const fn rebuild_cost(kind: DeviceKind) -> f32 {
+    build_cost(kind) * REBUILD_COST
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BuildTool {
+    Place(DeviceKind),
+    /// Click on a device to pick it up
+    Move,
+    /// The device is rebuilt where the blueprint gets placed
+    Moving {
+        device: Entity,
+        kind: DeviceKind,
+    },
+    Demolish,
+    /// Click on a trash pile to rebuild the device it was
+    Rebuild,
+}
+
+impl BuildTool {
+    /// Device shown under the cursor
+    const fn blueprint(&self) -> Option<DeviceKind> {
+        match self {
+            BuildTool::Place(kind) | BuildTool::Moving { kind, .. } => Some(*kind),
+            BuildTool::Move | BuildTool::Demolish | BuildTool::Rebuild => None,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct BuildMode {
+    pub active: bool,
+    pub tool: Option<BuildTool>,
+    /// Rotation of the blueprint around the vertical axis, in degrees
+    rotation: f32,
+}
+
+/// Work ordered in build mode, waiting for a crew member
+#[derive(Component)]
+pub struct ConstructionSite {
+    pub work: SiteWork,
+    /// Game seconds of work done
+    progress: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum SiteWork {
+    Build {
+        kind: DeviceKind,
+        rotation: f32,
+        /// Metal returned when the site is cancelled
+        paid: f32,
+        /// Device taken away once the new one stands
+        moved_from: Option<Entity>,
+    },
+    Demolish {
+        device: Entity,
+        kind: DeviceKind,
+    },
+    Rebuild {
+        pile: Entity,
+        wreckage: Wreckage,
+        /// Metal returned when the site is cancelled
+        paid: f32,
+        /// Metal trash returned when the site is cancelled
+        paid_trash: f32,
+    },
+}
+
+impl SiteWork {
+    const fn duration(&self) -> f32 {
+        match self {
+            SiteWork::Build {
+                kind,
+                moved_from: None,
+                ..
+            } => build_time(*kind),
+            // Moving only takes a device apart and puts it back together
+            SiteWork::Build { .. } => DEMOLISH_TIME * 2.0,
+            SiteWork::Demolish { .. } => DEMOLISH_TIME,
+            SiteWork::Rebuild { wreckage, .. } => build_time(wreckage.kind),
+        }
+    }
+
+    /// Gives back what was paid for a cancelled site
+    const fn refund(&self, metal: &mut Metal, metal_trash: &mut MetalTrash) {
+        match *self {
+            SiteWork::Build { paid, .. } => metal.amount += paid,
+            SiteWork::Rebuild {
+                paid, paid_trash, ..
+            } => {
+                metal.amount += paid;
+                metal_trash.amount += paid_trash;
+            }
+            SiteWork::Demolish { .. } => {}
+        }
+    }
+}
+
+#[derive(Component)]
+struct BuildPalette;
+
+#[derive(Component)]
+struct PaletteStatus;
+
+#[derive(Component)]
+struct BlueprintGhost;
+
+/// Crew member working on a [`ConstructionSite`]
+#[derive(Component)]
+struct Constructing {
+    site: Entity,
+}
+
+struct ConstructAction {
+    site: Entity,
+}
+
+impl CharacterAction for ConstructAction {
+    fn trigger_start(&self, commands: &mut Commands, target: Entity) {
+        commands
+            .entity(target)
+            .insert(Constructing { site: self.site });
+    }
+
+    fn terminate(&self, commands: &mut Commands, target: Entity) {
+        commands.entity(target).remove::<Constructing>();
+    }
+}
+
+fn toggle_build_mode(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut build_mode: ResMut<BuildMode>,
+    q_palette: Query<Entity, With<BuildPalette>>,
+) {
+    if build_mode.active && actions.just_pressed(InputAction::RotateBlueprint) {
+        build_mode.rotation = (build_mode.rotation + 90.0) % 360.0;
+    }
+    if build_mode.active && mouse.just_pressed(MouseButton::Right) {
+        build_mode.tool = None;
+    }
+
+    if !actions.just_pressed(InputAction::ToggleBuildMode) {
+        return;
+    }
+
+    build_mode.active = !build_mode.active;
+    build_mode.tool = None;
+    if let Ok(palette) = q_palette.get_single() {
+        commands.entity(palette).despawn_recursive();
+    }
+    if build_mode.active {
+        spawn_palette(&mut commands);
+    }
+}
+
+fn spawn_palette(commands: &mut Commands) {
+    let pick = |tool: BuildTool| {
+        On::<Pointer<Click>>::run(move |mut build_mode: ResMut<BuildMode>| {
+            build_mode.tool = Some(tool);
+        })
+    };
+
+    commands
+        .spawn((
+            Name::new("Build palette"),
+            BuildPalette,
+            NodeBundle {
+                z_index: ZIndex::Global(5),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(10.0),
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .with_children(|builder| {
+            builder.small_label("").insert(PaletteStatus);
+            builder
+                .panel(Style {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(4.0),
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for kind in BUILDABLE {
+                        row.small_button(format!("{} ({})", kind.label(), build_cost(kind)))
+                            .insert(pick(BuildTool::Place(kind)));
+                    }
+                    row.small_button("Move").insert(pick(BuildTool::Move));
+                    row.small_button("Demolish")
+                        .insert(pick(BuildTool::Demolish));
+                    row.small_button("Rebuild").insert(pick(BuildTool::Rebuild));
+                });
+        });
+}
+
+fn update_palette(
+    build_mode: Res<BuildMode>,
+    metal: Res<Metal>,
+    mut q_status: Query<&mut Text, With<PaletteStatus>>,
+) {
+    let Ok(mut status) = q_status.get_single_mut() else {
+        return;
+    };
+
+    let tool = match build_mode.tool {
+        None => "Pick a blueprint".to_string(),
+        Some(BuildTool::Place(kind)) => format!("Placing {}, Q rotates", kind.label()),
+        Some(BuildTool::Move) => "Click on a device to move".to_string(),
+        Some(BuildTool::Moving { kind, .. }) => format!("Moving {}, Q rotates", kind.label()),
+        Some(BuildTool::Demolish) => "Click on a device to demolish".to_string(),
+        Some(BuildTool::Rebuild) => "Click on a trash pile to rebuild".to_string(),
+    };
+    let value = format!(
+        "Metal: {} - {} - Right click cancels, B leaves",
+        metal.amount, tool
+    );
+    if status.sections[0].value != value {
+        status.sections[0].value = value;
+    }
+}
+
+/// Tile under the mouse cursor, on the ground of the floor
+pub fn hovered_tile(
+    q_windows: &Query<&Window, With<PrimaryWindow>>,
+    q_cameras: &Query<(&Camera, &GlobalTransform), With<IsDefaultUiCamera>>,
+    storey: usize,
+) -> Option<IVec2> {
+    let cursor = q_windows.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = q_cameras.get_single().ok()?;
+    let ray = camera.viewport_to_world(camera_transform, cursor)?;
+    let ground = Vec3::Y * storey_height(storey);
+    let distance = ray.intersect_plane(ground, InfinitePlane3d::new(Vec3::Y))?;
+    Some(world_to_tile(ray.get_point(distance)))
+}
+
+/// Centre of the tile on the ground of the floor
+const fn tile_ground(tile: IVec2, storey: usize) -> Vec3 {
+    Vec3::new(tile.x as f32, storey_height(storey), tile.y as f32)
+}
+
+/// Tiles of the crew standing on the floor
+fn crew_tiles(q_crew: &Query<&GlobalTransform, With<Player>>, storey: usize) -> Vec<IVec2> {
+    q_crew
+        .iter()
+        .map(|transform| transform.translation())
+        .filter(|position| storey_of(*position) == storey)
+        .map(world_to_tile)
+        .collect()
+}
+
+/// A device can stand on the tile and be used from a neighbour tile
+fn can_build(tile: IVec2, map: &ShipMap, blockers: &NavBlockers, occupied: &[IVec2]) -> bool {
+    let free = |tile: IVec2| {
+        map.is_walkable(tile)
+            && !blockers.is_blocked(map.storey(), tile)
+            && !occupied.contains(&tile)
+    };
+
+    // Not in a doorway
+    matches!(map.get(tile), Some(Tile::Floor))
+        && free(tile)
+        && [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+            .into_iter()
+            .any(|direction| free(tile + direction))
+}
+
+fn update_ghost(
+    mut commands: Commands,
+    build_mode: Res<BuildMode>,
+    metal: Res<Metal>,
+    blockers: Res<NavBlockers>,
+    viewed: Res<ViewedStorey>,
+    q_maps: Query<&ShipMap>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_cameras: Query<(&Camera, &GlobalTransform), With<IsDefaultUiCamera>>,
+    q_crew: Query<&GlobalTransform, With<Player>>,
+    mut q_ghost: Query<(Entity, &mut Transform, &Handle<StandardMaterial>), With<BlueprintGhost>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let blueprint = build_mode
+        .tool
+        .filter(|_| build_mode.active)
+        .and_then(|tool| tool.blueprint());
+    let tile = hovered_tile(&q_windows, &q_cameras, viewed.storey);
+    let map = on_storey(q_maps.iter(), viewed.storey);
+
+    let (Some(kind), Some(tile), Some(map)) = (blueprint, tile, map) else {
+        for (ghost, _, _) in q_ghost.iter() {
+            commands.entity(ghost).despawn_recursive();
+        }
+        return;
+    };
+
+    let crew = crew_tiles(&q_crew, viewed.storey);
+    let affordable = match build_mode.tool {
+        Some(BuildTool::Place(kind)) => metal.amount >= build_cost(kind),
+        _ => true,
+    };
+    let color = if affordable && can_build(tile, map, &blockers, &crew) {
+        VALID_GHOST
+    } else {
+        INVALID_GHOST
+    };
+
+    let transform = Transform::from_translation(tile_ground(tile, viewed.storey) + Vec3::Y * 0.5)
+        .with_rotation(Quat::from_rotation_y(build_mode.rotation.to_radians()));
+
+    if let Ok((_, mut ghost_transform, material)) = q_ghost.get_single_mut() {
+        *ghost_transform = transform;
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color = color;
+        }
+        return;
+    }
+
+    commands.spawn((
+        Name::new(format!("{} blueprint", kind.label())),
+        BlueprintGhost,
+        PbrBundle {
+            mesh: meshes.add(Cuboid::new(0.9, 1.0, 0.9)),
+            material: materials.add(StandardMaterial {
+                base_color: color,
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            transform,
+            ..default()
+        },
+        Pickable::IGNORE,
+    ));
+}
+
+fn place_blueprint(
+    mut commands: Commands,
+    mut build_mode: ResMut<BuildMode>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut metal: ResMut<Metal>,
+    blockers: Res<NavBlockers>,
+    viewed: Res<ViewedStorey>,
+    q_maps: Query<&ShipMap>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_cameras: Query<(&Camera, &GlobalTransform), With<IsDefaultUiCamera>>,
+    q_crew: Query<&GlobalTransform, With<Player>>,
+    q_buttons: Query<&Interaction>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !build_mode.active || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    // The click was for the palette
+    if q_buttons
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+
+    let (kind, moved_from) = match build_mode.tool {
+        Some(BuildTool::Place(kind)) => (kind, None),
+        Some(BuildTool::Moving { device, kind }) => (kind, Some(device)),
+        _ => return,
+    };
+    let tile = hovered_tile(&q_windows, &q_cameras, viewed.storey);
+    let (Some(tile), Some(map)) = (tile, on_storey(q_maps.iter(), viewed.storey)) else {
+        return;
+    };
+
+    let crew = crew_tiles(&q_crew, viewed.storey);
+    if !can_build(tile, map, &blockers, &crew) {
+        return;
+    }
+
+    let paid = if moved_from.is_some() {
+        0.0
+    } else {
+        build_cost(kind)
+    };
+    if metal.amount < paid {
+        info!("Not enough metal to build {}", kind.label());
+        return;
+    }
+    metal.amount -= paid;
+
+    spawn_site(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        tile_ground(tile, viewed.storey),
+        SiteWork::Build {
+            kind,
+            rotation: build_mode.rotation,
+            paid,
+            moved_from,
+        },
+    );
+
+    // A device is only moved once, new devices can be placed again
+    if moved_from.is_some() {
+        build_mode.tool = Some(BuildTool::Move);
+    }
+}
+
+fn spawn_site(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    ground: Vec3,
+    work: SiteWork,
+) {
+    let (name, color) = match work {
+        SiteWork::Build { kind, .. } => (format!("Building {}", kind.label()), BUILD_SITE),
+        SiteWork::Demolish { kind, .. } => (format!("Demolishing {}", kind.label()), DEMOLISH_SITE),
+        SiteWork::Rebuild { wreckage, .. } => {
+            (format!("Rebuilding {}", wreckage.kind.label()), BUILD_SITE)
+        }
+    };
+
+    commands.spawn((
+        Name::new(name),
+        ConstructionSite {
+            work,
+            progress: 0.0,
+        },
+        PbrBundle {
+            mesh: meshes.add(Cuboid::new(1.0, 1.2, 1.0)),
+            material: materials.add(StandardMaterial {
+                base_color: color,
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            }),
+            transform: Transform::from_translation(ground + Vec3::Y * 0.6),
+            ..default()
+        },
+        Selectable,
+        IgnoreJustMoving,
+    ));
+}
+
+/// Clicks on devices and sites while in build mode
+fn pick_device(
+    trigger: Trigger<OnMouseClick>,
+    mut commands: Commands,
+    mut build_mode: ResMut<BuildMode>,
+    mut metal: ResMut<Metal>,
+    mut metal_trash: ResMut<MetalTrash>,
+    q_devices: Query<(&DeviceKind, &GlobalTransform)>,
+    q_wreckage: Query<&Wreckage>,
+    q_sites: Query<&ConstructionSite>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let target = trigger.entity();
+    if !build_mode.active || trigger.event().0 != MouseButton::Left {
+        return;
+    }
+
+    // Demolishing a site cancels it
+    if let Ok(site) = q_sites.get(target) {
+        if build_mode.tool == Some(BuildTool::Demolish) {
+            site.work.refund(&mut metal, &mut metal_trash);
+            commands.entity(target).despawn_recursive();
+        }
+        return;
+    }
+
+    let Ok((kind, transform)) = q_devices.get(target) else {
+        return;
+    };
+    // Trash piles can only be rebuilt into what they were
+    if *kind == DeviceKind::MetalTrashPile {
+        let Ok(wreckage) = q_wreckage.get(target) else {
+            return;
+        };
+        let already_ordered = q_sites
+            .iter()
+            .any(|site| matches!(site.work, SiteWork::Rebuild { pile, .. } if pile == target));
+        if build_mode.tool != Some(BuildTool::Rebuild) || already_ordered {
+            return;
+        }
+
+        // Metal first, the gathered trash when short of it
+        let cost = rebuild_cost(wreckage.kind);
+        let (paid, paid_trash) = if metal.amount >= cost {
+            (cost, 0.0)
+        } else if metal_trash.amount >= cost {
+            (0.0, cost)
+        } else {
+            info!("Not enough metal to rebuild {}", wreckage.kind.label());
+            return;
+        };
+        metal.amount -= paid;
+        metal_trash.amount -= paid_trash;
+
+        let position = transform.translation();
+        spawn_site(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            tile_ground(world_to_tile(position), storey_of(position)),
+            SiteWork::Rebuild {
+                pile: target,
+                wreckage: *wreckage,
+                paid,
+                paid_trash,
+            },
+        );
+        return;
+    }
+
+    match build_mode.tool {
+        Some(BuildTool::Move) => {
+            build_mode.tool = Some(BuildTool::Moving {
+                device: target,
+                kind: *kind,
+            });
+        }
+        Some(BuildTool::Demolish) => {
+            let already_ordered = q_sites.iter().any(|site| match site.work {
+                SiteWork::Demolish { device, .. } => device == target,
+                // The move is cancelled first, or it would bring the device back
+                SiteWork::Build { moved_from, .. } => moved_from == Some(target),
+                SiteWork::Rebuild { .. } => false,
+            });
+            if !already_ordered {
+                let position = transform.translation();
+                spawn_site(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    tile_ground(world_to_tile(position), storey_of(position)),
+                    SiteWork::Demolish {
+                        device: target,
+                        kind: *kind,
+                    },
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+fn start_construction(
+    trigger: Trigger<OnInteract>,
+    mut commands: Commands,
+    q_sites: Query<&GlobalTransform, With<ConstructionSite>>,
+) {
+    let site = trigger.entity();
+    let Ok(transform) = q_sites.get(site) else {
+        return;
+    };
+
+    let mut actions = ActionGroup::new(SITE_GROUP.to_string());
+    if !trigger.event().direct {
+        actions.add(GoToAction {
+            target: site,
+            target_pos: transform.translation(),
+        });
+    }
+    actions.add(ConstructAction { site });
+
+    commands.trigger_targets(
+        NewActionSequence {
+            actions,
+            mode: trigger.event().mode(NewMode::SoftReplace),
+        },
+        trigger.event().actor,
+    );
+}
+
+fn update_construction(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut metal: ResMut<Metal>,
+    mut metal_trash: ResMut<MetalTrash>,
+    mut q_crew: Query<(Entity, &Constructing, &mut CharacterStates)>,
+    mut q_sites: Query<(&mut ConstructionSite, &GlobalTransform)>,
+    q_devices: Query<&DeviceKind>,
+    q_wreckage: Query<(), With<Wreckage>>,
+) {
+    for (crew, constructing, mut states) in q_crew.iter_mut() {
+        let Ok((mut site, transform)) = q_sites.get_mut(constructing.site) else {
+            // The site was cancelled
+            commands.entity(crew).remove::<Constructing>();
+            commands.trigger_targets(NextAction, crew);
+            continue;
+        };
+
+        // What the site works on burned down, was demolished or gathered up
+        // in the meantime
+        let gone = match site.work {
+            SiteWork::Build {
+                kind,
+                moved_from: Some(device),
+                ..
+            }
+            | SiteWork::Demolish { device, kind } => q_devices.get(device).ok() != Some(&kind),
+            SiteWork::Rebuild { pile, .. } => !q_wreckage.contains(pile),
+            SiteWork::Build { .. } => false,
+        };
+        if gone {
+            info!("Site cancelled, what it worked on is gone");
+            site.work.refund(&mut metal, &mut metal_trash);
+            commands.entity(constructing.site).despawn_recursive();
+            commands.entity(crew).remove::<Constructing>();
+            commands.trigger_targets(NextAction, crew);
+            continue;
+        }
+
+        states.add(CharState::Working);
+        site.progress += time.delta_seconds();
+        if site.progress < site.work.duration() {
+            continue;
+        }
+
+        let position = transform.translation();
+        let tile = world_to_tile(position);
+        let ground = storey_height(storey_of(position));
+        match site.work {
+            SiteWork::Build {
+                kind,
+                rotation,
+                moved_from,
+                ..
+            } => {
+                if let Some(device) = moved_from {
+                    commands.entity(device).despawn_recursive();
+                }
+                commands.add(SpawnDevice(DevicePlacement {
+                    kind,
+                    position: [tile.x as f32, ground + kind.floor_height(), tile.y as f32],
+                    rotation,
+                    scale: None,
+                    state: StartState::Working,
+                    components: default(),
+                }));
+                info!("{} built", kind.label());
+            }
+            SiteWork::Demolish { device, kind } => {
+                commands.entity(device).despawn_recursive();
+                let refund = build_cost(kind) * DEMOLISH_REFUND;
+                metal.amount += refund;
+                info!("{} demolished, {} metal recovered", kind.label(), refund);
+            }
+            SiteWork::Rebuild { pile, wreckage, .. } => {
+                if let Some(pile) = commands.get_entity(pile) {
+                    pile.despawn_recursive();
+                }
+                // Back where it stood, as it was placed
+                let (rotation, _, _) = wreckage.transform.rotation.to_euler(EulerRot::YXZ);
+                commands.add(SpawnDevice(DevicePlacement {
+                    kind: wreckage.kind,
+                    position: wreckage.transform.translation.to_array(),
+                    rotation: rotation.to_degrees(),
+                    scale: Some(wreckage.transform.scale.x),
+                    state: StartState::Working,
+                    components: default(),
+                }));
+                info!("{} rebuilt", wreckage.kind.label());
+            }
+        }
+
+        commands.entity(constructing.site).despawn_recursive();
+        commands.entity(crew).remove::<Constructing>();
+        commands.trigger_targets(NextAction, crew);
+    }
+}
+
+fn reset_build(
+    mut commands: Commands,
+    mut resets: EventReader<ResetGame>,
+    mut build_mode: ResMut<BuildMode>,
+    q_sites: Query<Entity, With<ConstructionSite>>,
+    q_palette: Query<Entity, With<BuildPalette>>,
+) {
+    for _ in resets.read() {
+        *build_mode = BuildMode::default();
+        for entity in q_sites.iter().chain(q_palette.iter()) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -12,16 +12,21 @@ use crate::{game::resources::Food, screen::Screen};
 
 use super::{
     assets::{HandleMap, SfxKey},
+    atmosphere::Atmosphere,
     billboard_state::{BillboardContent, BillboardSpawner},
     components::oxygen_recycler,
     daycycle::{GameTime, TimeSpeed},
+    difficult::RES_LIMIT,
+    map::navigation::Path,
+    needs::Need,
     resources::{
         CarbonDioxide, GameResource, Hungry, Oxygen, OxygenRecycling, Pee, ResourceThreshold,
         Thirst,
     },
-    selectable::OnMouseClick,
+    selectable::OnInteract,
     sequence::{ActionGroup, CharacterAction, NewActionSequence, NewMode, NextAction, Sequence},
     spawn::player::Player,
+    steering::Steering,
 };
 
 pub(crate) fn plugin(app: &mut App) {
@@ -29,10 +34,10 @@ pub(crate) fn plugin(app: &mut App) {
     app.observe(add_target);
     app.init_state::<HouseState>();
     app.add_systems(PreUpdate, clear_states);
-    app.add_systems(Update, set_resource_warnings::<Oxygen>);
-    app.add_systems(Update, set_resource_warnings::<Pee>);
-    app.add_systems(Update, set_resource_warnings::<Thirst>);
-    app.add_systems(Update, set_resource_warnings::<Hungry>);
+    app.add_systems(Update, set_air_warnings);
+    app.add_systems(Update, set_need_warnings::<Pee>);
+    app.add_systems(Update, set_need_warnings::<Thirst>);
+    app.add_systems(Update, set_need_warnings::<Hungry>);
     app.add_systems(PostUpdate, (print_state, set_house_state).chain());
     app.enable_state_scoped_entities::<HouseState>();
     app.add_systems(OnEnter(HouseState::Alarm), play_alarm);
@@ -50,12 +55,13 @@ pub enum HouseState {
 #[derive(Component)]
 pub struct IgnoreJustMoving;
 
+/// Walk to an interaction tile next to `target`, following a
+/// [`Path`](super::map::navigation::Path)
 #[derive(Component)]
 pub struct DestinationTarget {
     #[allow(dead_code)]
     pub target: Entity,
     pub target_pos: Vec3,
-    pub accept_radius: f32,
 }
 
 pub struct GoToAction {
@@ -65,27 +71,35 @@ pub struct GoToAction {
 
 impl CharacterAction for GoToAction {
     fn trigger_start(&self, commands: &mut Commands, target: Entity) {
-        commands.entity(target).insert(DestinationTarget {
-            target: self.target,
-            target_pos: self.target_pos,
-            accept_radius: 0.5,
-        });
+        commands
+            .entity(target)
+            .remove::<Path>()
+            .insert(DestinationTarget {
+                target: self.target,
+                target_pos: self.target_pos,
+            });
     }
 
     fn terminate(&self, commands: &mut Commands, target: Entity) {
-        commands.entity(target).remove::<DestinationTarget>();
+        commands
+            .entity(target)
+            .remove::<DestinationTarget>()
+            .remove::<Path>();
+    }
+
+    fn target(&self) -> Option<Entity> {
+        Some(self.target)
     }
 }
 
 fn add_target(
-    trigger: Trigger<OnMouseClick>,
+    trigger: Trigger<OnInteract>,
     mut commands: Commands,
-    q_player: Query<Entity, With<Player>>,
     q_selected: Query<&GlobalTransform, Without<IgnoreJustMoving>>,
 ) {
     let clicked_entity = trigger.entity();
 
-    if trigger.event().0 != MouseButton::Left {
+    if trigger.event().direct {
         return;
     }
 
@@ -100,39 +114,56 @@ fn add_target(
         target_pos: target_component.translation(),
     });
 
-    if !q_player.is_empty() {
-        commands.trigger_targets(
-            NewActionSequence {
-                actions,
-                mode: NewMode::Replace,
-            },
-            q_player.iter().next().unwrap(),
-        );
-    }
+    commands.trigger_targets(
+        NewActionSequence {
+            actions,
+            mode: trigger.event().mode(NewMode::Replace),
+        },
+        trigger.event().actor,
+    );
 }
 
-fn move_player_to_target(
+pub(crate) fn move_player_to_target(
     mut commands: Commands,
     time: Res<GameTime>,
-    mut query: Query<(Entity, &mut Transform, &DestinationTarget), With<Player>>,
+    mut query: Query<
+        (Entity, &mut Transform, &mut Path, Option<&Steering>),
+        (With<Player>, With<DestinationTarget>),
+    >,
 ) {
-    for (player_entity, mut transform, target) in query.iter_mut() {
-        let player_position = transform.translation;
-        let target_position = target.target_pos;
+    for (player_entity, mut transform, mut path, steering) in query.iter_mut() {
+        if path.is_finished() {
+            commands
+                .entity(player_entity)
+                .remove::<DestinationTarget>()
+                .remove::<Path>();
+            commands.trigger_targets(NextAction, player_entity);
+            continue;
+        }
 
-        let cos_result = Quat::from_scaled_axis(player_position - target_position);
-        let direction = (target_position - player_position).normalize();
-        let direction = Vec3::new(direction.x, 0.0, direction.z);
-        let distance = Vec3::new(player_position.x, 0.0, player_position.z).distance(Vec3::new(target_position.x, 0.0, target_position.z));
+        if steering.is_some_and(|steering| steering.yielding) {
+            continue;
+        }
+
+        let Some(waypoint) = path.next() else {
+            continue;
+        };
+
+        let player_position = transform.translation;
+        let cos_result = Quat::from_scaled_axis(player_position - waypoint);
+        // Waypoints only change height when riding the elevator
+        let offset = waypoint - player_position;
+        let step = time.delta_seconds() * PLAYER_SPEED;
 
-        if distance > target.accept_radius {
-            transform.translation += direction * time.delta_seconds() * PLAYER_SPEED;
+        if offset.length() > step {
+            transform.translation += offset.normalize() * step;
             // the character should just spin on its y axis at the beginning
             transform.rotate_local_y(cos_result.y);
         } else {
-            commands.entity(player_entity).remove::<DestinationTarget>();
-            commands.trigger_targets(NextAction, player_entity);
+            transform.translation += offset;
         }
+
+        path.advance(transform.translation);
     }
 }
 
@@ -283,44 +314,67 @@ fn print_state(mut q_char: Query<(&mut CharacterStates, &mut BillboardSpawner)>)
     }
 }
 
-fn set_resource_warnings<T: GameResource + Clone>(
-    mut q_char: Query<&mut CharacterStates>,
-    mut oxygen_recycler: Res<OxygenRecycling>,
-    resource: ResMut<T>,
-    mut time_speed: ResMut<TimeSpeed>,
+/// Every character checks the air of the room it is in
+fn set_air_warnings(
+    mut q_char: Query<(&mut CharacterStates, &GlobalTransform)>,
+    atmosphere: Res<Atmosphere>,
+    oxygen_recycler: Res<OxygenRecycling>,
+    screen: Res<State<Screen>>,
+) {
+    if *screen != Screen::Playing {
+        return;
+    }
+
+    for (mut states, transform) in q_char.iter_mut() {
+        let Some(air) = atmosphere.air_at(transform.translation()) else {
+            continue;
+        };
+        let oxygen = Oxygen::new(air.oxygen, RES_LIMIT);
+        let co2 = CarbonDioxide::new(air.co2, RES_LIMIT);
+        states.add(resource_warning(oxygen, oxygen_recycler.working));
+        states.add(resource_warning(co2, oxygen_recycler.working));
+    }
+}
+
+/// Same as [`set_air_warnings`], but every character checks its own [`Need`]
+fn set_need_warnings<T: GameResource + Copy>(
+    mut q_char: Query<(&mut CharacterStates, &Need<T>)>,
+    oxygen_recycler: Res<OxygenRecycling>,
     screen: Res<State<Screen>>,
 ) {
     if *screen != Screen::Playing {
         return;
     }
 
+    for (mut states, need) in q_char.iter_mut() {
+        states.add(resource_warning(need.0, oxygen_recycler.working));
+    }
+}
+
+/// [`CharState`] for a resource that is outside of its warning thresholds
+fn resource_warning<T: GameResource>(resource: T, oxygen_working: bool) -> CharState {
     let amount = resource.amount();
-    let oxygen_working = oxygen_recycler.working;
     let (min_o, max_o) = resource.warning_thresholds();
-    let state: CharState = match (resource.resource_threshold(), min_o, max_o) {
+    match (resource.resource_threshold(), min_o, max_o) {
         (ResourceThreshold::HealthyRange, Some(min), _) if min >= amount => {
-            resource_to_state(resource.clone(), true, oxygen_working)
+            resource_to_state(resource, true, oxygen_working)
         }
         (ResourceThreshold::HealthyRange, _, Some(max)) if max <= amount => {
-            resource_to_state(resource.clone(), false, oxygen_working)
+            resource_to_state(resource, false, oxygen_working)
         }
         (ResourceThreshold::Necessity, Some(min), _) if min >= amount => {
-            resource_to_state(resource.clone(), true, oxygen_working)
+            resource_to_state(resource, true, oxygen_working)
         }
         (ResourceThreshold::Waste, _, Some(max)) if max <= amount => {
-            resource_to_state(resource.clone(), false, oxygen_working)
+            resource_to_state(resource, false, oxygen_working)
         }
         _ => CharState::Idle,
-    };
-
-    for mut states in q_char.iter_mut() {
-        states.add(state);
     }
 }
 
 /**
  * Map from a [`GameResource`] to it's [`CharState`]
- * Used in [`set_resources_warnings`] to automatically set billboards
+ * Used in [`resource_warning`] to automatically set billboards
  */
 fn resource_to_state<T: GameResource + Any>(
     res: T,
@@ -340,6 +394,11 @@ fn resource_to_state<T: GameResource + Any>(
         };
     }
 
+    // Stale air feels the same as missing oxygen
+    if any_res.downcast_ref::<CarbonDioxide>().is_some() && !is_deficiency {
+        return CharState::WantOxigen;
+    }
+
     if any_res.downcast_ref::<Hungry>().is_some() && !is_deficiency {
         return CharState::WantEat;
     }
@@ -0,0 +1,117 @@
+//! Type of a device, derived from its marker component.
+//! Lets systems find "another device of the same type" without a query per
+//! marker.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    components::{hydroponic::Hydroponic, kitchen::Kitchen, pc::Pc},
+    selectable::Selectable,
+    spawn::spawn_commands::{
+        MetalTrashPile, OxygenRecyler, Sensor, Toilet, WaterCleaner, WaterDispenser,
+    },
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(Update, assign_device_kind);
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum DeviceKind {
+    Pc,
+    Kitchen,
+    Hydroponic,
+    OxygenRecycler,
+    Toilet,
+    WaterDispenser,
+    WaterCleaner,
+    Sensor,
+    MetalTrashPile,
+}
+
+impl DeviceKind {
+    pub const ALL: [DeviceKind; 9] = [
+        DeviceKind::Pc,
+        DeviceKind::Kitchen,
+        DeviceKind::Hydroponic,
+        DeviceKind::OxygenRecycler,
+        DeviceKind::Toilet,
+        DeviceKind::WaterDispenser,
+        DeviceKind::WaterCleaner,
+        DeviceKind::Sensor,
+        DeviceKind::MetalTrashPile,
+    ];
+
+    pub const fn label(&self) -> &'static str {
+        match self {
+            DeviceKind::Pc => "PC",
+            DeviceKind::Kitchen => "Kitchen",
+            DeviceKind::Hydroponic => "Hydroponics",
+            DeviceKind::OxygenRecycler => "Oxygen recycler",
+            DeviceKind::Toilet => "Toilet",
+            DeviceKind::WaterDispenser => "Water dispenser",
+            DeviceKind::WaterCleaner => "Water cleaner",
+            DeviceKind::Sensor => "Fire sensor",
+            DeviceKind::MetalTrashPile => "Metal trash",
+        }
+    }
+
+    /// Height of the device model above the floor tiles
+    pub const fn floor_height(&self) -> f32 {
+        match self {
+            DeviceKind::Pc => 0.5,
+            DeviceKind::Hydroponic => 0.1,
+            // On the wall, above the devices it watches
+            DeviceKind::Sensor => 1.2,
+            _ => 0.0,
+        }
+    }
+}
+
+fn assign_device_kind(
+    mut commands: Commands,
+    q_new: Query<
+        (
+            Entity,
+            Has<Pc>,
+            Has<Kitchen>,
+            Has<Hydroponic>,
+            Has<OxygenRecyler>,
+            Has<Toilet>,
+            Has<WaterDispenser>,
+            Has<WaterCleaner>,
+            Has<Sensor>,
+            Has<MetalTrashPile>,
+        ),
+        (With<Selectable>, Without<DeviceKind>),
+    >,
+) {
+    for (entity, pc, kitchen, hydroponic, recycler, toilet, dispenser, cleaner, sensor, trash) in
+        q_new.iter()
+    {
+        let kind = if pc {
+            DeviceKind::Pc
+        } else if kitchen {
+            DeviceKind::Kitchen
+        } else if hydroponic {
+            DeviceKind::Hydroponic
+        } else if recycler {
+            DeviceKind::OxygenRecycler
+        } else if toilet {
+            DeviceKind::Toilet
+        } else if dispenser {
+            DeviceKind::WaterDispenser
+        } else if cleaner {
+            DeviceKind::WaterCleaner
+        } else if sensor {
+            DeviceKind::Sensor
+        } else if trash {
+            DeviceKind::MetalTrashPile
+        } else {
+            continue;
+        };
+
+        commands.entity(entity).insert(kind);
+    }
+}
@@ -1,18 +1,32 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
+use rand_distr::{Distribution, Exp1};
 
 use crate::game::{
     assets::{HandleMap, SfxKey},
+    atmosphere::Atmosphere,
     auto_anim::{AnimRange, AnimSet, AutoAnim, AutoAnimPlugin},
+    daycycle::GameTime,
+    difficult::RES_LIMIT,
+    hazard::{Hazard, TroubleKind},
+    rng::GameRng,
+    selectable::Selectable,
+    spawn::spawn_commands::MetalTrashPile,
     ui::game_over::ResetGame,
 };
 
 pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<InFire>();
+    app.init_resource::<FireSpread>();
     app.add_plugins(AutoAnimPlugin::<FireSet>::default());
 
-    app.add_systems(PreUpdate, (reset_fires, in_fire).chain());
+    app.add_systems(PreUpdate, in_fire);
+    // With the other resets, before a new level is spawned in `Last`
+    app.add_systems(PostUpdate, reset_fires);
+    app.add_systems(Update, spread_fire);
 }
 
-#[derive(Default, Component)]
+#[derive(Default, Component, Reflect)]
+#[reflect(Component, Default)]
 pub struct InFire {
     /// If the fire mesh has been spawned
     pub fire_created: bool,
@@ -22,6 +36,15 @@ pub struct InFire {
 }
 
 const FIRE_TIMER: f32 = 10.0;
+/// Distance a fire can jump to another device
+const FIRE_SPREAD_RADIUS: f32 = 1.5;
+/// Chance per second to set a neighbor on fire, in a room full of oxygen
+const FIRE_SPREAD_CHANCE: f32 = 0.1;
+
+/// Exposure each neighbor of a fire can still take before it ignites, drawn
+/// once per fire and neighbor so the spread doesn't depend on the frame rate
+#[derive(Resource, Default)]
+struct FireSpread(HashMap<(Entity, Entity), f32>);
 
 impl InFire {
     pub fn time_remaining(&self, gametime: f32) -> f32 {
@@ -110,12 +133,70 @@ fn in_fire(
     }
 }
 
+/// Burning devices can set their neighbors on fire, the more oxygen in the room
+/// the more likely
+fn spread_fire(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    atmosphere: Res<Atmosphere>,
+    mut rng: ResMut<GameRng>,
+    mut spread: ResMut<FireSpread>,
+    q_in_fire: Query<(Entity, &GlobalTransform), With<InFire>>,
+    q_neighbors: Query<
+        (Entity, &GlobalTransform),
+        (
+            With<Selectable>,
+            Without<InFire>,
+            Without<Hazard>,
+            Without<MetalTrashPile>,
+        ),
+    >,
+) {
+    let rng = rng.gameplay();
+    let mut ignited = Vec::new();
+
+    spread
+        .0
+        .retain(|(fire, neighbor), _| q_in_fire.contains(*fire) && q_neighbors.contains(*neighbor));
+
+    for (fire, fire_transform) in q_in_fire.iter() {
+        let Some(air) = atmosphere.air_at(fire_transform.translation()) else {
+            continue;
+        };
+        let exposure =
+            FIRE_SPREAD_CHANCE * (air.oxygen / RES_LIMIT).max(0.0) * time.delta_seconds();
+
+        for (neighbor, transform) in q_neighbors.iter() {
+            if ignited.contains(&neighbor)
+                || transform
+                    .translation()
+                    .distance(fire_transform.translation())
+                    > FIRE_SPREAD_RADIUS
+            {
+                continue;
+            }
+            let remaining = spread
+                .0
+                .entry((fire, neighbor))
+                .or_insert_with(|| Exp1.sample(&mut *rng));
+            *remaining -= exposure;
+            if *remaining <= 0.0 {
+                info!("Fire spread to {}", neighbor);
+                ignited.push(neighbor);
+                TroubleKind::Fire.start(&mut commands, neighbor, time.elapsed_seconds());
+            }
+        }
+    }
+}
+
 fn reset_fires(
     mut commands: Commands,
     mut resets: EventReader<ResetGame>,
+    mut spread: ResMut<FireSpread>,
     mut q_in_fire: Query<Entity, With<InFire>>,
 ) {
     for _ in resets.read() {
+        spread.0.clear();
         for fire in q_in_fire.iter_mut() {
             commands.entity(fire).remove::<InFire>();
         }
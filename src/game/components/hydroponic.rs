@@ -3,26 +3,31 @@ use bevy_mod_billboard::BillboardTextBundle;
 
 use crate::game::{
     assets::{HandleMap, SfxKey},
+    atmosphere::AirExchange,
     billboard_state::BillboardContent,
     character::{CharState, CharacterStates, GoToAction},
     daycycle::GameTime,
+    device::{Stock, Stocks, Usable},
     device_state::{DeviceState, DeviceStatePlugin},
     difficult::{
         HYDROPONIC_FOOD_PER_HARVEST, HYDROPONIC_OXYGEN_RATE, HYDROPONIC_TIME_TO_FOOD,
         HYDROPONIC_WATER_MAX, HYDROPONIC_WATER_RATE,
     },
     resources::*,
-    selectable::OnMouseClick,
+    selectable::{OnInteract, OpenContextMenu},
     sequence::{ActionGroup, CharacterAction, NewActionSequence, NewMode, NextAction},
-    spawn::player::Player,
+    ui::device_menu::{spawn_device_menu, DeviceMenu},
 };
 
 use super::flowup_text::*;
 
 pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<Hydroponic>();
     app.add_systems(Update, update_hydroponic);
     app.add_systems(Update, hydroponic_work);
+    app.add_systems(Update, (spawn_plants, update_growth_stage));
     app.observe(on_clicked);
+    app.observe(open_crop_menu);
 
     app.add_plugins(DeviceStatePlugin::<HydroponicState>::default());
 }
@@ -64,14 +69,106 @@ impl DeviceState for HydroponicState {
     }
 }
 
-#[derive(Component)]
+/// Water level, as a share of the tank, the crop needs to grow well
+const CARE_WATER_LEVEL: f32 = 0.5;
+/// Quality of a crop refilled only when its tank runs dry, half of its
+/// growth is spent above [`CARE_WATER_LEVEL`]. It gives the harvest of
+/// [`Crop::harvest`] as is.
+const TYPICAL_QUALITY: f32 = 0.5;
+/// Harvest gained or lost for every point of quality away from
+/// [`TYPICAL_QUALITY`], from 0.6 of the harvest with no care to 1.4 with
+/// the tank always kept up
+const HARVEST_CARE_BONUS: f32 = 0.8;
+/// Harvest of greens, the one the food budget in [`crate::game::difficult`]
+/// is made for. Other crops are balanced against it.
+const GREENS_HARVEST: f32 = HYDROPONIC_FOOD_PER_HARVEST * 1.5;
+const DEAD_PLANT_COLOR: Color = Color::srgb(0.4, 0.3, 0.2);
+
+#[derive(Reflect, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Crop {
+    /// The usual food supply
+    #[default]
+    Greens,
+    /// Slow to grow, but a big harvest for less water
+    Potatoes,
+    /// Grows fast on little water, but barely feeds anyone
+    Algae,
+    /// Needed by the kitchen for the herb stew
+    Herbs,
+}
+
+impl Crop {
+    pub const ALL: [Crop; 4] = [Crop::Greens, Crop::Potatoes, Crop::Algae, Crop::Herbs];
+
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Crop::Greens => "Greens",
+            Crop::Potatoes => "Potatoes (slow, big harvest)",
+            Crop::Algae => "Algae (fast, little water)",
+            Crop::Herbs => "Herbs (for the stew)",
+        }
+    }
+
+    pub const fn growth_time(&self) -> f32 {
+        match self {
+            Crop::Greens => HYDROPONIC_TIME_TO_FOOD,
+            Crop::Potatoes => HYDROPONIC_TIME_TO_FOOD * 1.5,
+            Crop::Algae => HYDROPONIC_TIME_TO_FOOD * 0.5,
+            Crop::Herbs => HYDROPONIC_TIME_TO_FOOD * 0.75,
+        }
+    }
+
+    pub const fn water_rate(&self) -> f32 {
+        match self {
+            Crop::Potatoes => HYDROPONIC_WATER_RATE * 0.8,
+            Crop::Algae => HYDROPONIC_WATER_RATE * 0.4,
+            Crop::Greens | Crop::Herbs => HYDROPONIC_WATER_RATE,
+        }
+    }
+
+    /// The oxygen budget in [`crate::game::difficult`] counts every tray at
+    /// [`HYDROPONIC_OXYGEN_RATE`], so no crop gives more than that
+    pub const fn oxygen_rate(&self) -> f32 {
+        match self {
+            Crop::Potatoes => HYDROPONIC_OXYGEN_RATE * 0.7,
+            Crop::Greens | Crop::Algae | Crop::Herbs => HYDROPONIC_OXYGEN_RATE,
+        }
+    }
+
+    /// What a harvest of [`TYPICAL_QUALITY`] gives.
+    /// Potatoes feed as much per second as greens, they save water for less
+    /// oxygen. Algae feed half as much per second, for 0.4 of the water.
+    pub const fn harvest(&self) -> (Stock, f32) {
+        match self {
+            Crop::Greens => (Stock::Food, GREENS_HARVEST),
+            Crop::Potatoes => (Stock::Food, GREENS_HARVEST * 1.5),
+            Crop::Algae => (Stock::Food, GREENS_HARVEST * 0.25),
+            Crop::Herbs => (Stock::Herbs, 2.0),
+        }
+    }
+
+    const fn color(&self) -> Color {
+        match self {
+            Crop::Greens => Color::srgb(0.3, 0.8, 0.3),
+            Crop::Potatoes => Color::srgb(0.6, 0.5, 0.3),
+            Crop::Algae => Color::srgb(0.1, 0.6, 0.5),
+            Crop::Herbs => Color::srgb(0.5, 0.8, 0.2),
+        }
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component, Default)]
 pub struct Hydroponic {
     pub water: f32,
     pub max_water: f32,
     pub time_to_food: f32,
-    pub food_per_cycle: f32,
-    pub water_consumption_rate: f32,
     pub dead: bool,
+    pub crop: Crop,
+    /// Seconds the crop has grown since it was planted
+    pub grown: f32,
+    /// Seconds of them with enough water
+    pub care: f32,
 }
 
 impl Default for Hydroponic {
@@ -80,21 +177,80 @@ impl Default for Hydroponic {
             water: 10.0,
             max_water: HYDROPONIC_WATER_MAX,
             time_to_food: HYDROPONIC_TIME_TO_FOOD,
-            food_per_cycle: HYDROPONIC_FOOD_PER_HARVEST * 1.5,
-            water_consumption_rate: HYDROPONIC_WATER_RATE,
             dead: false,
+            crop: Crop::default(),
+            grown: 0.0,
+            care: 0.0,
+        }
+    }
+}
+
+impl Hydroponic {
+    /// Starts the crop over
+    pub const fn replant(&mut self) {
+        self.time_to_food = self.crop.growth_time();
+        self.grown = 0.0;
+        self.care = 0.0;
+    }
+
+    /// Share of the growth spent with enough water
+    pub const fn quality(&self) -> f32 {
+        if self.grown > 0.0 {
+            self.care / self.grown
+        } else {
+            1.0
+        }
+    }
+}
+
+const fn quality_label(quality: f32) -> &'static str {
+    if quality < 0.4 {
+        "poor"
+    } else if quality < 0.75 {
+        "fair"
+    } else {
+        "fine"
+    }
+}
+
+/// Visible stage of the crop, shown by the size of its plant
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GrowthStage {
+    Seedling,
+    Growing,
+    Mature,
+}
+
+impl GrowthStage {
+    const fn of(progress: f32) -> Self {
+        if progress < 0.3 {
+            GrowthStage::Seedling
+        } else if progress < 1.0 {
+            GrowthStage::Growing
+        } else {
+            GrowthStage::Mature
+        }
+    }
+
+    const fn scale(&self) -> f32 {
+        match self {
+            GrowthStage::Seedling => 0.3,
+            GrowthStage::Growing => 0.6,
+            GrowthStage::Mature => 1.0,
         }
     }
 }
 
+#[derive(Component)]
+struct Plant;
+
 fn update_hydroponic(
     time: Res<Time>,
-    mut query: Query<(&mut HydroponicState, &mut Hydroponic)>,
+    mut query: Query<(&mut HydroponicState, &mut Hydroponic, &GlobalTransform)>,
     mut bad_water: EventWriter<Generate<BadWater>>,
-    mut oxygen: EventWriter<Generate<Oxygen>>,
-    mut co2: EventWriter<Generate<CarbonDioxide>>,
+    mut air: EventWriter<AirExchange>,
 ) {
-    for (mut state, mut hydroponic) in query.iter_mut() {
+    for (mut state, mut hydroponic, transform) in query.iter_mut() {
         let dt = time.delta_seconds();
 
         if hydroponic.dead {
@@ -105,21 +261,29 @@ fn update_hydroponic(
         if hydroponic.water < 0.0 {
             *state = HydroponicState::Dead;
             hydroponic.dead = true;
-            hydroponic.time_to_food = HYDROPONIC_TIME_TO_FOOD;
+            hydroponic.replant();
             continue;
         }
 
+        let water_rate = hydroponic.crop.water_rate();
         if hydroponic.time_to_food > 0.0 {
             hydroponic.time_to_food -= dt;
-            hydroponic.water -= hydroponic.water_consumption_rate * dt;
-            bad_water.send(Generate::new(hydroponic.water_consumption_rate * 0.5));
-            oxygen.send(Generate::new(HYDROPONIC_OXYGEN_RATE));
-            co2.send(Generate::new(-HYDROPONIC_OXYGEN_RATE));
+            hydroponic.grown += dt;
+            if hydroponic.water >= hydroponic.max_water * CARE_WATER_LEVEL {
+                hydroponic.care += dt;
+            }
+            hydroponic.water -= water_rate * dt;
+            bad_water.send(Generate::new(water_rate * 0.5));
+            let oxygen = hydroponic.crop.oxygen_rate();
+            air.send(AirExchange {
+                position: transform.translation(),
+                oxygen,
+                co2: -oxygen,
+            });
         }
 
         if hydroponic.water < 3.0 {
-            *state =
-                HydroponicState::NeedWater(hydroponic.water / hydroponic.water_consumption_rate);
+            *state = HydroponicState::NeedWater(hydroponic.water / water_rate);
         } else if hydroponic.time_to_food > 0.0 {
             *state = HydroponicState::Growing;
         } else {
@@ -131,32 +295,29 @@ fn update_hydroponic(
 const HYDROPONIC_GROUP: &str = "hydroponic";
 
 fn on_clicked(
-    trigger: Trigger<OnMouseClick>,
+    trigger: Trigger<OnInteract>,
     mut commands: Commands,
-    q_players: Query<Entity, With<Player>>,
-    mut q_hydroponics: Query<(Entity, &GlobalTransform), With<Hydroponic>>,
+    mut q_hydroponics: Query<(Entity, &GlobalTransform), (With<Hydroponic>, Usable)>,
 ) {
     let target = trigger.entity();
 
-    if trigger.event().0 != MouseButton::Left {
-        return;
-    }
-
     if let Ok((_, hydro_transform)) = q_hydroponics.get_mut(target) {
         let mut actions = ActionGroup::new(HYDROPONIC_GROUP.to_string());
 
-        actions.add(GoToAction {
-            target,
-            target_pos: hydro_transform.translation(),
-        });
+        if !trigger.event().direct {
+            actions.add(GoToAction {
+                target,
+                target_pos: hydro_transform.translation(),
+            });
+        }
         actions.add(HydroponicAction { target });
 
         commands.trigger_targets(
             NewActionSequence {
                 actions,
-                mode: NewMode::SoftReplace,
+                mode: trigger.event().mode(NewMode::SoftReplace),
             },
-            q_players.iter().collect::<Vec<_>>(),
+            trigger.event().actor,
         );
 
         info!("Hydroponic working!");
@@ -177,6 +338,10 @@ impl CharacterAction for HydroponicAction {
     fn terminate(&self, commands: &mut Commands, target: Entity) {
         commands.entity(target).remove::<HydroponicWork>();
     }
+
+    fn target(&self) -> Option<Entity> {
+        Some(self.target)
+    }
 }
 
 #[derive(Component)]
@@ -196,16 +361,16 @@ impl HydroponicWork {
 
 fn hydroponic_work(
     mut commands: Commands,
-    mut q_hydroponics: Query<(&mut HydroponicState, &mut Hydroponic, &GlobalTransform)>,
+    mut q_hydroponics: Query<(&mut HydroponicState, &mut Hydroponic, &GlobalTransform), Usable>,
     mut q_players: Query<(Entity, &mut HydroponicWork, &mut CharacterStates)>,
     time: Res<GameTime>,
-    mut water: ResMut<Water>,
-    mut food: ResMut<Food>,
+    mut stocks: Stocks,
     sounds: Res<HandleMap<SfxKey>>,
 ) {
     for (player_entity, mut work, mut states) in q_players.iter_mut() {
         let Ok((state, mut hydrponic, hydroponic_transform)) = q_hydroponics.get_mut(work.target)
         else {
+            // Gone, burning or broken
             commands.entity(player_entity).remove::<HydroponicWork>();
             commands.trigger_targets(NextAction, player_entity);
             continue;
@@ -217,9 +382,9 @@ fn hydroponic_work(
         } else {
             //refill water
             let dw = hydrponic.max_water - hydrponic.water;
-            let free_water = water.amount().min(dw);
+            let free_water = stocks.amount(Stock::Water, player_entity).min(dw);
             hydrponic.water += free_water;
-            water.decrease(free_water);
+            stocks.change(Stock::Water, player_entity, -free_water);
 
             let size = 0.01;
 
@@ -247,8 +412,11 @@ fn hydroponic_work(
                         });
                 }
                 HydroponicState::Growed => {
-                    food.increase(hydrponic.food_per_cycle);
-                    hydrponic.time_to_food = HYDROPONIC_TIME_TO_FOOD;
+                    let (stock, amount) = hydrponic.crop.harvest();
+                    let quality = hydrponic.quality();
+                    let amount = amount * (1.0 + HARVEST_CARE_BONUS * (quality - TYPICAL_QUALITY));
+                    stocks.change(stock, player_entity, amount);
+                    hydrponic.replant();
 
                     commands
                         .spawn(BillboardTextBundle {
@@ -257,7 +425,12 @@ fn hydroponic_work(
                             )
                             .with_scale(Vec3::splat(size)),
                             text: Text::from_section(
-                                format!("    +{} Food", hydrponic.food_per_cycle),
+                                format!(
+                                    "    +{:.1} {} ({})",
+                                    amount,
+                                    stock.label(),
+                                    quality_label(quality)
+                                ),
                                 text_style,
                             ),
                             ..default()
@@ -305,3 +478,98 @@ fn hydroponic_work(
         }
     }
 }
+
+fn open_crop_menu(
+    trigger: Trigger<OpenContextMenu>,
+    mut commands: Commands,
+    q_hydroponics: Query<(&Hydroponic, &GlobalTransform)>,
+    q_cameras: Query<(&Camera, &GlobalTransform), With<IsDefaultUiCamera>>,
+    q_menus: Query<Entity, With<DeviceMenu>>,
+) {
+    let target = trigger.entity();
+    let Ok((current, transform)) = q_hydroponics.get(target) else {
+        return;
+    };
+
+    let entries = Crop::ALL.map(|crop| {
+        // Switching to another crop throws the current one away
+        let choose = move |world: &mut World| {
+            if let Some(mut hydroponic) = world.get_mut::<Hydroponic>(target) {
+                if hydroponic.crop != crop {
+                    hydroponic.crop = crop;
+                    hydroponic.replant();
+                }
+            }
+        };
+        (crop.label().to_string(), crop == current.crop, choose)
+    });
+    spawn_device_menu(
+        &mut commands,
+        &q_cameras,
+        &q_menus,
+        transform.translation(),
+        "Crop",
+        entries,
+    );
+}
+
+fn spawn_plants(
+    mut commands: Commands,
+    q_hydroponics: Query<(Entity, &Hydroponic), Without<GrowthStage>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, hydroponic) in q_hydroponics.iter() {
+        let plant = commands
+            .spawn((
+                Name::new("Plant"),
+                Plant,
+                PbrBundle {
+                    mesh: meshes.add(Sphere::new(0.5)),
+                    material: materials.add(StandardMaterial {
+                        base_color: hydroponic.crop.color(),
+                        ..default()
+                    }),
+                    transform: Transform::from_xyz(0.0, 1.0, 0.0)
+                        .with_scale(Vec3::splat(GrowthStage::Seedling.scale())),
+                    ..default()
+                },
+            ))
+            .id();
+        commands
+            .entity(entity)
+            .insert(GrowthStage::Seedling)
+            .add_child(plant);
+    }
+}
+
+fn update_growth_stage(
+    mut q_hydroponics: Query<(&Hydroponic, &mut GrowthStage, &Children)>,
+    mut q_plants: Query<(&mut Transform, &Handle<StandardMaterial>), With<Plant>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (hydroponic, mut stage, children) in q_hydroponics.iter_mut() {
+        let progress = 1.0 - hydroponic.time_to_food / hydroponic.crop.growth_time();
+        stage.set_if_neq(GrowthStage::of(progress));
+
+        let color = if hydroponic.dead {
+            DEAD_PLANT_COLOR
+        } else {
+            hydroponic.crop.color()
+        };
+        for child in children.iter() {
+            let Ok((mut transform, material)) = q_plants.get_mut(*child) else {
+                continue;
+            };
+            transform.scale = Vec3::splat(stage.scale());
+            if materials
+                .get(material)
+                .is_some_and(|m| m.base_color != color)
+            {
+                if let Some(m) = materials.get_mut(material) {
+                    m.base_color = color;
+                }
+            }
+        }
+    }
+}
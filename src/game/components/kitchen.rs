@@ -1,54 +1,187 @@
+//! Kitchens cook the [`Recipe`] picked in their context menu.
+
 use bevy::prelude::*;
 
 use crate::game::{
-    assets::{HandleMap, SfxKey},
-    character::GoToAction,
-    kitchen_work::KitchenWorkAction,
-    selectable::OnMouseClick,
-    sequence::{ActionGroup, NewActionSequence, NewMode},
-    spawn::player::Player,
+    assets::SfxKey,
+    character::CharState,
+    device::{Device, DeviceDefinition, DevicePlugin, Stock},
+    difficult::{RACION_SIZE, RES_LIMIT},
+    selectable::OpenContextMenu,
+    sequence::NewMode,
+    ui::device_menu::{spawn_device_menu, DeviceMenu},
 };
 
-#[derive(Component)]
-pub struct Kitchen;
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct Kitchen {
+    /// Meal cooked when a crew member uses the kitchen
+    pub recipe: Recipe,
+}
 
 pub(super) fn plugin(app: &mut App) {
-    app.observe(on_selected);
+    app.register_type::<Kitchen>();
+    app.add_plugins(DevicePlugin::<Kitchen>::default());
+    app.observe(open_recipe_menu);
 }
 
-const KITCHEN_WORK_GROUP: &str = "kitchen_work_group";
+/// Water used by a bowl of soup
+const SOUP_WATER: f32 = 10.0;
+const SOUP_TIME: f32 = 1.5;
+/// Water used by a cup of tea
+const TEA_WATER: f32 = 5.0;
+const TEA_TIME: f32 = 0.5;
+/// Herbs used by a pot of stew
+const STEW_HERBS: f32 = 1.0;
 
-fn on_selected(
-    trigger: Trigger<OnMouseClick>,
-    mut commands: Commands,
-    q_players: Query<Entity, With<Player>>,
-    mut q_kitchen: Query<&GlobalTransform, With<Kitchen>>,
-    sounds: Res<HandleMap<SfxKey>>,
-) {
-    let target = trigger.entity();
+#[derive(Reflect, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Recipe {
+    /// A quick ration, fills the stomach
+    #[default]
+    Ration,
+    /// Less filling than a ration, but quenches the thirst too
+    Soup,
+    /// Only quenches the thirst, no food needed
+    Tea,
+    /// A ration spiced with herbs from the hydroponics, the most filling meal
+    Stew,
+}
 
-    if trigger.event().0 != MouseButton::Left {
-        return;
+impl Recipe {
+    pub const ALL: [Recipe; 4] = [Recipe::Ration, Recipe::Soup, Recipe::Tea, Recipe::Stew];
+
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Recipe::Ration => "Ration (1 food)",
+            Recipe::Soup => "Soup (1 food, 10 water)",
+            Recipe::Tea => "Tea (5 water)",
+            Recipe::Stew => "Herb stew (1 food, 1 herbs)",
+        }
     }
 
-    if let Ok(kitchen_transform) = q_kitchen.get_mut(target) {
-        let mut actions = ActionGroup::new(KITCHEN_WORK_GROUP.to_string());
+    pub const fn definition(&self) -> &'static DeviceDefinition {
+        match self {
+            Recipe::Ration => &RATION,
+            Recipe::Soup => &SOUP,
+            Recipe::Tea => &TEA,
+            Recipe::Stew => &STEW,
+        }
+    }
+}
+
+const RATION: DeviceDefinition = DeviceDefinition {
+    group: "kitchen_work_group",
+    duration: 0.25,
+    state: CharState::Working,
+    mode: NewMode::SoftReplace,
+    sfx: SfxKey::Cooking,
+    volume: 3.0,
+    rates: &[],
+    until_empty: &[],
+    requires: &[(Stock::Food, RACION_SIZE)],
+    outputs: &[
+        (Stock::Food, -RACION_SIZE),
+        (Stock::Hungry, -RES_LIMIT),
+        (Stock::Pee, RACION_SIZE / 2.0),
+    ],
+    popup: Some(("Eating", SfxKey::Eating)),
+    wear: 0.0,
+};
 
-        actions.add(GoToAction {
-            target,
-            target_pos: kitchen_transform.translation(),
-        });
+const SOUP: DeviceDefinition = DeviceDefinition {
+    group: "kitchen_work_group",
+    duration: SOUP_TIME,
+    state: CharState::Working,
+    mode: NewMode::SoftReplace,
+    sfx: SfxKey::Cooking,
+    volume: 3.0,
+    rates: &[],
+    until_empty: &[],
+    // Taken once cooked, so a short tank doesn't give a free meal
+    requires: &[(Stock::Food, RACION_SIZE), (Stock::Water, SOUP_WATER)],
+    outputs: &[
+        (Stock::Food, -RACION_SIZE),
+        (Stock::Water, -SOUP_WATER),
+        (Stock::Hungry, -RES_LIMIT * 0.6),
+        (Stock::Thirst, -RES_LIMIT * 0.5),
+        (Stock::Pee, SOUP_WATER / 2.0),
+    ],
+    popup: Some(("Eating soup", SfxKey::Eating)),
+    wear: 0.0,
+};
 
-        actions.add(KitchenWorkAction(sounds[&SfxKey::Cooking].clone_weak()));
+const TEA: DeviceDefinition = DeviceDefinition {
+    group: "kitchen_work_group",
+    duration: TEA_TIME,
+    state: CharState::Drinking,
+    mode: NewMode::SoftReplace,
+    sfx: SfxKey::Cooking,
+    volume: 3.0,
+    rates: &[],
+    until_empty: &[],
+    requires: &[(Stock::Water, TEA_WATER)],
+    outputs: &[
+        (Stock::Water, -TEA_WATER),
+        (Stock::Thirst, -RES_LIMIT * 0.4),
+        (Stock::Pee, TEA_WATER),
+    ],
+    popup: Some(("Drinking tea", SfxKey::Water)),
+    wear: 0.0,
+};
 
-        commands.trigger_targets(
-            NewActionSequence {
-                actions,
-                mode: NewMode::SoftReplace,
-            },
-            q_players.iter().collect::<Vec<_>>(),
-        );
+const STEW: DeviceDefinition = DeviceDefinition {
+    group: "kitchen_work_group",
+    duration: 1.0,
+    state: CharState::Working,
+    mode: NewMode::SoftReplace,
+    sfx: SfxKey::Cooking,
+    volume: 3.0,
+    rates: &[],
+    until_empty: &[],
+    requires: &[(Stock::Food, RACION_SIZE), (Stock::Herbs, STEW_HERBS)],
+    outputs: &[
+        (Stock::Food, -RACION_SIZE),
+        (Stock::Herbs, -STEW_HERBS),
+        (Stock::Hungry, -RES_LIMIT),
+        (Stock::Thirst, -RES_LIMIT * 0.3),
+        (Stock::Pee, RACION_SIZE / 2.0),
+    ],
+    popup: Some(("Eating stew", SfxKey::Eating)),
+    wear: 0.0,
+};
 
-        info!("Cooking Food!");
+impl Device for Kitchen {
+    fn definition(&self) -> &'static DeviceDefinition {
+        self.recipe.definition()
     }
 }
+
+fn open_recipe_menu(
+    trigger: Trigger<OpenContextMenu>,
+    mut commands: Commands,
+    q_kitchens: Query<(&Kitchen, &GlobalTransform)>,
+    q_cameras: Query<(&Camera, &GlobalTransform), With<IsDefaultUiCamera>>,
+    q_menus: Query<Entity, With<DeviceMenu>>,
+) {
+    let kitchen = trigger.entity();
+    let Ok((current, transform)) = q_kitchens.get(kitchen) else {
+        return;
+    };
+
+    let entries = Recipe::ALL.map(|recipe| {
+        let choose = move |world: &mut World| {
+            if let Some(mut settings) = world.get_mut::<Kitchen>(kitchen) {
+                settings.recipe = recipe;
+            }
+        };
+        (recipe.label().to_string(), recipe == current.recipe, choose)
+    });
+    spawn_device_menu(
+        &mut commands,
+        &q_cameras,
+        &q_menus,
+        transform.translation(),
+        "Recipe",
+        entries,
+    );
+}
@@ -3,44 +3,42 @@ use bevy::prelude::*;
 use crate::game::{
     character::GoToAction,
     metal_trash::GatherMetalWorkAction,
-    selectable::OnMouseClick,
+    selectable::OnInteract,
     sequence::{ActionGroup, NewActionSequence, NewMode},
-    spawn::{player::Player, spawn_commands::MetalTrashPile},
+    spawn::spawn_commands::MetalTrashPile,
 };
 
 pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<MetalTrashPile>();
     app.observe(on_selected);
 }
 
 const METAL_TRASH_WORK_GROUP: &str = "gathering_metal_trash_work";
 
 fn on_selected(
-    trigger: Trigger<OnMouseClick>,
+    trigger: Trigger<OnInteract>,
     mut commands: Commands,
-    q_players: Query<Entity, With<Player>>,
     mut q_metal_trash_piles: Query<&GlobalTransform, With<MetalTrashPile>>,
 ) {
     let target = trigger.entity();
 
-    if trigger.event().0 != MouseButton::Left {
-        return;
-    }
-
     if let Ok(metal_trash_pile_transform) = q_metal_trash_piles.get_mut(target) {
         let mut actions = ActionGroup::new(METAL_TRASH_WORK_GROUP.to_string());
 
-        actions.add(GoToAction {
-            target,
-            target_pos: metal_trash_pile_transform.translation(),
-        });
-        actions.add(GatherMetalWorkAction);
+        if !trigger.event().direct {
+            actions.add(GoToAction {
+                target,
+                target_pos: metal_trash_pile_transform.translation(),
+            });
+        }
+        actions.add(GatherMetalWorkAction { pile: target });
 
         commands.trigger_targets(
             NewActionSequence {
                 actions,
-                mode: NewMode::SoftReplace,
+                mode: trigger.event().mode(NewMode::SoftReplace),
             },
-            q_players.iter().collect::<Vec<_>>(),
+            trigger.event().actor,
         );
 
         info!("Gathering Metal Trash!");
@@ -1,3 +1,4 @@
+pub mod device_kind;
 pub mod earth;
 pub mod fire;
 pub mod flowup_text;
@@ -25,5 +26,5 @@ pub(crate) fn plugin(app: &mut bevy::prelude::App) {
         water_cleaner::plugin,
     ));
 
-    app.add_plugins((hydroponic::plugin,));
+    app.add_plugins((hydroponic::plugin, device_kind::plugin));
 }
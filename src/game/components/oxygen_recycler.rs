@@ -1,29 +1,29 @@
-use bevy::{
-    audio::{PlaybackMode, Volume},
-    prelude::*,
-};
+use bevy::prelude::*;
 
 use crate::game::{
-    assets::{HandleMap, SfxKey},
+    assets::SfxKey,
     billboard_state::BillboardContent,
-    character::{CharState, CharacterStates, GoToAction},
+    character::CharState,
     daycycle::GameTime,
+    device::{Device, DeviceDefinition, DevicePlugin, DeviceWorkDone},
     device_state::{DeviceState, DeviceStatePlugin},
     resources::OxygenRecycling,
-    selectable::OnMouseClick,
-    sequence::{ActionGroup, CharacterAction, NewActionSequence, NewMode, NextAction},
-    spawn::{player::Player, spawn_commands::OxygenRecyler},
+    sequence::NewMode,
+    spawn::spawn_commands::OxygenRecyler,
 };
 
 use super::fire::InFire;
 
 pub(super) fn plugin(app: &mut App) {
-    app.observe(on_selected);
-    app.add_systems(Update, update_oxygen_recycler_work);
+    app.register_type::<OxygenRecyler>();
+    app.observe(toggle_recycling);
     app.add_systems(Update, update_oxygen_recycler_state);
     app.add_systems(PostUpdate, disable_oxygen_if_no_recycler);
 
-    app.add_plugins(DeviceStatePlugin::<OxygenRegeneratorState>::default());
+    app.add_plugins((
+        DevicePlugin::<OxygenRecyler>::default(),
+        DeviceStatePlugin::<OxygenRegeneratorState>::default(),
+    ));
 }
 
 #[derive(Component, PartialEq, Clone)]
@@ -49,86 +49,35 @@ impl DeviceState for OxygenRegeneratorState {
     }
 }
 
-const OXYGEN_RECYCLER_WORK_GROUP: &str = "oxygen_recycler";
-
-fn on_selected(
-    trigger: Trigger<OnMouseClick>,
-    mut commands: Commands,
-    q_players: Query<Entity, With<Player>>,
-    mut q_oxygen_recyclers: Query<&GlobalTransform, With<OxygenRecyler>>,
-    sounds: Res<HandleMap<SfxKey>>,
-) {
-    let target = trigger.entity();
-
-    if trigger.event().0 != MouseButton::Left {
-        return;
-    }
-
-    if let Ok(or_transform) = q_oxygen_recyclers.get_mut(target) {
-        let mut actions = ActionGroup::new(OXYGEN_RECYCLER_WORK_GROUP.to_string());
-        actions.add(GoToAction {
-            target,
-            target_pos: or_transform.translation(),
-        });
-        actions.add(OxygenRecyclerAction(
-            sounds[&SfxKey::StartMachine].clone_weak(),
-        ));
-
-        commands.trigger_targets(
-            NewActionSequence {
-                actions,
-                mode: NewMode::Replace,
-            },
-            q_players.iter().collect::<Vec<_>>(),
-        );
-
-        info!("Oxygen Recycling!");
-    }
-}
-
-pub struct OxygenRecyclerAction(pub Handle<AudioSource>);
-
-#[derive(Component, Default)]
-pub struct OxygenRecyclerWork {
-    pub work_time: f32,
-}
-
-impl CharacterAction for OxygenRecyclerAction {
-    fn trigger_start(&self, commands: &mut Commands, target: Entity) {
-        info!("trigger start or");
-        commands
-            .entity(target)
-            .insert(OxygenRecyclerWork::default())
-            .insert(AudioBundle {
-                source: self.0.clone_weak(),
-                settings: PlaybackSettings {
-                    mode: PlaybackMode::Remove,
-                    volume: Volume::new(2.0),
-                    ..Default::default()
-                },
-            });
-    }
+/// Switches the oxygen recycling on or off
+const OXYGEN_RECYCLER_WORK: DeviceDefinition = DeviceDefinition {
+    group: "oxygen_recycler",
+    duration: 0.25,
+    state: CharState::Working,
+    mode: NewMode::Replace,
+    sfx: SfxKey::StartMachine,
+    volume: 2.0,
+    rates: &[],
+    until_empty: &[],
+    requires: &[],
+    outputs: &[],
+    popup: None,
+    wear: 0.0,
+};
 
-    fn terminate(&self, commands: &mut Commands, target: Entity) {
-        commands.entity(target).remove::<OxygenRecyclerWork>();
+impl Device for OxygenRecyler {
+    fn definition(&self) -> &'static DeviceDefinition {
+        &OXYGEN_RECYCLER_WORK
     }
 }
 
-fn update_oxygen_recycler_work(
-    time: Res<GameTime>,
-    mut commands: Commands,
-    mut q_oxygen_recycler_work: Query<(Entity, &mut OxygenRecyclerWork, &mut CharacterStates)>,
-    // work_config: Res<PcWorkConfig>,
+fn toggle_recycling(
+    trigger: Trigger<DeviceWorkDone>,
+    q_oxygen_recyclers: Query<(), With<OxygenRecyler>>,
     mut oxygen_recycling: ResMut<OxygenRecycling>,
 ) {
-    for (entity, mut or_work, mut states) in q_oxygen_recycler_work.iter_mut() {
-        or_work.work_time += time.delta_seconds();
-        states.add(CharState::Working);
-        if or_work.work_time >= 0.25 {
-            oxygen_recycling.working = !oxygen_recycling.working;
-            commands.entity(entity).remove::<OxygenRecyclerWork>();
-            commands.trigger_targets(NextAction, entity);
-        }
+    if q_oxygen_recyclers.contains(trigger.entity()) {
+        oxygen_recycling.working = !oxygen_recycling.working;
     }
 }
 
@@ -4,26 +4,60 @@ use bevy::prelude::*;
 
 use crate::game::{
     assets::{HandleMap, SfxKey},
-    character::{GoToAction, IgnoreJustMoving},
-    pc_work::PcWorkAction,
-    selectable::OnMouseClick,
-    sequence::{ActionGroup, NewActionSequence, NewMode},
-    spawn::{
-        player::Player,
-        spawn_commands::{MetalTrashPile, OxygenRecyler},
-    },
+    character::{CharState, IgnoreJustMoving},
+    daycycle::GameTime,
+    debt::Debt,
+    device::{spawn_popup, Device, DeviceDefinition, DevicePlugin, DeviceWorkDone},
+    durability::PC_WEAR,
+    sequence::NewMode,
+    spawn::spawn_commands::{MetalTrashPile, OxygenRecyler},
 };
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct Pc;
 
 pub(crate) fn plugin(app: &mut App) {
-    app.observe(on_selected);
+    app.register_type::<Pc>();
+    app.insert_resource(PcWorkConfig {
+        amount_after_work: 10.0,
+        multiplier: 1,
+        last_updated: 0.0,
+    });
+    app.add_plugins(DevicePlugin::<Pc>::default());
+    app.observe(pay_debt);
 
     app.add_systems(Update, auto_add_complex_moving);
 }
 
-const PC_WORK_GROUP: &str = "pc_work";
+/// Pays some debt, more when the work is done in quick succession
+const PC_WORK: DeviceDefinition = DeviceDefinition {
+    group: "pc_work",
+    duration: 0.25,
+    state: CharState::Working,
+    mode: NewMode::SoftReplace,
+    sfx: SfxKey::Typing,
+    volume: 4.0,
+    rates: &[],
+    until_empty: &[],
+    requires: &[],
+    outputs: &[],
+    popup: None,
+    wear: PC_WEAR,
+};
+
+impl Device for Pc {
+    fn definition(&self) -> &'static DeviceDefinition {
+        &PC_WORK
+    }
+}
+
+#[derive(Resource)]
+pub struct PcWorkConfig {
+    pub amount_after_work: f32,
+    pub multiplier: i32,
+    pub last_updated: f32,
+}
 
 fn auto_add_complex_moving(
     mut commands: Commands,
@@ -34,36 +68,41 @@ fn auto_add_complex_moving(
     }
 }
 
-fn on_selected(
-    trigger: Trigger<OnMouseClick>,
+fn pay_debt(
+    trigger: Trigger<DeviceWorkDone>,
     mut commands: Commands,
-    q_players: Query<Entity, With<Player>>,
-    mut q_pcs: Query<&GlobalTransform, With<Pc>>,
+    time: Res<GameTime>,
+    mut work_config: ResMut<PcWorkConfig>,
+    mut debt: ResMut<Debt>,
+    q_pcs: Query<&GlobalTransform, With<Pc>>,
     sounds: Res<HandleMap<SfxKey>>,
 ) {
-    let target: Entity = trigger.entity();
-
-    if trigger.event().0 != MouseButton::Left {
+    let Ok(pc_transform) = q_pcs.get(trigger.entity()) else {
         return;
-    }
+    };
 
-    if let Ok(pc_transform) = q_pcs.get_mut(target) {
-        let mut actions = ActionGroup::new(PC_WORK_GROUP.to_string());
-
-        actions.add(GoToAction {
-            target,
-            target_pos: pc_transform.translation(),
-        });
-        actions.add(PcWorkAction(sounds[&SfxKey::Typing].clone_weak()));
+    let current_time = time.elapsed_seconds();
+    if current_time - work_config.last_updated < 1.0 {
+        work_config.multiplier += 1;
+    } else {
+        work_config.multiplier = 1;
+    }
+    work_config.last_updated = current_time;
 
-        commands.trigger_targets(
-            NewActionSequence {
-                actions,
-                mode: NewMode::SoftReplace,
-            },
-            q_players.iter().collect::<Vec<_>>(),
-        );
+    // Worn PCs are slower, so less work gets done
+    let efficiency = trigger.event().efficiency;
+    let dept_decrease = work_config.amount_after_work * work_config.multiplier as f32 * efficiency;
+    debt.amount -= dept_decrease;
+    info!("Debt decreased by {}", dept_decrease);
 
-        info!("PC working!");
+    let mut show_debt = 0.0;
+    for m in 1..=work_config.multiplier {
+        show_debt += m as f32 * work_config.amount_after_work * efficiency;
     }
+    spawn_popup(
+        &mut commands,
+        pc_transform.translation(),
+        format!("+{:.0}$", show_debt),
+        sounds[&SfxKey::Coin].clone_weak(),
+    );
 }
@@ -0,0 +1,145 @@
+//! Control schemes for the selected crew member.
+//! Click-to-move sends it to whatever device gets clicked, direct control
+//! walks it with WASD and uses the nearest device with the interact key,
+//! hybrid allows both at the same time.
+
+use bevy::prelude::*;
+use node_tree::{styling::Styling, tree::NodeTree, InsertNodumEntity};
+
+use crate::screen::Screen;
+
+use super::{
+    crew::SelectedCrew,
+    focus::Focus,
+    input::{ActionState, InputAction},
+    selectable::{RequestInteract, Selectable},
+    spawn::player::Player,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<ControlScheme>();
+    app.add_systems(Startup, |mut cmds: Commands| {
+        cmds.spawn(ControlSchemeMarker);
+    });
+    app.add_systems(
+        Update,
+        (cycle_control_scheme, interact_with_nearest).run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(Update, show_control_scheme);
+}
+
+/// How far (in world units) a device can be from the crew member to be used directly
+pub const INTERACT_RANGE: f32 = 1.5;
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ControlScheme {
+    /// Click on a device to send the selected crew member there
+    #[default]
+    ClickToMove,
+    /// Walk with WASD or the left stick and interact next to a device
+    Direct,
+    /// Both at the same time
+    Hybrid,
+}
+
+impl ControlScheme {
+    pub const fn allows_click(&self) -> bool {
+        matches!(self, ControlScheme::ClickToMove | ControlScheme::Hybrid)
+    }
+
+    pub const fn allows_direct(&self) -> bool {
+        matches!(self, ControlScheme::Direct | ControlScheme::Hybrid)
+    }
+
+    const fn next(&self) -> Self {
+        match self {
+            ControlScheme::ClickToMove => ControlScheme::Direct,
+            ControlScheme::Direct => ControlScheme::Hybrid,
+            ControlScheme::Hybrid => ControlScheme::ClickToMove,
+        }
+    }
+
+    const fn label(&self) -> &'static str {
+        match self {
+            ControlScheme::ClickToMove => "Click to move",
+            ControlScheme::Direct => "WASD + E",
+            ControlScheme::Hybrid => "Click + WASD + E",
+        }
+    }
+}
+
+fn cycle_control_scheme(actions: Res<ActionState>, mut scheme: ResMut<ControlScheme>) {
+    if actions.just_pressed(InputAction::CycleControlScheme) {
+        *scheme = scheme.next();
+        info!("Control scheme: {:?}", *scheme);
+    }
+}
+
+/// Uses the nearest [`Selectable`] in reach of every selected crew member,
+/// without walking there first
+fn interact_with_nearest(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    scheme: Res<ControlScheme>,
+    focus: Res<Focus>,
+    q_crew: Query<(Entity, &GlobalTransform), (With<Player>, With<SelectedCrew>)>,
+    q_selectables: Query<(Entity, &GlobalTransform), (With<Selectable>, Without<Player>)>,
+) {
+    if !scheme.allows_direct() || !actions.just_pressed(InputAction::Interact) {
+        return;
+    }
+    // A focused device takes precedence, see `focus`
+    if focus.entity.is_some() && scheme.allows_click() {
+        return;
+    }
+
+    for (actor, crew_transform) in q_crew.iter() {
+        let position = crew_transform.translation().xz();
+
+        let nearest = q_selectables
+            .iter()
+            .map(|(entity, transform)| (entity, transform.translation().xz().distance(position)))
+            .filter(|(_, distance)| *distance <= INTERACT_RANGE)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((target, _)) = nearest {
+            commands.trigger_targets(
+                RequestInteract {
+                    actor,
+                    direct: true,
+                    append: false,
+                },
+                target,
+            );
+        }
+    }
+}
+
+#[derive(Component)]
+struct ControlSchemeMarker;
+
+fn show_control_scheme(
+    mut commands: Commands,
+    scheme: Res<ControlScheme>,
+    q_show: Query<Entity, With<ControlSchemeMarker>>,
+) {
+    if !scheme.is_changed() {
+        return;
+    }
+
+    let tree = NodeTree::default()
+        .with_bundle(TextBundle::from_section(
+            format!("Controls: {} (C)", scheme.label()),
+            TextStyle::default(),
+        ))
+        .with_top(Val::Px(50.0))
+        .with_right(Val::Px(10.0))
+        .with_position_type(PositionType::Absolute);
+
+    if let Ok(entity) = q_show.get_single() {
+        commands.add(InsertNodumEntity {
+            entity,
+            nodum: tree,
+        });
+    }
+}
@@ -0,0 +1,115 @@
+//! Crew selection.
+//! Clicking a crew member selects it, and clicking a device tells every
+//! selected crew member to use it through [`RequestInteract`].
+
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+
+use super::{
+    build::BuildMode,
+    controls::ControlScheme,
+    input::{ActionState, InputAction},
+    selectable::{OnMouseClick, RequestInteract},
+    spawn::player::{Player, PLAYER_SCALE},
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.observe(interact_with_selected_crew);
+    app.add_systems(Update, (make_crew_pickable, update_selection_rings));
+}
+
+/// Crew member that receives the orders given by clicking devices
+#[derive(Component)]
+pub struct SelectedCrew;
+
+#[derive(Component)]
+struct SelectionRing;
+
+fn interact_with_selected_crew(
+    trigger: Trigger<OnMouseClick>,
+    mut commands: Commands,
+    scheme: Res<ControlScheme>,
+    build_mode: Res<BuildMode>,
+    actions: Res<ActionState>,
+    q_selected: Query<Entity, (With<Player>, With<SelectedCrew>)>,
+) {
+    // In build mode clicks pick devices to move or demolish
+    if trigger.event().0 != MouseButton::Left || !scheme.allows_click() || build_mode.active {
+        return;
+    }
+
+    for actor in q_selected.iter() {
+        commands.trigger_targets(
+            RequestInteract {
+                actor,
+                direct: false,
+                append: actions.pressed(InputAction::QueueAppend),
+            },
+            trigger.entity(),
+        );
+    }
+}
+
+fn make_crew_pickable(mut commands: Commands, q_crew: Query<Entity, Added<Player>>) {
+    for entity in q_crew.iter() {
+        commands
+            .entity(entity)
+            .insert(PickableBundle::default())
+            .insert(On::<Pointer<Click>>::run(
+                |mut event: ListenerMut<Pointer<Click>>,
+                 mut commands: Commands,
+                 q_selected: Query<Entity, With<SelectedCrew>>| {
+                    if event.button != PointerButton::Primary {
+                        return;
+                    }
+
+                    for entity in q_selected.iter() {
+                        commands.entity(entity).remove::<SelectedCrew>();
+                    }
+                    commands.entity(event.listener()).insert(SelectedCrew);
+                    info!("Crew selected {}", event.listener());
+
+                    event.stop_propagation();
+                },
+            ));
+    }
+}
+
+fn update_selection_rings(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    q_new: Query<Entity, Added<SelectedCrew>>,
+    mut removed: RemovedComponents<SelectedCrew>,
+    q_rings: Query<(Entity, &Parent), With<SelectionRing>>,
+) {
+    for entity in removed.read() {
+        for (ring, parent) in q_rings.iter() {
+            if parent.get() == entity {
+                commands.entity(ring).despawn_recursive();
+            }
+        }
+    }
+
+    for entity in q_new.iter() {
+        let ring = commands
+            .spawn((
+                SelectionRing,
+                PbrBundle {
+                    mesh: meshes.add(Torus::new(0.4, 0.5)),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::linear_rgb(1.0, 1.0, 0.0),
+                        unlit: true,
+                        ..default()
+                    }),
+                    // Undo the scale of the crew model, so the ring has a world size
+                    transform: Transform::from_translation(Vec3::Y * 0.05)
+                        .with_scale(Vec3::splat(1.0 / PLAYER_SCALE)),
+                    ..default()
+                },
+                Pickable::IGNORE,
+            ))
+            .id();
+        commands.entity(entity).add_child(ring);
+    }
+}
@@ -3,13 +3,13 @@
 use std::time::Duration;
 
 use bevy::{
-    input::{keyboard::KeyboardInput, ButtonState},
     prelude::*,
     transform::commands,
 };
 use node_tree::{styling::Styling, tree::NodeTree, InsertNodumEntity};
 
 use super::{
+    input::{ActionState, InputAction},
     ui::game_over::ResetGame,
     {resources::GameResource, ui::components::hex2color},
 };
@@ -25,6 +25,7 @@ pub(crate) fn plugin(app: &mut App) {
     app.insert_resource(TimeSpeed::Pause);
     app.insert_resource(DayState::Day);
     app.insert_resource(GameTime::default());
+    app.register_type::<NightLight>();
 
     app.init_state::<PlayerState>();
     app.add_event::<NightStart>();
@@ -48,7 +49,8 @@ pub(crate) fn plugin(app: &mut App) {
 
 
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct NightLight;
 
 const SPOTLIGHT_COLOR: &str = "#a85032";
@@ -77,7 +79,7 @@ fn night_light(
     }
 }
 
-#[derive(Resource, Debug, PartialEq, Eq)]
+#[derive(Resource, Debug, PartialEq, Eq, Clone, Copy)]
 pub enum TimeSpeed {
     Pause,
     Normal,
@@ -101,6 +103,12 @@ pub struct DayDuration(pub f32);
 #[derive(Resource)]
 pub struct DayPassed(u32);
 
+impl DayPassed {
+    pub const fn days(&self) -> u32 {
+        self.0
+    }
+}
+
 #[derive(Resource, Reflect, PartialEq, Eq)]
 pub enum DayState {
     Night,
@@ -221,33 +229,34 @@ fn update_time(mut gametime: ResMut<GameTime>, real_time: Res<Time>) {
 }
 
 fn change_time_speed(
-    mut keyboard_input: EventReader<KeyboardInput>,
+    actions: Res<ActionState>,
     mut time_speed: ResMut<TimeSpeed>,
+    mut before_pause: Local<Option<TimeSpeed>>,
 ) {
-    for key in keyboard_input.read() {
-        if key.state != ButtonState::Pressed {
-            continue;
+    if actions.just_pressed(InputAction::Pause) {
+        if *time_speed == TimeSpeed::Pause {
+            *time_speed = before_pause.take().unwrap_or(TimeSpeed::Normal);
+        } else {
+            *before_pause = Some(*time_speed);
+            *time_speed = TimeSpeed::Pause;
         }
-        if key.key_code == KeyCode::BracketRight {
-            *time_speed = match *time_speed {
-                TimeSpeed::Pause => TimeSpeed::Normal,
-                TimeSpeed::Normal => TimeSpeed::Fast,
-                TimeSpeed::Fast => TimeSpeed::Fast2,
-                TimeSpeed::Fast2 => TimeSpeed::Fast3,
-                TimeSpeed::Fast3 => TimeSpeed::Pause,
-            }
-        } else if key.key_code == KeyCode::BracketLeft {
-            *time_speed = match *time_speed {
-                TimeSpeed::Pause => TimeSpeed::Fast3,
-                TimeSpeed::Normal => TimeSpeed::Pause,
-                TimeSpeed::Fast => TimeSpeed::Normal,
-                TimeSpeed::Fast2 => TimeSpeed::Fast,
-                TimeSpeed::Fast3 => TimeSpeed::Fast2,
-            }
+    } else if actions.just_pressed(InputAction::TimeFaster) {
+        *time_speed = match *time_speed {
+            TimeSpeed::Pause => TimeSpeed::Normal,
+            TimeSpeed::Normal => TimeSpeed::Fast,
+            TimeSpeed::Fast => TimeSpeed::Fast2,
+            TimeSpeed::Fast2 => TimeSpeed::Fast3,
+            TimeSpeed::Fast3 => TimeSpeed::Pause,
+        }
+    } else if actions.just_pressed(InputAction::TimeSlower) {
+        *time_speed = match *time_speed {
+            TimeSpeed::Pause => TimeSpeed::Fast3,
+            TimeSpeed::Normal => TimeSpeed::Pause,
+            TimeSpeed::Fast => TimeSpeed::Normal,
+            TimeSpeed::Fast2 => TimeSpeed::Fast,
+            TimeSpeed::Fast3 => TimeSpeed::Fast2,
         }
     }
-
-    keyboard_input.clear();
 }
 
 #[derive(Default)]
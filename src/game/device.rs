@@ -0,0 +1,386 @@
+//! Devices the crew works on.
+//! A device marker implements [`Device`] to describe its work as a
+//! [`DeviceDefinition`]: how long it takes, what it takes from and gives to
+//! the ship and the crew member, and the feedback once it is done. The
+//! [`DevicePlugin`] of the marker sends the crew member over when the device
+//! is clicked, and the work itself is the same for every device. Anything
+//! more specific listens to [`DeviceWorkDone`] on the device.
+
+use std::marker::PhantomData;
+
+use bevy::{
+    audio::{PlaybackMode, Volume},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use bevy_mod_billboard::BillboardTextBundle;
+
+use crate::screen::Screen;
+
+use super::{
+    assets::{HandleMap, SfxKey},
+    character::{CharState, CharacterStates, GoToAction},
+    components::{fire::InFire, flowup_text::FlowUpText},
+    daycycle::GameTime,
+    durability::{efficiency_of, Broken, Durability},
+    hazard::Hazard,
+    needs::Need,
+    resources::{
+        BadWater, Food, GameResource, Generate, Herbs, Hungry, Pee, Thirst, Toilet, Water,
+    },
+    selectable::OnInteract,
+    sequence::{ActionGroup, CharacterAction, NewActionSequence, NewMode, NextAction},
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(Update, update_device_work.run_if(in_state(Screen::Playing)));
+}
+
+/// Sends the crew to work on the devices with the marker `T`
+pub struct DevicePlugin<T: Device> {
+    _type: PhantomData<T>,
+}
+
+impl<T: Device> Default for DevicePlugin<T> {
+    fn default() -> Self {
+        Self { _type: PhantomData }
+    }
+}
+
+impl<T: Device> Plugin for DevicePlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.observe(start_work::<T>);
+    }
+}
+
+/// Marker of a device the crew can work on
+pub trait Device: Component {
+    /// Work done on this device, which may depend on its settings
+    fn definition(&self) -> &'static DeviceDefinition;
+}
+
+/// How a crew member works on a device
+pub struct DeviceDefinition {
+    /// Action group of the work, see [`ActionGroup`]
+    pub group: &'static str,
+    /// Game seconds the work takes
+    pub duration: f32,
+    /// State of the crew member while working
+    pub state: CharState,
+    /// How the work is added to what the crew member is doing
+    pub mode: NewMode,
+    /// Played while working
+    pub sfx: SfxKey,
+    pub volume: f32,
+    /// Stocks changed for every second of work, less on a worn device
+    pub rates: &'static [(Stock, f32)],
+    /// The work ends early once one of them is empty
+    pub until_empty: &'static [Stock],
+    /// Amounts needed once the work is done, nothing happens without them
+    pub requires: &'static [(Stock, f32)],
+    /// Stocks changed once the work is done
+    pub outputs: &'static [(Stock, f32)],
+    /// Text shown over the device once the work is done, with its sound
+    pub popup: Option<(&'static str, SfxKey)>,
+    /// Condition lost by the device every time the work is done
+    pub wear: f32,
+}
+
+/// Devices a crew member can work with. Burning, hazardous and broken
+/// devices only take the fix of their trouble
+pub type Usable = (Without<InFire>, Without<Hazard>, Without<Broken>);
+
+/// Something a device takes or gives
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stock {
+    Water,
+    BadWater,
+    Food,
+    Herbs,
+    /// Need of the crew member at the device
+    Thirst,
+    Hungry,
+    Pee,
+    Toilet,
+}
+
+impl Stock {
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Stock::Water => "water",
+            Stock::BadWater => "bad water",
+            Stock::Food => "food",
+            Stock::Herbs => "herbs",
+            Stock::Thirst => "thirst",
+            Stock::Hungry => "hunger",
+            Stock::Pee => "pee",
+            Stock::Toilet => "toilet",
+        }
+    }
+}
+
+/// Access to every [`Stock`], for a crew member
+#[derive(SystemParam)]
+pub struct Stocks<'w, 's> {
+    water: ResMut<'w, Water>,
+    bad_water: ResMut<'w, BadWater>,
+    food: ResMut<'w, Food>,
+    herbs: ResMut<'w, Herbs>,
+    water_flow: EventWriter<'w, Generate<Water>>,
+    bad_water_flow: EventWriter<'w, Generate<BadWater>>,
+    food_flow: EventWriter<'w, Generate<Food>>,
+    herbs_flow: EventWriter<'w, Generate<Herbs>>,
+    q_needs: Query<
+        'w,
+        's,
+        (
+            &'static mut Need<Thirst>,
+            &'static mut Need<Hungry>,
+            &'static mut Need<Pee>,
+            &'static mut Need<Toilet>,
+        ),
+    >,
+}
+
+impl Stocks<'_, '_> {
+    pub fn amount(&self, stock: Stock, crew: Entity) -> f32 {
+        let needs = self.q_needs.get(crew).ok();
+        match stock {
+            Stock::Water => self.water.amount(),
+            Stock::BadWater => self.bad_water.amount(),
+            Stock::Food => self.food.amount(),
+            Stock::Herbs => self.herbs.amount(),
+            Stock::Thirst => needs.map_or(0.0, |(thirst, ..)| thirst.amount()),
+            Stock::Hungry => needs.map_or(0.0, |(_, hungry, ..)| hungry.amount()),
+            Stock::Pee => needs.map_or(0.0, |(_, _, pee, _)| pee.amount()),
+            Stock::Toilet => needs.map_or(0.0, |(.., toilet)| toilet.amount()),
+        }
+    }
+
+    fn get_mut(&mut self, stock: Stock, crew: Entity) -> Option<&mut dyn GameResource> {
+        let needs = self.q_needs.get_mut(crew).ok();
+        match stock {
+            Stock::Water => Some(&mut *self.water),
+            Stock::BadWater => Some(&mut *self.bad_water),
+            Stock::Food => Some(&mut *self.food),
+            Stock::Herbs => Some(&mut *self.herbs),
+            Stock::Thirst => {
+                needs.map(|(thirst, ..)| &mut thirst.into_inner().0 as &mut dyn GameResource)
+            }
+            Stock::Hungry => {
+                needs.map(|(_, hungry, ..)| &mut hungry.into_inner().0 as &mut dyn GameResource)
+            }
+            Stock::Pee => {
+                needs.map(|(_, _, pee, _)| &mut pee.into_inner().0 as &mut dyn GameResource)
+            }
+            Stock::Toilet => {
+                needs.map(|(.., toilet)| &mut toilet.into_inner().0 as &mut dyn GameResource)
+            }
+        }
+    }
+
+    /// Adds the amount at once, or takes it away when negative
+    pub fn change(&mut self, stock: Stock, crew: Entity, amount: f32) {
+        let Some(resource) = self.get_mut(stock, crew) else {
+            return;
+        };
+        if amount >= 0.0 {
+            resource.increase(amount);
+        } else {
+            resource.decrease(-amount);
+        }
+    }
+
+    /// Keeps the stock flowing at `rate` per second during the frame
+    pub fn flow(&mut self, stock: Stock, crew: Entity, rate: f32, delta: f32) {
+        // Ship resources go through their generation, so the HUD shows it
+        match stock {
+            Stock::Water => {
+                self.water_flow.send(Generate::new(rate));
+            }
+            Stock::BadWater => {
+                self.bad_water_flow.send(Generate::new(rate));
+            }
+            Stock::Food => {
+                self.food_flow.send(Generate::new(rate));
+            }
+            Stock::Herbs => {
+                self.herbs_flow.send(Generate::new(rate));
+            }
+            _ => self.change(stock, crew, rate * delta),
+        }
+    }
+}
+
+/// Sent to the device when a crew member is done working on it
+#[derive(Event)]
+pub struct DeviceWorkDone {
+    /// Efficiency of the device during the work
+    pub efficiency: f32,
+}
+
+/// Crew member working on a device
+#[derive(Component)]
+pub struct DeviceWork {
+    pub device: Entity,
+    definition: &'static DeviceDefinition,
+    progress: f32,
+}
+
+struct DeviceWorkAction {
+    device: Entity,
+    definition: &'static DeviceDefinition,
+    sound: Handle<AudioSource>,
+}
+
+impl CharacterAction for DeviceWorkAction {
+    fn trigger_start(&self, commands: &mut Commands, target: Entity) {
+        commands
+            .entity(target)
+            .insert(DeviceWork {
+                device: self.device,
+                definition: self.definition,
+                progress: 0.0,
+            })
+            .insert(AudioBundle {
+                source: self.sound.clone_weak(),
+                settings: PlaybackSettings {
+                    mode: PlaybackMode::Remove,
+                    volume: Volume::new(self.definition.volume),
+                    ..default()
+                },
+            });
+    }
+
+    fn terminate(&self, commands: &mut Commands, target: Entity) {
+        commands.entity(target).remove::<DeviceWork>();
+    }
+
+    fn target(&self) -> Option<Entity> {
+        Some(self.device)
+    }
+}
+
+/// Floating text over a device, with a sound
+pub fn spawn_popup(
+    commands: &mut Commands,
+    position: Vec3,
+    text: impl Into<String>,
+    sound: Handle<AudioSource>,
+) {
+    commands
+        .spawn(BillboardTextBundle {
+            transform: Transform::from_translation(position).with_scale(Vec3::splat(0.01)),
+            text: Text::from_section(
+                text,
+                TextStyle {
+                    color: Color::linear_rgb(0.0, 1.0, 0.0),
+                    font_size: 94.0,
+                    ..default()
+                },
+            ),
+            ..default()
+        })
+        .insert(FlowUpText { lifetime: 1.0 })
+        .insert(AudioBundle {
+            source: sound,
+            ..default()
+        });
+}
+
+fn start_work<T: Device>(
+    trigger: Trigger<OnInteract>,
+    mut commands: Commands,
+    q_devices: Query<(&T, &GlobalTransform), Usable>,
+    sounds: Res<HandleMap<SfxKey>>,
+) {
+    let device = trigger.entity();
+    let Ok((marker, transform)) = q_devices.get(device) else {
+        return;
+    };
+    let definition = marker.definition();
+
+    let mut actions = ActionGroup::new(definition.group.to_string());
+    if !trigger.event().direct {
+        actions.add(GoToAction {
+            target: device,
+            target_pos: transform.translation(),
+        });
+    }
+    actions.add(DeviceWorkAction {
+        device,
+        definition,
+        sound: sounds[&definition.sfx].clone_weak(),
+    });
+
+    commands.trigger_targets(
+        NewActionSequence {
+            actions,
+            mode: trigger.event().mode(definition.mode),
+        },
+        trigger.event().actor,
+    );
+}
+
+fn update_device_work(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    sounds: Res<HandleMap<SfxKey>>,
+    mut stocks: Stocks,
+    mut q_crew: Query<(Entity, &mut DeviceWork, &mut CharacterStates)>,
+    mut q_devices: Query<(&GlobalTransform, Option<&mut Durability>), Usable>,
+) {
+    for (crew, mut work, mut states) in q_crew.iter_mut() {
+        let Ok((transform, mut durability)) = q_devices.get_mut(work.device) else {
+            // The device is gone, or it caught fire or broke down meanwhile
+            commands.entity(crew).remove::<DeviceWork>();
+            commands.trigger_targets(NextAction, crew);
+            continue;
+        };
+        let definition = work.definition;
+        states.add(definition.state);
+
+        let efficiency = efficiency_of(durability.as_deref());
+        for &(stock, rate) in definition.rates {
+            stocks.flow(stock, crew, rate * efficiency, time.delta_seconds());
+        }
+        work.progress += time.delta_seconds();
+
+        let emptied = definition
+            .until_empty
+            .iter()
+            .any(|stock| stocks.amount(*stock, crew) <= 0.0);
+        if work.progress < definition.duration && !emptied {
+            continue;
+        }
+
+        let position = transform.translation();
+        let missing = definition
+            .requires
+            .iter()
+            .find(|(stock, amount)| stocks.amount(*stock, crew) < *amount);
+        if let Some((stock, _)) = missing {
+            spawn_popup(
+                &mut commands,
+                position,
+                format!("Not enough {}", stock.label()),
+                sounds[&SfxKey::NotEnoughResource].clone_weak(),
+            );
+        } else {
+            // Only the work actually done wears the device
+            if let Some(durability) = durability.as_mut() {
+                durability.wear(definition.wear);
+            }
+            for &(stock, amount) in definition.outputs {
+                stocks.change(stock, crew, amount);
+            }
+            if let Some((text, sfx)) = definition.popup {
+                spawn_popup(&mut commands, position, text, sounds[&sfx].clone_weak());
+            }
+            commands.trigger_targets(DeviceWorkDone { efficiency }, work.device);
+        }
+
+        commands.entity(crew).remove::<DeviceWork>();
+        commands.trigger_targets(NextAction, crew);
+    }
+}
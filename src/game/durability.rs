@@ -0,0 +1,313 @@
+//! Wear of devices.
+//! Every device has a [`Durability`] that goes down as it is used: PC work,
+//! toilet flushes, cleaner cycles and recycler uptime. Worn devices work
+//! slower, and a device worn out completely is [`Broken`] and can't be used
+//! until a crew member repairs it with some [`Metal`].
+
+use bevy::prelude::*;
+
+use crate::screen::Screen;
+
+use super::{
+    billboard_state::BillboardContent,
+    build::build_cost,
+    character::{CharState, CharacterStates, GoToAction},
+    components::device_kind::DeviceKind,
+    daycycle::GameTime,
+    device_state::{DeviceState, DeviceStatePlugin},
+    resources::{Metal, OxygenRecycling},
+    selectable::OnInteract,
+    sequence::{ActionGroup, CharacterAction, NewActionSequence, NewMode, NextAction},
+    spawn::spawn_commands::OxygenRecyler,
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<Durability>();
+    app.observe(start_repair);
+    app.add_systems(
+        Update,
+        (
+            add_durability,
+            wear_recyclers,
+            break_devices,
+            update_repair,
+            update_wear_state,
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(PostUpdate, reset_durability);
+
+    app.add_plugins(DeviceStatePlugin::<WearState>::default());
+}
+
+const REPAIR_GROUP: &str = "repair";
+
+/// Condition of a new device
+pub const MAX_CONDITION: f32 = 100.0;
+/// Below this condition the device starts to work slower
+const WORN_CONDITION: f32 = 50.0;
+/// Efficiency of a device just about to break
+const MIN_EFFICIENCY: f32 = 0.5;
+/// Part of the build cost paid in metal to repair a device
+const REPAIR_COST: f32 = 0.25;
+/// Game seconds of work to repair a device
+const REPAIR_TIME: f32 = 4.0;
+
+/// Condition lost by a PC for every finished work
+pub const PC_WEAR: f32 = 1.0;
+/// Condition lost by a toilet for every flush
+pub const TOILET_WEAR: f32 = 4.0;
+/// Condition lost by a water cleaner for every cleaning cycle
+pub const CLEANER_WEAR: f32 = 5.0;
+/// Condition lost by a running oxygen recycler, per second
+const RECYCLER_WEAR: f32 = 0.2;
+
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component, Default)]
+pub struct Durability {
+    pub condition: f32,
+}
+
+impl Default for Durability {
+    fn default() -> Self {
+        Self {
+            condition: MAX_CONDITION,
+        }
+    }
+}
+
+impl Durability {
+    pub fn wear(&mut self, amount: f32) {
+        self.condition = (self.condition - amount).max(0.0);
+    }
+
+    pub const fn is_worn(&self) -> bool {
+        self.condition < WORN_CONDITION
+    }
+
+    /// Speed of the device, from [`MIN_EFFICIENCY`] when about to break to
+    /// `1.0` until it gets worn
+    pub const fn efficiency(&self) -> f32 {
+        if !self.is_worn() {
+            return 1.0;
+        }
+        MIN_EFFICIENCY + (1.0 - MIN_EFFICIENCY) * self.condition / WORN_CONDITION
+    }
+}
+
+/// Efficiency of a device that may not have a [`Durability`] yet
+pub fn efficiency_of(durability: Option<&Durability>) -> f32 {
+    durability.map_or(1.0, Durability::efficiency)
+}
+
+/// The device is worn out and waits for a repair
+#[derive(Component)]
+pub struct Broken;
+
+#[derive(Component, PartialEq, Clone)]
+pub enum WearState {
+    Good,
+    Worn,
+    Broken,
+}
+
+impl DeviceState for WearState {
+    fn content(&self) -> BillboardContent {
+        let text = |value: &str, color: Color| {
+            BillboardContent::Text(Text::from_section(value, TextStyle { color, ..default() }))
+        };
+        match self {
+            WearState::Good => BillboardContent::None,
+            WearState::Worn => text("Worn", Color::linear_rgb(1.0, 0.6, 0.1)),
+            WearState::Broken => text("Broken", Color::linear_rgb(1.0, 0.1, 0.1)),
+        }
+    }
+}
+
+/// Crew member repairing a [`Broken`] device
+#[derive(Component)]
+struct Repairing {
+    device: Entity,
+    progress: f32,
+}
+
+struct RepairAction {
+    device: Entity,
+}
+
+impl CharacterAction for RepairAction {
+    fn trigger_start(&self, commands: &mut Commands, target: Entity) {
+        commands.entity(target).insert(Repairing {
+            device: self.device,
+            progress: 0.0,
+        });
+    }
+
+    fn terminate(&self, commands: &mut Commands, target: Entity) {
+        commands.entity(target).remove::<Repairing>();
+    }
+}
+
+fn add_durability(
+    mut commands: Commands,
+    q_new: Query<(Entity, &DeviceKind), Without<Durability>>,
+) {
+    for (device, kind) in q_new.iter() {
+        if *kind != DeviceKind::MetalTrashPile {
+            commands.entity(device).insert(Durability::default());
+        }
+    }
+}
+
+fn wear_recyclers(
+    time: Res<GameTime>,
+    recycling: Res<OxygenRecycling>,
+    mut q_recyclers: Query<&mut Durability, (With<OxygenRecyler>, Without<Broken>)>,
+) {
+    if !recycling.working {
+        return;
+    }
+    for mut durability in q_recyclers.iter_mut() {
+        durability.wear(RECYCLER_WEAR * time.delta_seconds());
+    }
+}
+
+fn break_devices(
+    mut commands: Commands,
+    q_devices: Query<(Entity, &Durability, Option<&DeviceKind>), Without<Broken>>,
+) {
+    for (device, durability, kind) in q_devices.iter() {
+        if durability.condition <= 0.0 {
+            info!(
+                "{} {} broke down",
+                kind.map_or("Device", DeviceKind::label),
+                device
+            );
+            commands.entity(device).insert(Broken);
+        }
+    }
+}
+
+fn start_repair(
+    trigger: Trigger<OnInteract>,
+    mut commands: Commands,
+    q_broken: Query<&GlobalTransform, With<Broken>>,
+) {
+    let device = trigger.entity();
+    let Ok(transform) = q_broken.get(device) else {
+        return;
+    };
+
+    let mut actions = ActionGroup::new(REPAIR_GROUP.to_string());
+    if !trigger.event().direct {
+        actions.add(GoToAction {
+            target: device,
+            target_pos: transform.translation(),
+        });
+    }
+    actions.add(RepairAction { device });
+
+    commands.trigger_targets(
+        NewActionSequence {
+            actions,
+            mode: trigger.event().mode(NewMode::SoftReplace),
+        },
+        trigger.event().actor,
+    );
+}
+
+fn update_repair(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut metal: ResMut<Metal>,
+    mut q_crew: Query<(Entity, &mut Repairing, &mut CharacterStates)>,
+    mut q_devices: Query<(&mut Durability, &DeviceKind), With<Broken>>,
+) {
+    for (crew, mut repairing, mut states) in q_crew.iter_mut() {
+        let Ok((mut durability, kind)) = q_devices.get_mut(repairing.device) else {
+            // Repaired by someone else, or the device is gone
+            commands.entity(crew).remove::<Repairing>();
+            commands.trigger_targets(NextAction, crew);
+            continue;
+        };
+
+        states.add(CharState::Working);
+        repairing.progress += time.delta_seconds();
+        if repairing.progress < REPAIR_TIME {
+            continue;
+        }
+
+        let cost = build_cost(*kind) * REPAIR_COST;
+        if metal.amount >= cost {
+            metal.amount -= cost;
+            durability.condition = MAX_CONDITION;
+            commands.entity(repairing.device).remove::<Broken>();
+            info!("{} repaired for {} metal", kind.label(), cost);
+        } else {
+            info!("Not enough metal to repair {}", kind.label());
+        }
+        commands.entity(crew).remove::<Repairing>();
+        commands.trigger_targets(NextAction, crew);
+    }
+}
+
+/// Child of a device showing its [`WearState`], so the condition doesn't take
+/// the billboard of the device's own state
+#[derive(Component)]
+struct WearBillboard(Entity);
+
+/// Height of the condition billboard above the device's own one
+const WEAR_BILLBOARD_OFFSET: f32 = 0.5;
+
+fn update_wear_state(
+    mut commands: Commands,
+    q_devices: Query<(Entity, &Durability, Has<Broken>, Option<&WearBillboard>)>,
+    q_wear: Query<&WearState>,
+) {
+    for (device, durability, broken, billboard) in q_devices.iter() {
+        let state = if broken {
+            WearState::Broken
+        } else if durability.is_worn() {
+            WearState::Worn
+        } else {
+            WearState::Good
+        };
+        match billboard {
+            Some(billboard) => {
+                if q_wear.get(billboard.0).ok() != Some(&state) {
+                    commands.entity(billboard.0).insert(state);
+                }
+            }
+            // Devices in good shape don't need a billboard until they get worn
+            None if state == WearState::Good => {}
+            None => {
+                let billboard = commands
+                    .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+                        0.0,
+                        WEAR_BILLBOARD_OFFSET,
+                        0.0,
+                    )))
+                    .insert(state)
+                    .set_parent(device)
+                    .id();
+                commands.entity(device).insert(WearBillboard(billboard));
+            }
+        }
+    }
+}
+
+/// Devices are as good as new for the next game
+fn reset_durability(
+    mut commands: Commands,
+    mut resets: EventReader<ResetGame>,
+    mut q_devices: Query<(Entity, &mut Durability)>,
+) {
+    for _ in resets.read() {
+        for (device, mut durability) in q_devices.iter_mut() {
+            *durability = Durability::default();
+            commands.entity(device).remove::<Broken>();
+        }
+    }
+}
@@ -0,0 +1,664 @@
+//! Level editor, only in dev builds.
+//! F2 opens an overlay to spawn any [`DeviceKind`] at the cursor, drag
+//! [`Selectable`] entities with the middle mouse button and edit the
+//! reflected components of the last one middle-clicked. The edited state is
+//! saved to [`EDITOR_LEVEL_PATH`] as a [`Level`], and can be loaded back to
+//! reproduce a bug without replaying the whole game.
+
+use std::collections::BTreeMap;
+
+use bevy::{
+    prelude::*,
+    reflect::{serde::TypedReflectSerializer, ReflectMut, ReflectRef},
+    window::PrimaryWindow,
+};
+use bevy_mod_picking::prelude::*;
+
+use crate::{screen::Screen, ui::prelude::*};
+
+use super::{
+    assets::{HandleMap, LevelKey},
+    build::hovered_tile,
+    components::{device_kind::DeviceKind, fire::InFire, hydroponic::HydroponicState},
+    daycycle::NightLight,
+    map::{navigation::Path, on_storey, storey_height, ShipMap, STOREY_HEIGHT},
+    resources::{
+        BadWater, CarbonDioxide, Extinguisher, Food, GameResource, Herbs, Hydrogen, Metal,
+        MetalTrash, Oxygen, Pee, Water,
+    },
+    selectable::{OnMouseClick, Selectable},
+    spawn::{
+        layout::{
+            AmbientPlacement, CrewPlacement, DevicePlacement, Level, LightPlacement, SpawnDevice,
+            StartState,
+        },
+        level::{spawn_lights, CurrentLevel},
+        player::Player,
+    },
+    storeys::ViewedStorey,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<Editor>();
+    app.init_resource::<FieldEdits>();
+    app.init_resource::<InspectorRows>();
+    app.observe(inspect);
+    app.add_systems(
+        Update,
+        (
+            toggle_editor,
+            spawn_at_cursor,
+            drag_selectable,
+            inspect_components,
+            update_inspector,
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// Level file written by the editor, relative to the working directory
+#[cfg(not(target_family = "wasm"))]
+pub const EDITOR_LEVEL_PATH: &str = "assets/levels/editor.level.ron";
+
+/// Change of a numeric field for each click
+const NUMBER_STEP: f32 = 1.0;
+
+#[derive(Resource, Default)]
+struct Editor {
+    active: bool,
+    /// Device spawned by the next click on the floor
+    spawn: Option<DeviceKind>,
+    dragging: Option<Entity>,
+    inspected: Option<Entity>,
+}
+
+#[derive(Clone, Copy)]
+enum FieldChange {
+    Add(f32),
+    Toggle,
+}
+
+/// Change asked from the inspector, applied through reflection
+#[derive(Clone)]
+struct FieldEdit {
+    component: String,
+    field: String,
+    change: FieldChange,
+}
+
+#[derive(Resource, Default)]
+struct FieldEdits(Vec<FieldEdit>);
+
+/// Field of a component of the inspected entity
+struct InspectorRow {
+    component: String,
+    field: String,
+    value: String,
+    /// Editable fields
+    change: Option<FieldChange>,
+}
+
+#[derive(Resource, Default)]
+struct InspectorRows(Vec<InspectorRow>);
+
+#[derive(Component)]
+struct EditorPanel;
+
+#[derive(Component)]
+struct InspectorList;
+
+#[derive(Component)]
+struct InspectorValue(usize);
+
+fn row() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            flex_wrap: FlexWrap::Wrap,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(4.0),
+            row_gap: Val::Px(4.0),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn toggle_editor(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    q_panel: Query<Entity, With<EditorPanel>>,
+) {
+    if !keys.just_pressed(KeyCode::F2) {
+        return;
+    }
+
+    *editor = Editor {
+        active: !editor.active,
+        ..default()
+    };
+    for panel in q_panel.iter() {
+        commands.entity(panel).despawn_recursive();
+    }
+    if !editor.active {
+        return;
+    }
+
+    commands
+        .panel(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            max_width: Val::Px(420.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        })
+        .insert((Name::new("Level editor"), EditorPanel, ZIndex::Global(10)))
+        .with_children(|panel| {
+            panel.small_label(
+                "Editor (F2) - click to spawn, middle drag to move, middle click to inspect",
+            );
+            panel.spawn(row()).with_children(|row| {
+                for kind in DeviceKind::ALL {
+                    row.small_button(kind.label())
+                        .insert(On::<Pointer<Click>>::run(
+                            move |mut editor: ResMut<Editor>| {
+                                editor.spawn = Some(kind);
+                            },
+                        ));
+                }
+            });
+            #[cfg(not(target_family = "wasm"))]
+            panel.spawn(row()).with_children(|row| {
+                row.small_button("Save")
+                    .insert(On::<Pointer<Click>>::run(save_level));
+                row.small_button("Load")
+                    .insert(On::<Pointer<Click>>::run(load_level));
+            });
+            panel.spawn((
+                InspectorList,
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(2.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn spawn_at_cursor(
+    mut commands: Commands,
+    mut editor: ResMut<Editor>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    viewed: Res<ViewedStorey>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_cameras: Query<(&Camera, &GlobalTransform), With<IsDefaultUiCamera>>,
+    q_buttons: Query<&Interaction>,
+) {
+    if !editor.active {
+        return;
+    }
+    if mouse.just_pressed(MouseButton::Right) {
+        editor.spawn = None;
+    }
+    let Some(kind) = editor.spawn else {
+        return;
+    };
+    // The click was for the overlay
+    if !mouse.just_pressed(MouseButton::Left)
+        || q_buttons
+            .iter()
+            .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+    let Some(tile) = hovered_tile(&q_windows, &q_cameras, viewed.storey) else {
+        return;
+    };
+
+    commands.add(SpawnDevice(DevicePlacement {
+        kind,
+        position: [
+            tile.x as f32,
+            storey_height(viewed.storey) + kind.floor_height(),
+            tile.y as f32,
+        ],
+        rotation: 0.0,
+        scale: None,
+        state: StartState::Working,
+        components: default(),
+    }));
+}
+
+/// The [`Selectable`] entity a picked mesh belongs to
+fn selectable_ancestor(
+    entity: Entity,
+    q_parents: &Query<&Parent>,
+    q_selectable: &Query<(), With<Selectable>>,
+) -> Option<Entity> {
+    std::iter::successors(Some(entity), |entity| {
+        q_parents.get(*entity).ok().map(Parent::get)
+    })
+    .find(|entity| q_selectable.contains(*entity))
+}
+
+fn drag_selectable(
+    mut editor: ResMut<Editor>,
+    mut drag_starts: EventReader<Pointer<DragStart>>,
+    mut drag_ends: EventReader<Pointer<DragEnd>>,
+    viewed: Res<ViewedStorey>,
+    q_parents: Query<&Parent>,
+    q_selectable: Query<(), With<Selectable>>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_cameras: Query<(&Camera, &GlobalTransform), With<IsDefaultUiCamera>>,
+    mut q_transforms: Query<&mut Transform>,
+) {
+    for drag in drag_starts.read() {
+        if editor.active && drag.button == PointerButton::Middle {
+            editor.dragging = selectable_ancestor(drag.target, &q_parents, &q_selectable);
+        }
+    }
+    if drag_ends.read().count() > 0 {
+        editor.dragging = None;
+    }
+
+    let Some(dragged) = editor.dragging.filter(|_| editor.active) else {
+        return;
+    };
+    let (Some(tile), Ok(mut transform)) = (
+        hovered_tile(&q_windows, &q_cameras, viewed.storey),
+        q_transforms.get_mut(dragged),
+    ) else {
+        return;
+    };
+
+    // Keeps the height above the floor of the device
+    let ground = storey_height(viewed.storey);
+    let lift = transform.translation.y.rem_euclid(STOREY_HEIGHT);
+    transform.translation = Vec3::new(tile.x as f32, ground + lift, tile.y as f32);
+}
+
+fn inspect(trigger: Trigger<OnMouseClick>, mut editor: ResMut<Editor>) {
+    if editor.active && trigger.event().0 == MouseButton::Middle {
+        editor.inspected = Some(trigger.entity());
+    }
+}
+
+/// Applies the inspector edits and lists the fields of the inspected
+/// entity components, for the components of the game
+fn inspect_components(world: &mut World) {
+    let edits = std::mem::take(&mut world.resource_mut::<FieldEdits>().0);
+    let inspected = world
+        .resource::<Editor>()
+        .inspected
+        .filter(|entity| world.get_entity(*entity).is_some());
+    let Some(entity) = inspected else {
+        world.resource_mut::<InspectorRows>().0.clear();
+        return;
+    };
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    for edit in edits {
+        let Some(reflect_component) = registry
+            .get_with_type_path(&edit.component)
+            .and_then(|registration| registration.data::<ReflectComponent>())
+        else {
+            continue;
+        };
+        let Some(mut component) = reflect_component.reflect_mut(world.entity_mut(entity)) else {
+            continue;
+        };
+        let ReflectMut::Struct(fields) = (*component).reflect_mut() else {
+            continue;
+        };
+        let Some(field) = fields.field_mut(&edit.field) else {
+            continue;
+        };
+        match edit.change {
+            FieldChange::Add(step) => {
+                if let Some(value) = field.downcast_mut::<f32>() {
+                    *value += step;
+                }
+            }
+            FieldChange::Toggle => {
+                if let Some(value) = field.downcast_mut::<bool>() {
+                    *value = !*value;
+                }
+            }
+        }
+    }
+
+    let entity_ref = world.entity(entity);
+    let mut rows = Vec::new();
+    for registration in registry.iter() {
+        let component = registration.type_info().type_path();
+        if !component.starts_with(env!("CARGO_CRATE_NAME")) {
+            continue;
+        }
+        let Some(reflected) = registration
+            .data::<ReflectComponent>()
+            .and_then(|reflect_component| reflect_component.reflect(entity_ref))
+        else {
+            continue;
+        };
+        let ReflectRef::Struct(fields) = reflected.reflect_ref() else {
+            continue;
+        };
+
+        for i in 0..fields.field_len() {
+            let (Some(field), Some(value)) = (fields.name_at(i), fields.field_at(i)) else {
+                continue;
+            };
+            let (value, change) = if let Some(number) = value.downcast_ref::<f32>() {
+                (
+                    format!("{:.2}", number),
+                    Some(FieldChange::Add(NUMBER_STEP)),
+                )
+            } else if let Some(flag) = value.downcast_ref::<bool>() {
+                (flag.to_string(), Some(FieldChange::Toggle))
+            } else {
+                (format!("{:?}", value), None)
+            };
+            rows.push(InspectorRow {
+                component: component.to_string(),
+                field: field.to_string(),
+                value,
+                change,
+            });
+        }
+    }
+    // Registry order changes between runs, fields keep their order
+    rows.sort_by(|a, b| a.component.cmp(&b.component));
+
+    world.resource_mut::<InspectorRows>().0 = rows;
+}
+
+fn update_inspector(
+    mut commands: Commands,
+    rows: Res<InspectorRows>,
+    q_list: Query<Entity, With<InspectorList>>,
+    mut q_values: Query<(&InspectorValue, &mut Text)>,
+    mut shown: Local<Vec<(String, String)>>,
+) {
+    let Ok(list) = q_list.get_single() else {
+        shown.clear();
+        return;
+    };
+
+    let fields: Vec<(String, String)> = rows
+        .0
+        .iter()
+        .map(|row| (row.component.clone(), row.field.clone()))
+        .collect();
+    if fields == *shown {
+        for (InspectorValue(i), mut text) in q_values.iter_mut() {
+            if let Some(row) = rows.0.get(*i) {
+                if text.sections[0].value != row.value {
+                    text.sections[0].value.clone_from(&row.value);
+                }
+            }
+        }
+        return;
+    }
+    *shown = fields;
+
+    commands.entity(list).despawn_descendants();
+    commands.entity(list).with_children(|list| {
+        for (i, row) in rows.0.iter().enumerate() {
+            let short_name = row.component.rsplit("::").next().unwrap_or_default();
+            list.spawn(self::row()).with_children(|line| {
+                line.small_label(format!("{}.{}", short_name, row.field));
+                line.small_label(row.value.clone())
+                    .insert(InspectorValue(i));
+
+                let buttons = match row.change {
+                    Some(FieldChange::Add(step)) => {
+                        vec![
+                            ("-", FieldChange::Add(-step)),
+                            ("+", FieldChange::Add(step)),
+                        ]
+                    }
+                    Some(FieldChange::Toggle) => vec![("Toggle", FieldChange::Toggle)],
+                    None => Vec::new(),
+                };
+                for (label, change) in buttons {
+                    let edit = FieldEdit {
+                        component: row.component.clone(),
+                        field: row.field.clone(),
+                        change,
+                    };
+                    line.small_button(label).insert(On::<Pointer<Click>>::run(
+                        move |mut edits: ResMut<FieldEdits>| {
+                            edits.0.push(edit.clone());
+                        },
+                    ));
+                }
+            });
+        }
+    });
+}
+
+/// Type path and RON of the reflected components of the game on an entity
+#[cfg(not(target_family = "wasm"))]
+fn reflected_components(world: &World, entity: Entity) -> BTreeMap<String, String> {
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let entity_ref = world.entity(entity);
+
+    let mut components = BTreeMap::new();
+    for registration in registry.iter() {
+        let component = registration.type_info().type_path();
+        if !component.starts_with(env!("CARGO_CRATE_NAME")) {
+            continue;
+        }
+        let Some(reflected) = registration
+            .data::<ReflectComponent>()
+            .and_then(|reflect_component| reflect_component.reflect(entity_ref))
+        else {
+            continue;
+        };
+        match ron::to_string(&TypedReflectSerializer::new(reflected, &registry)) {
+            Ok(ron) => {
+                components.insert(component.to_string(), ron);
+            }
+            Err(err) => warn!("Can't save component {}: {}", component, err),
+        }
+    }
+    components
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_stock<T: GameResource>(world: &World, stock: &mut BTreeMap<String, f32>) {
+    let resource = world.resource::<T>();
+    stock.insert(resource.label(), resource.amount());
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn load_stock<T: GameResource>(world: &mut World, stock: &BTreeMap<String, f32>) {
+    let mut resource = world.resource_mut::<T>();
+    if let Some(amount) = stock.get(&resource.label()) {
+        resource.set_amount(*amount);
+    }
+}
+
+/// Amounts of the ship resources kept in [`Level::stock`], the metal has a
+/// field of its own
+#[cfg(not(target_family = "wasm"))]
+fn ship_stock(world: &World) -> BTreeMap<String, f32> {
+    let mut stock = BTreeMap::new();
+    save_stock::<Oxygen>(world, &mut stock);
+    save_stock::<CarbonDioxide>(world, &mut stock);
+    save_stock::<Hydrogen>(world, &mut stock);
+    save_stock::<Water>(world, &mut stock);
+    save_stock::<BadWater>(world, &mut stock);
+    save_stock::<Pee>(world, &mut stock);
+    save_stock::<Food>(world, &mut stock);
+    save_stock::<Herbs>(world, &mut stock);
+    save_stock::<MetalTrash>(world, &mut stock);
+    save_stock::<Extinguisher>(world, &mut stock);
+    stock
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn restock_ship(world: &mut World, stock: &BTreeMap<String, f32>) {
+    load_stock::<Oxygen>(world, stock);
+    load_stock::<CarbonDioxide>(world, stock);
+    load_stock::<Hydrogen>(world, stock);
+    load_stock::<Water>(world, stock);
+    load_stock::<BadWater>(world, stock);
+    load_stock::<Pee>(world, stock);
+    load_stock::<Food>(world, stock);
+    load_stock::<Herbs>(world, stock);
+    load_stock::<MetalTrash>(world, stock);
+    load_stock::<Extinguisher>(world, stock);
+}
+
+/// Writes the devices with their components, the crew, the lights and the
+/// resources of the running game as a level
+#[cfg(not(target_family = "wasm"))]
+fn save_level(
+    world: &World,
+    current_level: Res<CurrentLevel>,
+    level_handler: Res<HandleMap<LevelKey>>,
+    levels: Res<Assets<Level>>,
+    metal: Res<Metal>,
+    ambient: Res<AmbientLight>,
+    q_maps: Query<&ShipMap>,
+    q_devices: Query<(
+        Entity,
+        &DeviceKind,
+        &Transform,
+        Has<InFire>,
+        Option<&HydroponicState>,
+    )>,
+    q_crew: Query<(&Name, &Transform), With<Player>>,
+    q_lights: Query<(&SpotLight, &Transform), With<NightLight>>,
+) {
+    // The ship model and tanks can't be edited, they come from the level file
+    let (ship, tanks) = match *current_level {
+        CurrentLevel::File(key) => levels
+            .get(&level_handler[&key])
+            .map(|level| (level.ship.clone(), level.tanks.clone()))
+            .unwrap_or_default(),
+        CurrentLevel::Generated { .. } => (None, Vec::new()),
+    };
+    let map_size = on_storey(q_maps.iter(), 0)
+        .map(|map| (map.width(), map.height()))
+        .unwrap_or_default();
+
+    let devices = q_devices
+        .iter()
+        .map(|(entity, kind, transform, on_fire, hydroponic)| {
+            let state = match (on_fire, hydroponic) {
+                (true, _) => StartState::OnFire,
+                (_, Some(HydroponicState::Growed)) => StartState::Ripe,
+                (_, Some(HydroponicState::Dead)) => StartState::Withered,
+                _ => StartState::Working,
+            };
+            DevicePlacement {
+                kind: *kind,
+                position: transform.translation.to_array(),
+                rotation: transform.rotation.to_euler(EulerRot::YXZ).0.to_degrees(),
+                scale: Some(transform.scale.x),
+                state,
+                components: reflected_components(world, entity),
+            }
+        })
+        .collect();
+
+    let crew = q_crew
+        .iter()
+        .map(|(name, transform)| CrewPlacement {
+            name: name.to_string(),
+            position: transform.translation.to_array(),
+        })
+        .collect();
+
+    let lights = q_lights
+        .iter()
+        .map(|(light, transform)| LightPlacement {
+            position: transform.translation.to_array(),
+            inner_angle: light.inner_angle.to_degrees(),
+            outer_angle: light.outer_angle.to_degrees(),
+        })
+        .collect();
+
+    let color = ambient.color.to_srgba();
+    let level = Level {
+        ship,
+        map_size,
+        ambient_light: Some(AmbientPlacement {
+            brightness: ambient.brightness,
+            color: [color.red, color.green, color.blue],
+        }),
+        lights,
+        crew,
+        devices,
+        tanks,
+        metal: metal.amount,
+        stock: ship_stock(world),
+    };
+
+    let saved = ron::ser::to_string_pretty(&level, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|ron| std::fs::write(EDITOR_LEVEL_PATH, ron).map_err(|err| err.to_string()));
+    match saved {
+        Ok(()) => info!("Level saved to {}", EDITOR_LEVEL_PATH),
+        Err(err) => error!("Can't save level to {}: {}", EDITOR_LEVEL_PATH, err),
+    }
+}
+
+/// Replaces the devices and lights with the saved ones, puts the crew back
+/// where it was saved and sets the saved resources
+#[cfg(not(target_family = "wasm"))]
+fn load_level(
+    mut commands: Commands,
+    mut metal: ResMut<Metal>,
+    mut editor: ResMut<Editor>,
+    q_devices: Query<Entity, With<DeviceKind>>,
+    q_lights: Query<Entity, With<NightLight>>,
+    mut q_crew: Query<(Entity, &Name, &mut Transform), With<Player>>,
+) {
+    let level = std::fs::read_to_string(EDITOR_LEVEL_PATH)
+        .map_err(|err| err.to_string())
+        .and_then(|ron| ron::de::from_str::<Level>(&ron).map_err(|err| err.to_string()));
+    let level = match level {
+        Ok(level) => level,
+        Err(err) => {
+            error!("Can't load level from {}: {}", EDITOR_LEVEL_PATH, err);
+            return;
+        }
+    };
+
+    editor.dragging = None;
+    editor.inspected = None;
+    for device in q_devices.iter() {
+        commands.entity(device).despawn_recursive();
+    }
+    for device in level.devices.iter().cloned() {
+        commands.add(SpawnDevice(device));
+    }
+    for light in q_lights.iter() {
+        commands.entity(light).despawn_recursive();
+    }
+    spawn_lights(&mut commands, &level);
+
+    for (entity, name, mut transform) in q_crew.iter_mut() {
+        if let Some(placement) = level.crew.iter().find(|crew| crew.name == name.as_str()) {
+            transform.translation = Vec3::from_array(placement.position);
+            commands.entity(entity).remove::<Path>();
+        }
+    }
+
+    metal.amount = level.metal;
+    commands.add(move |world: &mut World| restock_ship(world, &level.stock));
+    info!("Level loaded from {}", EDITOR_LEVEL_PATH);
+}
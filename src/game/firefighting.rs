@@ -0,0 +1,213 @@
+//! Putting out fires.
+//! A crew member sent to a burning device sprays it for a while, longer for
+//! older fires. Every fire put out uses an [`Extinguisher`] charge, or some
+//! [`Water`] when none is left. Without either the fire keeps burning.
+//! Charges are bought with more debt or refilled from the water tank.
+
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+
+use crate::{screen::Screen, ui::prelude::*};
+
+use super::{
+    character::{CharState, CharacterStates, GoToAction},
+    components::fire::InFire,
+    daycycle::GameTime,
+    debt::Debt,
+    resources::{Extinguisher, GameResource, Water},
+    selectable::OnInteract,
+    sequence::{ActionGroup, CharacterAction, NewActionSequence, NewMode, NextAction},
+    spawn::level::SpawnLevel,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.observe(start_extinguishing);
+    app.observe(spawn_extinguisher_panel);
+    app.add_systems(
+        Update,
+        (update_extinguishing, update_extinguisher_panel).run_if(in_state(Screen::Playing)),
+    );
+}
+
+const EXTINGUISH_GROUP: &str = "extinguish";
+
+/// Extinguisher charges at the start of a level
+pub const STARTING_EXTINGUISHERS: f32 = 3.0;
+/// Game seconds to put out a fire that just started
+const EXTINGUISH_TIME: f32 = 1.0;
+/// Extra game seconds for every second the fire has been burning
+const EXTINGUISH_TIME_PER_AGE: f32 = 0.2;
+/// Water sprayed on a fire when no extinguisher is left
+const EXTINGUISH_WATER: f32 = 20.0;
+/// Debt taken to buy an extinguisher charge
+const EXTINGUISHER_PRICE: f32 = 300.0;
+/// Water used to refill an extinguisher charge, more than spraying it
+/// directly, the charge is only handier to keep
+const REFILL_WATER: f32 = EXTINGUISH_WATER * 1.25;
+
+/// Crew member putting out the fire of a device
+#[derive(Component)]
+struct Extinguishing {
+    device: Entity,
+    progress: f32,
+    /// Set when the spraying starts, from the age of the fire then
+    duration: Option<f32>,
+}
+
+struct ExtinguishAction {
+    device: Entity,
+}
+
+impl CharacterAction for ExtinguishAction {
+    fn trigger_start(&self, commands: &mut Commands, target: Entity) {
+        commands.entity(target).insert(Extinguishing {
+            device: self.device,
+            progress: 0.0,
+            duration: None,
+        });
+    }
+
+    fn terminate(&self, commands: &mut Commands, target: Entity) {
+        commands.entity(target).remove::<Extinguishing>();
+    }
+}
+
+#[derive(Component)]
+struct ExtinguisherLabel;
+
+const fn extinguish_time(fire_age: f32) -> f32 {
+    EXTINGUISH_TIME + EXTINGUISH_TIME_PER_AGE * fire_age
+}
+
+fn start_extinguishing(
+    trigger: Trigger<OnInteract>,
+    mut commands: Commands,
+    q_fires: Query<&GlobalTransform, With<InFire>>,
+) {
+    let device = trigger.entity();
+    let Ok(transform) = q_fires.get(device) else {
+        return;
+    };
+
+    let mut actions = ActionGroup::new(EXTINGUISH_GROUP.to_string());
+    if !trigger.event().direct {
+        actions.add(GoToAction {
+            target: device,
+            target_pos: transform.translation(),
+        });
+    }
+    actions.add(ExtinguishAction { device });
+
+    commands.trigger_targets(
+        NewActionSequence {
+            actions,
+            mode: trigger.event().mode(NewMode::SoftReplace),
+        },
+        trigger.event().actor,
+    );
+}
+
+fn update_extinguishing(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut extinguishers: ResMut<Extinguisher>,
+    mut water: ResMut<Water>,
+    mut q_crew: Query<(Entity, &mut Extinguishing, &mut CharacterStates)>,
+    q_fires: Query<&InFire>,
+) {
+    for (crew, mut extinguishing, mut states) in q_crew.iter_mut() {
+        let Ok(fire) = q_fires.get(extinguishing.device) else {
+            // Put out by someone else, or burnt down
+            commands.entity(crew).remove::<Extinguishing>();
+            commands.trigger_targets(NextAction, crew);
+            continue;
+        };
+
+        states.add(CharState::Working);
+        let duration = *extinguishing
+            .duration
+            .get_or_insert_with(|| extinguish_time(time.elapsed_seconds() - fire.started_at));
+        extinguishing.progress += time.delta_seconds();
+        if extinguishing.progress < duration {
+            continue;
+        }
+
+        let put_out = if extinguishers.amount() >= 1.0 {
+            extinguishers.decrease(1.0);
+            true
+        } else if water.amount() >= EXTINGUISH_WATER {
+            water.decrease(EXTINGUISH_WATER);
+            true
+        } else {
+            false
+        };
+
+        if put_out {
+            info!(
+                "Fire was alive for {} seconds",
+                time.elapsed_seconds() - fire.started_at
+            );
+            commands.entity(extinguishing.device).remove::<InFire>();
+        } else {
+            info!("No extinguisher or water left, the fire keeps burning");
+        }
+        commands.entity(crew).remove::<Extinguishing>();
+        commands.trigger_targets(NextAction, crew);
+    }
+}
+
+fn buy_extinguisher(mut extinguishers: ResMut<Extinguisher>, mut debt: ResMut<Debt>) {
+    debt.amount += EXTINGUISHER_PRICE;
+    extinguishers.increase(1.0);
+    info!("Extinguisher bought for {}", EXTINGUISHER_PRICE);
+}
+
+fn refill_extinguisher(mut extinguishers: ResMut<Extinguisher>, mut water: ResMut<Water>) {
+    if water.amount() < REFILL_WATER {
+        info!("Not enough water to refill an extinguisher");
+        return;
+    }
+    water.decrease(REFILL_WATER);
+    extinguishers.increase(1.0);
+}
+
+fn spawn_extinguisher_panel(
+    _trigger: Trigger<SpawnLevel>,
+    mut commands: Commands,
+    q_label: Query<(), With<ExtinguisherLabel>>,
+) {
+    if !q_label.is_empty() {
+        return;
+    }
+
+    commands
+        .panel(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            bottom: Val::Px(56.0),
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(4.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        })
+        .insert(Name::new("Extinguishers"))
+        .with_children(|builder| {
+            builder.small_label("").insert(ExtinguisherLabel);
+            builder
+                .small_button(format!("Buy ({} debt)", EXTINGUISHER_PRICE))
+                .insert(On::<Pointer<Click>>::run(buy_extinguisher));
+            builder
+                .small_button(format!("Refill ({} water)", REFILL_WATER))
+                .insert(On::<Pointer<Click>>::run(refill_extinguisher));
+        });
+}
+
+fn update_extinguisher_panel(
+    extinguishers: Res<Extinguisher>,
+    mut q_label: Query<&mut Text, With<ExtinguisherLabel>>,
+) {
+    for mut text in q_label.iter_mut() {
+        text.sections[0].value = format!("Extinguishers: {}", extinguishers.amount());
+    }
+}
@@ -0,0 +1,142 @@
+//! Device focus for keyboard and gamepad.
+//! Without a mouse the player steps through the [`Selectable`] devices with
+//! the focus actions or the left stick. The focused device is highlighted
+//! like a hovered one, and interacting with it works like clicking on it.
+
+use bevy::prelude::*;
+
+use crate::screen::Screen;
+
+use super::{
+    controls::ControlScheme,
+    input::{ActionState, InputAction},
+    selectable::{
+        CloseContextMenu, OnMouseClick, OnMouseOut, OnMouseOver, OpenContextMenu, Selectable,
+    },
+    spawn::player::Player,
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<Focus>();
+    app.add_systems(
+        Update,
+        (forget_lost_focus, cycle_focus, use_focus)
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(PostUpdate, reset_focus);
+}
+
+/// How far the stick has to be pushed to move the focus
+const STICK_FLICK: f32 = 0.6;
+
+#[derive(Resource, Default)]
+pub struct Focus {
+    pub entity: Option<Entity>,
+    /// The context menu of the focused device is open
+    menu_open: bool,
+    /// The stick went back to the center since the last flick
+    stick_released: bool,
+}
+
+fn forget_lost_focus(mut focus: ResMut<Focus>, q_selectables: Query<(), With<Selectable>>) {
+    if let Some(entity) = focus.entity {
+        if !q_selectables.contains(entity) {
+            focus.entity = None;
+            focus.menu_open = false;
+        }
+    }
+}
+
+fn cycle_focus(
+    mut commands: Commands,
+    mut focus: ResMut<Focus>,
+    actions: Res<ActionState>,
+    scheme: Res<ControlScheme>,
+    q_selectables: Query<(Entity, &GlobalTransform), (With<Selectable>, Without<Player>)>,
+) {
+    let mut step = 0;
+    if actions.just_pressed(InputAction::FocusNext) {
+        step += 1;
+    }
+    if actions.just_pressed(InputAction::FocusPrevious) {
+        step -= 1;
+    }
+
+    // The stick walks the crew member when it is under direct control
+    if !scheme.allows_direct() {
+        let stick = actions.stick();
+        if stick.length() < STICK_FLICK {
+            focus.stick_released = true;
+        } else if focus.stick_released {
+            focus.stick_released = false;
+            step += if stick.x + stick.y >= 0.0 { 1 } else { -1 };
+        }
+    }
+
+    if step == 0 {
+        return;
+    }
+
+    // Reading order over the floor, so the focus moves predictably
+    let mut devices: Vec<(Entity, Vec3)> = q_selectables
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation()))
+        .collect();
+    if devices.is_empty() {
+        return;
+    }
+    devices.sort_by(|a, b| a.1.z.total_cmp(&b.1.z).then(a.1.x.total_cmp(&b.1.x)));
+
+    let next = match focus
+        .entity
+        .and_then(|entity| devices.iter().position(|(device, _)| *device == entity))
+    {
+        Some(index) => (index as i32 + step).rem_euclid(devices.len() as i32) as usize,
+        None if step > 0 => 0,
+        None => devices.len() - 1,
+    };
+    let next = devices[next].0;
+
+    if let Some(previous) = focus.entity {
+        if focus.menu_open {
+            commands.trigger_targets(CloseContextMenu, previous);
+            focus.menu_open = false;
+        }
+        commands.trigger_targets(OnMouseOut, previous);
+    }
+    commands.trigger_targets(OnMouseOver, next);
+    focus.entity = Some(next);
+}
+
+fn use_focus(
+    mut commands: Commands,
+    mut focus: ResMut<Focus>,
+    actions: Res<ActionState>,
+    scheme: Res<ControlScheme>,
+) {
+    let Some(entity) = focus.entity else {
+        return;
+    };
+
+    // Under direct control only the nearest device can be used, see `controls`
+    if actions.just_pressed(InputAction::Interact) && scheme.allows_click() {
+        commands.trigger_targets(OnMouseClick(MouseButton::Left), entity);
+    }
+
+    if actions.just_pressed(InputAction::ContextMenu) {
+        if focus.menu_open {
+            commands.trigger_targets(CloseContextMenu, entity);
+        } else {
+            commands.trigger_targets(OpenContextMenu, entity);
+        }
+        focus.menu_open = !focus.menu_open;
+    }
+}
+
+fn reset_focus(mut resets: EventReader<ResetGame>, mut focus: ResMut<Focus>) {
+    for _ in resets.read() {
+        *focus = Focus::default();
+    }
+}
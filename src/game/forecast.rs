@@ -0,0 +1,218 @@
+//! Warnings about the next trouble.
+//! The [`TroublePlanner`] picks the next trouble a few seconds before it
+//! happens. A fire picked next to a [`Sensor`] is announced over its device
+//! with an alarm, so the crew can be sent before it starts. The risk overlay
+//! shows the chance of every device to be hit by the next trouble.
+
+use bevy::{
+    audio::{PlaybackMode, Volume},
+    prelude::*,
+};
+use bevy_mod_billboard::BillboardTextBundle;
+
+use crate::screen::Screen;
+
+use super::{
+    assets::{HandleMap, SfxKey},
+    components::{device_kind::DeviceKind, fire::InFire},
+    daycycle::DayPassed,
+    durability::Durability,
+    hazard::{Hazard, TroubleKind},
+    input::{ActionState, InputAction},
+    map::storey_of,
+    spawn::spawn_commands::Sensor,
+    trouble_planner::{trouble_odds, TroublePlanner},
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<Sensor>();
+    app.init_resource::<RiskOverlay>();
+    app.add_systems(
+        Update,
+        (
+            warn_upcoming_fires,
+            toggle_risk_overlay,
+            update_risk_overlay,
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(PostUpdate, reset_forecast);
+}
+
+/// Distance at which a sensor notices a device about to burn
+const SENSOR_RANGE: f32 = 4.0;
+/// Height of the labels over their device
+const LABEL_HEIGHT: f32 = 1.8;
+const WARNING_COLOR: Color = Color::linear_rgb(1.0, 0.4, 0.0);
+const RISK_COLOR: Color = Color::linear_rgb(1.0, 1.0, 0.3);
+
+/// Shows the chance of every device to be hit by the next trouble
+#[derive(Resource, Default)]
+pub struct RiskOverlay {
+    pub enabled: bool,
+}
+
+/// Billboard announcing the fire about to start on a device
+#[derive(Component)]
+struct FireWarning(Entity);
+
+/// Billboard with the risk of a device
+#[derive(Component)]
+struct RiskLabel(Entity);
+
+fn label(value: String, color: Color, position: Vec3) -> BillboardTextBundle {
+    BillboardTextBundle {
+        transform: Transform::from_translation(position + Vec3::Y * LABEL_HEIGHT)
+            .with_scale(Vec3::splat(0.01)),
+        text: Text::from_section(
+            value,
+            TextStyle {
+                color,
+                font_size: 60.0,
+                ..default()
+            },
+        ),
+        ..default()
+    }
+}
+
+/// A sensor on the same floor is close enough to the device
+fn is_watched(position: Vec3, sensors: &[Vec3]) -> bool {
+    sensors.iter().any(|sensor| {
+        storey_of(*sensor) == storey_of(position) && sensor.distance(position) <= SENSOR_RANGE
+    })
+}
+
+fn warn_upcoming_fires(
+    mut commands: Commands,
+    planner: Res<TroublePlanner>,
+    sounds: Res<HandleMap<SfxKey>>,
+    q_sensors: Query<&GlobalTransform, With<Sensor>>,
+    q_devices: Query<&GlobalTransform, With<DeviceKind>>,
+    mut q_warnings: Query<(Entity, &FireWarning, &mut Text)>,
+) {
+    let sensors = q_sensors
+        .iter()
+        .map(GlobalTransform::translation)
+        .collect::<Vec<_>>();
+    let watched = planner
+        .upcoming
+        .filter(|trouble| trouble.kind == TroubleKind::Fire)
+        .and_then(|trouble| {
+            let position = q_devices.get(trouble.target).ok()?.translation();
+            is_watched(position, &sensors).then_some((trouble.target, position))
+        });
+
+    let mut shown = false;
+    for (warning, FireWarning(device), mut text) in q_warnings.iter_mut() {
+        if watched.is_some_and(|(target, _)| target == *device) {
+            shown = true;
+            text.sections[0].value = format!("Fire in {:.0}s", planner.peace_time.max(0.0));
+        } else {
+            commands.entity(warning).despawn_recursive();
+        }
+    }
+
+    let Some((target, position)) = watched else {
+        return;
+    };
+    if shown {
+        return;
+    }
+
+    info!("Sensor: fire expected on {}", target);
+    commands.spawn((
+        Name::new("Fire warning"),
+        FireWarning(target),
+        label(
+            format!("Fire in {:.0}s", planner.peace_time.max(0.0)),
+            WARNING_COLOR,
+            position,
+        ),
+    ));
+    commands.spawn(AudioBundle {
+        source: sounds[&SfxKey::Alarm].clone_weak(),
+        settings: PlaybackSettings {
+            mode: PlaybackMode::Despawn,
+            volume: Volume::new(2.0),
+            ..default()
+        },
+    });
+}
+
+fn toggle_risk_overlay(actions: Res<ActionState>, mut overlay: ResMut<RiskOverlay>) {
+    if actions.just_pressed(InputAction::ToggleRiskOverlay) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+fn update_risk_overlay(
+    mut commands: Commands,
+    overlay: Res<RiskOverlay>,
+    days: Res<DayPassed>,
+    q_candidates: Query<
+        (Entity, &DeviceKind, Option<&Durability>),
+        (Without<InFire>, Without<Hazard>),
+    >,
+    q_devices: Query<(Entity, &GlobalTransform, &InheritedVisibility), With<DeviceKind>>,
+    mut q_labels: Query<(Entity, &RiskLabel, &mut Text, &mut Transform)>,
+) {
+    if !overlay.enabled {
+        for (entity, ..) in q_labels.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let odds = trouble_odds(days.days(), q_candidates.iter());
+    let risk_of = |device: Entity| {
+        odds.iter()
+            .filter(|(trouble, _)| trouble.target == device)
+            .map(|(_, chance)| chance)
+            .sum::<f32>()
+    };
+
+    let mut labelled = Vec::new();
+    for (entity, RiskLabel(device), mut text, mut transform) in q_labels.iter_mut() {
+        match q_devices.get(*device) {
+            Ok((_, device_transform, visibility)) if visibility.get() => {
+                text.sections[0].value = format!("{:.0}%", risk_of(*device) * 100.0);
+                transform.translation = device_transform.translation() + Vec3::Y * LABEL_HEIGHT;
+                labelled.push(*device);
+            }
+            // Gone, or on a hidden floor
+            _ => commands.entity(entity).despawn_recursive(),
+        }
+    }
+
+    for (device, transform, visibility) in q_devices.iter() {
+        if !visibility.get() || labelled.contains(&device) {
+            continue;
+        }
+        commands.spawn((
+            Name::new("Risk label"),
+            RiskLabel(device),
+            label(
+                format!("{:.0}%", risk_of(device) * 100.0),
+                RISK_COLOR,
+                transform.translation(),
+            ),
+        ));
+    }
+}
+
+fn reset_forecast(
+    mut commands: Commands,
+    mut resets: EventReader<ResetGame>,
+    mut overlay: ResMut<RiskOverlay>,
+    q_labels: Query<Entity, Or<(With<FireWarning>, With<RiskLabel>)>>,
+) {
+    for _ in resets.read() {
+        overlay.enabled = false;
+        for entity in q_labels.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
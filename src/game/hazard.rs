@@ -0,0 +1,395 @@
+//! Troubles that can hit the apartment.
+//! Every [`TroubleKind`] defines how likely it is, which devices it can hit,
+//! what it does every second, how long it takes to fix and what happens when
+//! nobody fixes it in time. Fire keeps its own [`InFire`] component, the other
+//! kinds put a [`Hazard`] on the device, which is fixed by interacting with it.
+//! The [`trouble_planner`](super::trouble_planner) picks the kind of the next
+//! trouble.
+
+use bevy::prelude::*;
+
+use super::{
+    assets::{HandleMap, SceneKey},
+    atmosphere::AirExchange,
+    character::{CharState, CharacterStates, GoToAction},
+    components::{device_kind::DeviceKind, fire::InFire, hydroponic::Hydroponic},
+    daycycle::GameTime,
+    jobs::WorkType,
+    resources::{BadWater, GameResource, Generate, Water},
+    selectable::OnInteract,
+    sequence::{ActionGroup, CharacterAction, NewActionSequence, NewMode, NextAction},
+    trouble_planner::wreck,
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.observe(start_fixing);
+    app.add_systems(
+        Update,
+        (hazard_effects, update_fixing, hazard_failures, show_hazards).chain(),
+    );
+    app.add_systems(PostUpdate, reset_hazards);
+}
+
+const FIX_GROUP: &str = "fix_hazard";
+
+/// Water lost by a leak, per second
+const LEAK_RATE: f32 = 0.5;
+/// Bad water spilled by a clogged toilet, per second
+const CLOG_RATE: f32 = 0.3;
+/// Plant water eaten by the blight, per second
+const BLIGHT_RATE: f32 = 0.2;
+/// Air vented to space by a hull breach, per second
+const BREACH_RATE: f32 = 1.5;
+/// Part of the water lost when a leaking pipe bursts
+const BURST_LOSS: f32 = 0.5;
+/// Bad water spilled when a clogged toilet overflows
+const OVERFLOW: f32 = 20.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TroubleKind {
+    Fire,
+    WaterLeak,
+    ShortCircuit,
+    CloggedToilet,
+    Blight,
+    HullBreach,
+}
+
+impl TroubleKind {
+    pub const ALL: [TroubleKind; 6] = [
+        TroubleKind::Fire,
+        TroubleKind::WaterLeak,
+        TroubleKind::ShortCircuit,
+        TroubleKind::CloggedToilet,
+        TroubleKind::Blight,
+        TroubleKind::HullBreach,
+    ];
+
+    pub const fn label(&self) -> &'static str {
+        match self {
+            TroubleKind::Fire => "Fire",
+            TroubleKind::WaterLeak => "Water leak",
+            TroubleKind::ShortCircuit => "Short circuit",
+            TroubleKind::CloggedToilet => "Clogged toilet",
+            TroubleKind::Blight => "Blight",
+            TroubleKind::HullBreach => "Hull breach",
+        }
+    }
+
+    /// Chance to be picked relative to the other kinds, troubles get more
+    /// varied as the days pass
+    pub const fn weight(&self, days: u32) -> f32 {
+        let days = days as f32;
+        match self {
+            TroubleKind::Fire => 4.0 + days * 0.5,
+            TroubleKind::WaterLeak => 2.0 + days * 0.5,
+            TroubleKind::ShortCircuit => 1.0 + days * 0.5,
+            TroubleKind::CloggedToilet => 1.0 + days * 0.25,
+            TroubleKind::Blight => 1.0 + days * 0.25,
+            // Meteors only show up after a few days
+            TroubleKind::HullBreach if days < 2.0 => 0.0,
+            TroubleKind::HullBreach => (days - 1.0) * 0.5,
+        }
+    }
+
+    /// The trouble can happen on this kind of device
+    pub const fn can_hit(&self, kind: DeviceKind) -> bool {
+        match self {
+            TroubleKind::Fire | TroubleKind::HullBreach => {
+                !matches!(kind, DeviceKind::MetalTrashPile)
+            }
+            TroubleKind::WaterLeak => matches!(
+                kind,
+                DeviceKind::WaterDispenser | DeviceKind::WaterCleaner | DeviceKind::Toilet
+            ),
+            TroubleKind::ShortCircuit => matches!(
+                kind,
+                DeviceKind::Pc | DeviceKind::Kitchen | DeviceKind::OxygenRecycler
+            ),
+            TroubleKind::CloggedToilet => matches!(kind, DeviceKind::Toilet),
+            TroubleKind::Blight => matches!(kind, DeviceKind::Hydroponic),
+        }
+    }
+
+    /// Seconds before the trouble gets worse, see [`hazard_failures`]
+    const fn time_limit(&self) -> f32 {
+        match self {
+            TroubleKind::Fire => 10.0,
+            TroubleKind::WaterLeak => 30.0,
+            TroubleKind::ShortCircuit => 20.0,
+            TroubleKind::CloggedToilet => 40.0,
+            TroubleKind::Blight => 45.0,
+            TroubleKind::HullBreach => 25.0,
+        }
+    }
+
+    /// Seconds of work needed to fix the trouble
+    const fn fix_time(&self) -> f32 {
+        match self {
+            TroubleKind::Fire => 0.0,
+            TroubleKind::WaterLeak | TroubleKind::CloggedToilet => 3.0,
+            TroubleKind::ShortCircuit | TroubleKind::Blight => 4.0,
+            TroubleKind::HullBreach => 6.0,
+        }
+    }
+
+    pub const fn work_type(&self) -> WorkType {
+        match self {
+            TroubleKind::Fire => WorkType::Firefighting,
+            TroubleKind::WaterLeak | TroubleKind::CloggedToilet => WorkType::Plumbing,
+            TroubleKind::Blight => WorkType::Farming,
+            TroubleKind::ShortCircuit | TroubleKind::HullBreach => WorkType::Building,
+        }
+    }
+
+    const fn color(&self) -> Color {
+        match self {
+            TroubleKind::Fire => Color::srgb(1.0, 0.5, 0.0),
+            TroubleKind::WaterLeak => Color::srgb(0.2, 0.4, 1.0),
+            TroubleKind::ShortCircuit => Color::srgb(1.0, 1.0, 0.2),
+            TroubleKind::CloggedToilet => Color::srgb(0.5, 0.35, 0.1),
+            TroubleKind::Blight => Color::srgb(0.6, 0.2, 0.7),
+            TroubleKind::HullBreach => Color::srgb(0.9, 0.9, 0.9),
+        }
+    }
+
+    /// Puts the trouble on the device
+    pub fn start(&self, commands: &mut Commands, device: Entity, now: f32) {
+        match self {
+            TroubleKind::Fire => {
+                commands.entity(device).insert(InFire {
+                    fire_created: false,
+                    started_at: now,
+                });
+            }
+            kind => {
+                commands.entity(device).insert(Hazard {
+                    kind: *kind,
+                    started_at: now,
+                    progress: 0.0,
+                });
+            }
+        }
+        info!("{} on {}", self.label(), device);
+    }
+}
+
+/// A trouble other than fire on a device. The device can't be used until a
+/// crew member fixes it.
+#[derive(Component)]
+pub struct Hazard {
+    pub kind: TroubleKind,
+    /// Based on the [`GameTime`]
+    pub started_at: f32,
+    /// Seconds of fixing done
+    progress: f32,
+}
+
+/// Marker floating over a device with a [`Hazard`]
+#[derive(Component)]
+struct HazardMarker(Entity);
+
+/// Crew member fixing a [`Hazard`]
+#[derive(Component)]
+struct Fixing {
+    device: Entity,
+}
+
+struct FixAction {
+    device: Entity,
+}
+
+impl CharacterAction for FixAction {
+    fn trigger_start(&self, commands: &mut Commands, target: Entity) {
+        commands.entity(target).insert(Fixing {
+            device: self.device,
+        });
+    }
+
+    fn terminate(&self, commands: &mut Commands, target: Entity) {
+        commands.entity(target).remove::<Fixing>();
+    }
+}
+
+fn start_fixing(
+    trigger: Trigger<OnInteract>,
+    mut commands: Commands,
+    q_hazards: Query<&GlobalTransform, With<Hazard>>,
+) {
+    let device = trigger.entity();
+    let Ok(transform) = q_hazards.get(device) else {
+        return;
+    };
+
+    let mut actions = ActionGroup::new(FIX_GROUP.to_string());
+    if !trigger.event().direct {
+        actions.add(GoToAction {
+            target: device,
+            target_pos: transform.translation(),
+        });
+    }
+    actions.add(FixAction { device });
+
+    commands.trigger_targets(
+        NewActionSequence {
+            actions,
+            mode: trigger.event().mode(NewMode::SoftReplace),
+        },
+        trigger.event().actor,
+    );
+}
+
+fn hazard_effects(
+    time: Res<GameTime>,
+    mut q_hazards: Query<(&Hazard, &GlobalTransform, Option<&mut Hydroponic>)>,
+    mut water: EventWriter<Generate<Water>>,
+    mut bad_water: EventWriter<Generate<BadWater>>,
+    mut exchanges: EventWriter<AirExchange>,
+) {
+    for (hazard, transform, hydroponic) in q_hazards.iter_mut() {
+        match hazard.kind {
+            TroubleKind::WaterLeak => {
+                water.send(Generate::new(-LEAK_RATE));
+            }
+            TroubleKind::CloggedToilet => {
+                bad_water.send(Generate::new(CLOG_RATE));
+            }
+            TroubleKind::Blight => {
+                if let Some(mut hydroponic) = hydroponic {
+                    hydroponic.water =
+                        (hydroponic.water - BLIGHT_RATE * time.delta_seconds()).max(0.0);
+                }
+            }
+            TroubleKind::HullBreach => {
+                exchanges.send(AirExchange {
+                    position: transform.translation(),
+                    oxygen: -BREACH_RATE,
+                    co2: -BREACH_RATE,
+                });
+            }
+            // Out of order devices are handled by their own systems
+            TroubleKind::Fire | TroubleKind::ShortCircuit => {}
+        }
+    }
+}
+
+fn update_fixing(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut q_crew: Query<(Entity, &Fixing, &mut CharacterStates)>,
+    mut q_hazards: Query<&mut Hazard>,
+) {
+    for (crew, fixing, mut states) in q_crew.iter_mut() {
+        let Ok(mut hazard) = q_hazards.get_mut(fixing.device) else {
+            // Fixed by someone else, or the device is gone
+            commands.entity(crew).remove::<Fixing>();
+            commands.trigger_targets(NextAction, crew);
+            continue;
+        };
+
+        states.add(CharState::Working);
+        hazard.progress += time.delta_seconds();
+        if hazard.progress < hazard.kind.fix_time() {
+            continue;
+        }
+
+        info!(
+            "{} fixed after {:.0} seconds",
+            hazard.kind.label(),
+            time.elapsed_seconds() - hazard.started_at
+        );
+        commands.entity(fixing.device).remove::<Hazard>();
+        commands.entity(crew).remove::<Fixing>();
+        commands.trigger_targets(NextAction, crew);
+    }
+}
+
+/// Troubles left alone for too long get worse
+fn hazard_failures(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    scene_handler: Res<HandleMap<SceneKey>>,
+    mut q_hazards: Query<(
+        Entity,
+        &Hazard,
+        &Transform,
+        Option<&DeviceKind>,
+        Option<&mut Hydroponic>,
+    )>,
+    mut water: ResMut<Water>,
+    mut bad_water: ResMut<BadWater>,
+) {
+    for (device, hazard, transform, kind, hydroponic) in q_hazards.iter_mut() {
+        if time.elapsed_seconds() - hazard.started_at < hazard.kind.time_limit() {
+            continue;
+        }
+
+        info!("{} on {} wasn't fixed in time", hazard.kind.label(), device);
+        commands.entity(device).remove::<Hazard>();
+        match hazard.kind {
+            TroubleKind::WaterLeak => {
+                let amount = water.amount();
+                water.set_amount(amount * (1.0 - BURST_LOSS));
+            }
+            TroubleKind::ShortCircuit => {
+                TroubleKind::Fire.start(&mut commands, device, time.elapsed_seconds());
+            }
+            TroubleKind::CloggedToilet => bad_water.increase(OVERFLOW),
+            // The hydroponic state follows the plant, so kill the plant itself
+            TroubleKind::Blight => {
+                if let Some(mut hydroponic) = hydroponic {
+                    hydroponic.dead = true;
+                }
+            }
+            TroubleKind::HullBreach => {
+                wreck(&mut commands, &scene_handler, device, kind, transform);
+            }
+            TroubleKind::Fire => {}
+        }
+    }
+}
+
+fn show_hazards(
+    mut commands: Commands,
+    q_new: Query<(Entity, &Hazard, &GlobalTransform), Added<Hazard>>,
+    q_hazards: Query<(), With<Hazard>>,
+    q_markers: Query<(Entity, &HazardMarker)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (device, hazard, transform) in q_new.iter() {
+        commands.spawn((
+            Name::new(hazard.kind.label()),
+            HazardMarker(device),
+            PbrBundle {
+                mesh: meshes.add(Sphere::new(0.2)),
+                material: materials.add(StandardMaterial {
+                    base_color: hazard.kind.color(),
+                    emissive: hazard.kind.color().into(),
+                    ..default()
+                }),
+                transform: Transform::from_translation(transform.translation() + Vec3::Y * 2.0),
+                ..default()
+            },
+        ));
+    }
+
+    for (marker, HazardMarker(device)) in q_markers.iter() {
+        if !q_hazards.contains(*device) {
+            commands.entity(marker).despawn_recursive();
+        }
+    }
+}
+
+fn reset_hazards(
+    mut commands: Commands,
+    mut resets: EventReader<ResetGame>,
+    q_hazards: Query<Entity, With<Hazard>>,
+) {
+    for _ in resets.read() {
+        for device in q_hazards.iter() {
+            commands.entity(device).remove::<Hazard>();
+        }
+    }
+}
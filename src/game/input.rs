@@ -0,0 +1,459 @@
+//! Input abstraction.
+//! Keyboard keys, mouse buttons and gamepad buttons are bound to
+//! [`InputAction`]s through the [`InputMap`]. Gameplay systems read the
+//! resulting [`ActionState`] instead of the raw devices, so every action
+//! works the same with any of them.
+//! On native builds the map is loaded from and saved to [`KEYMAP_PATH`], and
+//! can be changed from the settings screen.
+
+use bevy::{
+    input::InputSystem,
+    prelude::*,
+    reflect::{DynamicEnum, DynamicVariant, Enum},
+    utils::{HashMap, HashSet},
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<InputMap>();
+    app.init_resource::<ActionState>();
+    app.add_systems(PreUpdate, update_action_state.after(InputSystem));
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        app.add_systems(Startup, load_input_map);
+        app.add_systems(Last, save_input_map);
+    }
+}
+
+/// Sticks closer to the center than this are ignored
+const STICK_DEAD_ZONE: f32 = 0.2;
+
+/// File the keymap is persisted to, relative to the working directory
+#[cfg(not(target_family = "wasm"))]
+pub const KEYMAP_PATH: &str = "keymap.cfg";
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Reflect)]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Interact,
+    /// Held while giving an order to queue it after the current work
+    QueueAppend,
+    ContextMenu,
+    FocusNext,
+    FocusPrevious,
+    TimeFaster,
+    TimeSlower,
+    Pause,
+    ToggleAutopilot,
+    ToggleWorkPriorities,
+    CycleControlScheme,
+    ToggleBuildMode,
+    RotateBlueprint,
+    StoreyUp,
+    StoreyDown,
+    ToggleRiskOverlay,
+    Reset,
+    Menu,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 22] = [
+        InputAction::MoveUp,
+        InputAction::MoveDown,
+        InputAction::MoveLeft,
+        InputAction::MoveRight,
+        InputAction::Interact,
+        InputAction::QueueAppend,
+        InputAction::ContextMenu,
+        InputAction::FocusNext,
+        InputAction::FocusPrevious,
+        InputAction::TimeFaster,
+        InputAction::TimeSlower,
+        InputAction::Pause,
+        InputAction::ToggleAutopilot,
+        InputAction::ToggleWorkPriorities,
+        InputAction::CycleControlScheme,
+        InputAction::ToggleBuildMode,
+        InputAction::RotateBlueprint,
+        InputAction::StoreyUp,
+        InputAction::StoreyDown,
+        InputAction::ToggleRiskOverlay,
+        InputAction::Reset,
+        InputAction::Menu,
+    ];
+
+    /// Name used in the keymap file
+    pub fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    pub const fn label(&self) -> &'static str {
+        match self {
+            InputAction::MoveUp => "Move up",
+            InputAction::MoveDown => "Move down",
+            InputAction::MoveLeft => "Move left",
+            InputAction::MoveRight => "Move right",
+            InputAction::Interact => "Interact",
+            InputAction::QueueAppend => "Queue order",
+            InputAction::ContextMenu => "Context menu",
+            InputAction::FocusNext => "Next device",
+            InputAction::FocusPrevious => "Previous device",
+            InputAction::TimeFaster => "Time faster",
+            InputAction::TimeSlower => "Time slower",
+            InputAction::Pause => "Pause",
+            InputAction::ToggleAutopilot => "Autopilot",
+            InputAction::ToggleWorkPriorities => "Work priorities",
+            InputAction::CycleControlScheme => "Control scheme",
+            InputAction::ToggleBuildMode => "Build mode",
+            InputAction::RotateBlueprint => "Rotate blueprint",
+            InputAction::StoreyUp => "Floor up",
+            InputAction::StoreyDown => "Floor down",
+            InputAction::ToggleRiskOverlay => "Risk overlay",
+            InputAction::Reset => "Restart",
+            InputAction::Menu => "Menu",
+        }
+    }
+}
+
+/// A physical button that can trigger an [`InputAction`]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl InputBinding {
+    /// Name of the button, like `BracketLeft` or `South`
+    pub fn label(&self) -> &str {
+        match self {
+            InputBinding::Key(key) => key.variant_name(),
+            InputBinding::Mouse(button) => button.variant_name(),
+            InputBinding::Gamepad(button) => button.variant_name(),
+        }
+    }
+
+    /// Name used in the keymap file, like `Key:BracketLeft`
+    pub fn name(&self) -> String {
+        let device = match self {
+            InputBinding::Key(_) => "Key",
+            InputBinding::Mouse(_) => "Mouse",
+            InputBinding::Gamepad(_) => "Gamepad",
+        };
+        format!("{}:{}", device, self.label())
+    }
+
+    /// Reads a name written by [`InputBinding::name`]. Buttons without a
+    /// name of their own, like unidentified keys, can't be read back.
+    pub fn parse(name: &str) -> Option<Self> {
+        let (device, variant) = name.trim().split_once(':')?;
+        let button = DynamicEnum::new(variant.trim(), DynamicVariant::Unit);
+        match device.trim() {
+            "Key" => KeyCode::from_reflect(&button).map(InputBinding::Key),
+            "Mouse" => MouseButton::from_reflect(&button).map(InputBinding::Mouse),
+            "Gamepad" => GamepadButtonType::from_reflect(&button).map(InputBinding::Gamepad),
+            _ => None,
+        }
+    }
+
+    pub const fn is_gamepad(&self) -> bool {
+        matches!(self, InputBinding::Gamepad(_))
+    }
+}
+
+#[derive(Resource)]
+pub struct InputMap {
+    bindings: HashMap<InputAction, Vec<InputBinding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use GamepadButtonType as Pad;
+        use InputAction as A;
+        use InputBinding::{Gamepad, Key};
+
+        let bindings = [
+            (A::MoveUp, vec![Key(KeyCode::KeyW), Key(KeyCode::ArrowUp)]),
+            (
+                A::MoveDown,
+                vec![Key(KeyCode::KeyS), Key(KeyCode::ArrowDown)],
+            ),
+            (
+                A::MoveLeft,
+                vec![Key(KeyCode::KeyA), Key(KeyCode::ArrowLeft)],
+            ),
+            (
+                A::MoveRight,
+                vec![Key(KeyCode::KeyD), Key(KeyCode::ArrowRight)],
+            ),
+            (A::Interact, vec![Key(KeyCode::KeyE), Gamepad(Pad::South)]),
+            (
+                A::QueueAppend,
+                vec![
+                    Key(KeyCode::ShiftLeft),
+                    Key(KeyCode::ShiftRight),
+                    Gamepad(Pad::LeftTrigger2),
+                ],
+            ),
+            (A::ContextMenu, vec![Key(KeyCode::KeyF), Gamepad(Pad::East)]),
+            (
+                A::FocusNext,
+                vec![Key(KeyCode::Tab), Gamepad(Pad::DPadRight)],
+            ),
+            (A::FocusPrevious, vec![Gamepad(Pad::DPadLeft)]),
+            // The numpad is there on every layout, the brackets are not
+            (
+                A::TimeFaster,
+                vec![
+                    Key(KeyCode::BracketRight),
+                    Key(KeyCode::NumpadAdd),
+                    Gamepad(Pad::RightTrigger),
+                ],
+            ),
+            (
+                A::TimeSlower,
+                vec![
+                    Key(KeyCode::BracketLeft),
+                    Key(KeyCode::NumpadSubtract),
+                    Gamepad(Pad::LeftTrigger),
+                ],
+            ),
+            (A::Pause, vec![Key(KeyCode::Space)]),
+            (
+                A::ToggleAutopilot,
+                vec![Key(KeyCode::KeyP), Gamepad(Pad::West)],
+            ),
+            (
+                A::ToggleWorkPriorities,
+                vec![Key(KeyCode::KeyJ), Gamepad(Pad::North)],
+            ),
+            (
+                A::CycleControlScheme,
+                vec![Key(KeyCode::KeyC), Gamepad(Pad::RightThumb)],
+            ),
+            (A::ToggleBuildMode, vec![Key(KeyCode::KeyB)]),
+            (A::RotateBlueprint, vec![Key(KeyCode::KeyQ)]),
+            (A::StoreyUp, vec![Key(KeyCode::PageUp)]),
+            (A::StoreyDown, vec![Key(KeyCode::PageDown)]),
+            (A::ToggleRiskOverlay, vec![Key(KeyCode::KeyO)]),
+            (A::Reset, vec![Key(KeyCode::KeyR), Gamepad(Pad::Select)]),
+            (A::Menu, vec![Key(KeyCode::Escape), Gamepad(Pad::Start)]),
+        ];
+
+        Self {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn bindings(&self, action: InputAction) -> &[InputBinding] {
+        self.bindings
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Replaces the keyboard and mouse bindings of the action, gamepad
+    /// bindings are kept
+    pub fn rebind_key(&mut self, action: InputAction, key: KeyCode) {
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(InputBinding::is_gamepad);
+        bindings.insert(0, InputBinding::Key(key));
+    }
+
+    /// Other actions sharing a binding with the action
+    pub fn conflicts(&self, action: InputAction) -> Vec<InputAction> {
+        let bindings = self.bindings(action);
+        InputAction::ALL
+            .into_iter()
+            .filter(|other| *other != action)
+            .filter(|other| {
+                self.bindings(*other)
+                    .iter()
+                    .any(|binding| bindings.contains(binding))
+            })
+            .collect()
+    }
+
+    /// One `Action = Device:Button, ...` line per action
+    pub fn to_config(&self) -> String {
+        InputAction::ALL
+            .iter()
+            .map(|action| {
+                let bindings: Vec<String> = self
+                    .bindings(*action)
+                    .iter()
+                    .map(InputBinding::name)
+                    .collect();
+                format!("{} = {}\n", action.name(), bindings.join(", "))
+            })
+            .collect()
+    }
+
+    /// Reads a keymap written by [`InputMap::to_config`]. Actions missing
+    /// from the text keep their default bindings.
+    pub fn from_config(text: &str) -> Self {
+        let mut map = Self::default();
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, bindings)) = line.split_once('=') else {
+                warn!("Invalid keymap line: {}", line);
+                continue;
+            };
+            let Some(action) = InputAction::ALL
+                .into_iter()
+                .find(|action| action.name() == name.trim())
+            else {
+                warn!("Unknown input action: {}", name.trim());
+                continue;
+            };
+
+            let bindings = bindings
+                .split(',')
+                .filter(|binding| !binding.trim().is_empty())
+                .filter_map(|binding| {
+                    let parsed = InputBinding::parse(binding);
+                    if parsed.is_none() {
+                        warn!("Unknown input binding: {}", binding.trim());
+                    }
+                    parsed
+                })
+                .collect();
+            map.bindings.insert(action, bindings);
+        }
+
+        map
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn load_input_map(mut input_map: ResMut<InputMap>) {
+    match std::fs::read_to_string(KEYMAP_PATH) {
+        Ok(text) => *input_map = InputMap::from_config(&text),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => warn!("Can't read {}: {}", KEYMAP_PATH, err),
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_input_map(input_map: Res<InputMap>) {
+    if !input_map.is_changed() || input_map.is_added() {
+        return;
+    }
+    if let Err(err) = std::fs::write(KEYMAP_PATH, input_map.to_config()) {
+        warn!("Can't write {}: {}", KEYMAP_PATH, err);
+    }
+}
+
+/// What the player is doing this frame, whatever the device
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<InputAction>,
+    just_pressed: HashSet<InputAction>,
+    /// Direction from the move actions and the left stick, at most 1 long
+    movement: Vec2,
+    /// Left stick of the gamepad pushed the furthest
+    stick: Vec2,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub const fn movement(&self) -> Vec2 {
+        self.movement
+    }
+
+    /// Left stick position of any gamepad
+    pub const fn stick(&self) -> Vec2 {
+        self.stick
+    }
+}
+
+/// Run condition that is true the frame the action starts
+pub fn action_just_pressed(action: InputAction) -> impl FnMut(Res<ActionState>) -> bool + Clone {
+    move |state: Res<ActionState>| state.just_pressed(action)
+}
+
+pub(crate) fn update_action_state(
+    input_map: Res<InputMap>,
+    mut state: ResMut<ActionState>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+) {
+    let is = |binding: &InputBinding, just: bool| match *binding {
+        InputBinding::Key(key) if just => keys.just_pressed(key),
+        InputBinding::Key(key) => keys.pressed(key),
+        InputBinding::Mouse(button) if just => mouse.just_pressed(button),
+        InputBinding::Mouse(button) => mouse.pressed(button),
+        InputBinding::Gamepad(button_type) => gamepads.iter().any(|gamepad| {
+            let button = GamepadButton::new(gamepad, button_type);
+            if just {
+                gamepad_buttons.just_pressed(button)
+            } else {
+                gamepad_buttons.pressed(button)
+            }
+        }),
+    };
+
+    state.pressed.clear();
+    state.just_pressed.clear();
+    for action in InputAction::ALL {
+        let bindings = input_map.bindings(action);
+        if bindings.iter().any(|binding| is(binding, false)) {
+            state.pressed.insert(action);
+        }
+        if bindings.iter().any(|binding| is(binding, true)) {
+            state.just_pressed.insert(action);
+        }
+    }
+
+    let mut stick = Vec2::ZERO;
+    for gamepad in gamepads.iter() {
+        let axis = |axis_type| {
+            gamepad_axes
+                .get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or_default()
+        };
+        let position = Vec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+        if position.length() > STICK_DEAD_ZONE && position.length() > stick.length() {
+            stick = position;
+        }
+    }
+    state.stick = stick.clamp_length_max(1.0);
+
+    let mut movement = Vec2::ZERO;
+    if state.pressed(InputAction::MoveUp) {
+        movement.y += 1.0;
+    }
+    if state.pressed(InputAction::MoveDown) {
+        movement.y -= 1.0;
+    }
+    if state.pressed(InputAction::MoveLeft) {
+        movement.x -= 1.0;
+    }
+    if state.pressed(InputAction::MoveRight) {
+        movement.x += 1.0;
+    }
+    // Normalize so that diagonal movement has the same speed as
+    // horizontal and vertical movement.
+    state.movement = (movement.normalize_or_zero() + state.stick).clamp_length_max(1.0);
+}
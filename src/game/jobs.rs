@@ -0,0 +1,333 @@
+//! Job board.
+//! Device problems post jobs with a priority, and idle crew members claim them
+//! based on their [`WorkPriorities`], the job priority and the distance.
+//! A claimed job is started through [`RequestInteract`], so it ends up as an
+//! [`ActionGroup`](super::sequence::ActionGroup) on the claimant's [`Sequence`]
+//! exactly like an order given with the mouse.
+
+use bevy::prelude::*;
+
+use crate::screen::Screen;
+
+use super::{
+    autopilot::Autopilot,
+    build::{ConstructionSite, SiteWork},
+    components::{fire::InFire, hydroponic::HydroponicState},
+    daycycle::GameTime,
+    durability::Broken,
+    hazard::Hazard,
+    metal_trash::TrashLeft,
+    needs::Need,
+    resources::{self, BadWater, GameResource},
+    selectable::RequestInteract,
+    sequence::Sequence,
+    spawn::{
+        player::Player,
+        spawn_commands::{Toilet, WaterCleaner},
+    },
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<JobBoard>();
+    app.add_systems(
+        Update,
+        (
+            post_jobs,
+            release_jobs,
+            assign_jobs.run_if(|autopilot: Res<Autopilot>| !autopilot.enabled),
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(PostUpdate, reset_jobs);
+}
+
+/// Game seconds before a job that a crew member couldn't finish is claimed again
+const JOB_RETRY_DELAY: f32 = 2.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum WorkType {
+    Firefighting,
+    Farming,
+    Plumbing,
+    Hauling,
+    Building,
+}
+
+impl WorkType {
+    pub const ALL: [WorkType; 5] = [
+        WorkType::Firefighting,
+        WorkType::Farming,
+        WorkType::Plumbing,
+        WorkType::Hauling,
+        WorkType::Building,
+    ];
+
+    pub const fn label(&self) -> &'static str {
+        match self {
+            WorkType::Firefighting => "Firefight",
+            WorkType::Farming => "Farm",
+            WorkType::Plumbing => "Plumb",
+            WorkType::Hauling => "Haul",
+            WorkType::Building => "Build",
+        }
+    }
+
+    const fn index(&self) -> usize {
+        match self {
+            WorkType::Firefighting => 0,
+            WorkType::Farming => 1,
+            WorkType::Plumbing => 2,
+            WorkType::Hauling => 3,
+            WorkType::Building => 4,
+        }
+    }
+}
+
+/// Lowest priority a crew member can give to a [`WorkType`]
+pub const MAX_WORK_PRIORITY: u8 = 3;
+
+/// Per-character permissions for every [`WorkType`].
+/// `1` is the most important, [`MAX_WORK_PRIORITY`] the least and `0` means
+/// the character never takes that work.
+#[derive(Component, Clone, Copy)]
+pub struct WorkPriorities([u8; WorkType::ALL.len()]);
+
+impl Default for WorkPriorities {
+    fn default() -> Self {
+        Self([1, 2, 2, MAX_WORK_PRIORITY, 2])
+    }
+}
+
+impl WorkPriorities {
+    pub fn get(&self, work: WorkType) -> u8 {
+        self.0[work.index()]
+    }
+
+    /// Cycles `1 -> 2 -> .. -> MAX -> disabled -> 1`
+    pub fn cycle(&mut self, work: WorkType) {
+        let priority = &mut self.0[work.index()];
+        *priority = (*priority + 1) % (MAX_WORK_PRIORITY + 1);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub target: Entity,
+    pub work: WorkType,
+    /// Higher is more urgent
+    pub priority: u8,
+    pub claimed_by: Option<Entity>,
+    /// Crew member the job is for, when it answers one of its own needs.
+    /// Only that crew member claims it, whatever its [`WorkPriorities`]
+    pub owner: Option<Entity>,
+    /// Name of the group the claimant is working on, once it has started
+    group: Option<String>,
+    /// Game time before the job can be claimed again
+    retry_at: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct JobBoard {
+    pub jobs: Vec<Job>,
+}
+
+impl JobBoard {
+    fn post(&mut self, target: Entity, work: WorkType, priority: u8, owner: Option<Entity>) {
+        if let Some(job) = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.target == target && job.work == work && job.owner == owner)
+        {
+            job.priority = priority;
+            return;
+        }
+
+        self.jobs.push(Job {
+            target,
+            work,
+            priority,
+            claimed_by: None,
+            owner,
+            group: None,
+            retry_at: 0.0,
+        });
+    }
+
+    /// Job the crew member is working on
+    pub fn claimed_by(&self, crew: Entity) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.claimed_by == Some(crew))
+    }
+}
+
+fn post_jobs(
+    mut board: ResMut<JobBoard>,
+    q_fire: Query<Entity, With<InFire>>,
+    q_hazards: Query<(Entity, &Hazard)>,
+    q_broken: Query<Entity, (With<Broken>, Without<InFire>)>,
+    q_hydroponics: Query<(Entity, &HydroponicState), Without<InFire>>,
+    q_trash: Query<(Entity, &TrashLeft)>,
+    q_cleaners: Query<Entity, (With<WaterCleaner>, Without<InFire>)>,
+    q_toilets: Query<(Entity, &GlobalTransform), (With<Toilet>, Without<InFire>)>,
+    q_crew: Query<(Entity, &Need<resources::Toilet>, &GlobalTransform), With<Player>>,
+    q_sites: Query<(Entity, &ConstructionSite)>,
+    bad_water: Res<BadWater>,
+) {
+    let mut posted = Vec::new();
+
+    for target in q_fire.iter() {
+        posted.push((target, WorkType::Firefighting, 3, None));
+    }
+
+    for (target, hazard) in q_hazards.iter() {
+        posted.push((target, hazard.kind.work_type(), 3, None));
+    }
+
+    for target in q_broken.iter() {
+        posted.push((target, WorkType::Building, 2, None));
+    }
+
+    for (target, state) in q_hydroponics.iter() {
+        match state {
+            HydroponicState::NeedWater(_) => posted.push((target, WorkType::Farming, 2, None)),
+            HydroponicState::Growed => posted.push((target, WorkType::Farming, 1, None)),
+            _ => {}
+        }
+    }
+
+    // Only piles that run out, and not the ones waiting to be rebuilt
+    for (target, trash_left) in q_trash.iter() {
+        let rebuilt = q_sites
+            .iter()
+            .any(|(_, site)| matches!(site.work, SiteWork::Rebuild { pile, .. } if pile == target));
+        if trash_left.0 > 0.0 && !rebuilt {
+            posted.push((target, WorkType::Hauling, 1, None));
+        }
+    }
+
+    for (target, _) in q_sites.iter() {
+        posted.push((target, WorkType::Building, 1, None));
+    }
+
+    // A crew member about to burst gets sent to the nearest toilet
+    for (crew, toilet, crew_transform) in q_crew.iter() {
+        let (_, toilet_warning) = toilet.warning_thresholds();
+        if !toilet_warning.is_some_and(|max| toilet.amount() >= max * 0.5) {
+            continue;
+        }
+        let nearest = q_toilets.iter().min_by(|(_, a), (_, b)| {
+            let a = a.translation().distance(crew_transform.translation());
+            let b = b.translation().distance(crew_transform.translation());
+            a.total_cmp(&b)
+        });
+        if let Some((target, _)) = nearest {
+            posted.push((target, WorkType::Plumbing, 2, Some(crew)));
+        }
+    }
+
+    // The toilets flush into the bad water tank, which needs cleaning once it fills up
+    let (_, bad_water_warning) = bad_water.warning_thresholds();
+    if bad_water_warning.is_some_and(|max| bad_water.amount() >= max * 0.5) {
+        for target in q_cleaners.iter() {
+            posted.push((target, WorkType::Plumbing, 2, None));
+        }
+    }
+
+    board.jobs.retain(|job| {
+        posted.iter().any(|(target, work, _, owner)| {
+            job.target == *target && job.work == *work && job.owner == *owner
+        })
+    });
+    for (target, work, priority, owner) in posted {
+        board.post(target, work, priority, owner);
+    }
+}
+
+/// Frees jobs whose claimant stopped working on them, either because it
+/// finished without solving the problem or because it got another order
+fn release_jobs(
+    mut board: ResMut<JobBoard>,
+    q_crew: Query<&Sequence, With<Player>>,
+    time: Res<GameTime>,
+) {
+    for job in board.jobs.iter_mut() {
+        let Some(crew) = job.claimed_by else {
+            continue;
+        };
+
+        let current_group = q_crew
+            .get(crew)
+            .ok()
+            .and_then(|sequence| sequence.current_group())
+            .map(str::to_string);
+
+        match (&job.group, current_group) {
+            // Wait for the claimed work to show up in the sequence
+            (None, Some(group)) => job.group = Some(group),
+            (Some(group), Some(current)) if *group == current => {}
+            (_, current) => {
+                if current.is_some() {
+                    info!("Job {:?} on {} interrupted", job.work, job.target);
+                }
+                job.claimed_by = None;
+                job.group = None;
+                job.retry_at = time.elapsed_seconds() + JOB_RETRY_DELAY;
+            }
+        }
+    }
+}
+
+fn assign_jobs(
+    mut commands: Commands,
+    mut board: ResMut<JobBoard>,
+    q_crew: Query<(Entity, &Sequence, &WorkPriorities, &GlobalTransform), With<Player>>,
+    q_targets: Query<&GlobalTransform>,
+    time: Res<GameTime>,
+) {
+    for (crew, sequence, priorities, crew_transform) in q_crew.iter() {
+        if !sequence.actions.is_empty() || board.claimed_by(crew).is_some() {
+            continue;
+        }
+
+        let best = board
+            .jobs
+            .iter_mut()
+            .filter(|job| job.claimed_by.is_none() && job.retry_at <= time.elapsed_seconds())
+            .filter(|job| {
+                job.owner
+                    .map_or(priorities.get(job.work) > 0, |owner| owner == crew)
+            })
+            .filter_map(|job| {
+                let target = q_targets.get(job.target).ok()?;
+                let distance = crew_transform.translation().distance(target.translation());
+                Some((job, distance))
+            })
+            .min_by(|(a, a_dist), (b, b_dist)| {
+                priorities
+                    .get(a.work)
+                    .cmp(&priorities.get(b.work))
+                    .then(b.priority.cmp(&a.priority))
+                    .then(a_dist.total_cmp(b_dist))
+            });
+
+        if let Some((job, _)) = best {
+            info!("{} claims {:?} on {}", crew, job.work, job.target);
+            job.claimed_by = Some(crew);
+            commands.trigger_targets(
+                RequestInteract {
+                    actor: crew,
+                    direct: false,
+                    append: false,
+                },
+                job.target,
+            );
+        }
+    }
+}
+
+fn reset_jobs(mut resets: EventReader<ResetGame>, mut board: ResMut<JobBoard>) {
+    for _ in resets.read() {
+        board.jobs.clear();
+    }
+}
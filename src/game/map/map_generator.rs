@@ -1,42 +1,489 @@
-use crate::game::{assets::{HandleMap, SceneKey}, spawn::level::SpawnLevel};
+//! Ship maps.
+//! A layout from a level file only needs a walled rectangle for navigation,
+//! the ship model draws the rooms. A generated apartment is built here from a
+//! seed: the floor is split into rooms, every split gets a door so each room
+//! can be reached, then the life-support devices are placed along the walls.
+//! The same seed always gives the same apartment.
+
 use bevy::prelude::*;
+use rand::{prelude::*, rngs::StdRng};
+
+use crate::game::{
+    components::device_kind::DeviceKind,
+    spawn::layout::{
+        AmbientPlacement, CrewPlacement, DevicePlacement, Level, LightPlacement, StartState,
+    },
+};
 
 use super::*;
 
-pub(crate) fn plugin(app: &mut App) {
-    app.observe(spawn_map);
+/// Size of a generated apartment, outer walls included
+const APARTMENT_SIZE: (usize, usize) = (16, 12);
+/// Smallest side of a room, in floor tiles
+const MIN_ROOM: i32 = 3;
+/// Rooms with a longer side are split again
+const MAX_ROOM: i32 = 6;
+/// Placement tries before giving up on a seed
+const MAX_ATTEMPTS: usize = 32;
+const CREW: [&str; 3] = ["Alice", "Bob", "Carol"];
+const STARTING_METAL: f32 = 60.0;
+
+/// Devices that must be in the same room or in rooms sharing a door
+const NEIGHBOURS: [(DeviceKind, DeviceKind); 2] = [
+    (DeviceKind::Toilet, DeviceKind::WaterCleaner),
+    (DeviceKind::Kitchen, DeviceKind::Hydroponic),
+];
+/// Devices without placement rules
+const SINGLES: [DeviceKind; 3] = [
+    DeviceKind::WaterDispenser,
+    DeviceKind::OxygenRecycler,
+    DeviceKind::Pc,
+];
+
+/// Map with a floor surrounded by walls
+pub fn walled_map(width: usize, height: usize) -> ShipMap {
+    let mut map = ShipMap::new(width, height);
+
+    for x in 0..width {
+        for y in 0..height {
+            let edge = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            map.set(x, y, if edge { Tile::Wall } else { Tile::Floor });
+        }
+    }
+
+    map
+}
+
+/// Floor tiles of a room, bounds included
+#[derive(Clone, Copy, Debug)]
+struct Room {
+    min: IVec2,
+    max: IVec2,
+}
+
+impl Room {
+    fn contains(&self, tile: IVec2) -> bool {
+        tile.cmpge(self.min).all() && tile.cmple(self.max).all()
+    }
+
+    fn size(&self) -> IVec2 {
+        self.max - self.min + IVec2::ONE
+    }
+
+    fn center(&self) -> Vec2 {
+        (self.min + self.max).as_vec2() / 2.0
+    }
+
+    fn tiles(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (self.min.y..=self.max.y)
+            .flat_map(move |y| (self.min.x..=self.max.x).map(move |x| IVec2::new(x, y)))
+    }
+}
+
+/// Opening in the wall between two rooms
+#[derive(Clone, Copy, Debug)]
+struct Door {
+    tile: IVec2,
+    rooms: (usize, usize),
+}
+
+pub struct Apartment {
+    pub map: ShipMap,
+    /// Crew, lights and devices, spawned like a level file
+    pub level: Level,
 }
 
-fn spawn_map(_: Trigger<SpawnLevel>,
-     mut commands: Commands,
-     asset_server: Res<AssetServer>,
-     scene_map: Res<HandleMap<SceneKey>>,
+/// Apartment of the seed, `None` when not even a single room can hold the
+/// crew and the devices
+pub fn generate_apartment(seed: u64) -> Option<Apartment> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for _ in 0..MAX_ATTEMPTS {
+        if let Some(apartment) = try_generate(&mut rng) {
+            return Some(apartment);
+        }
+    }
+
+    // Only reachable with a tiny apartment size, furnish one big room instead
+    warn!("No apartment could be split into rooms with seed {}", seed);
+    let (width, height) = APARTMENT_SIZE;
+    furnish_rooms(walled_map(width, height), vec![outline()], &[], &mut rng)
+}
+
+/// Floor inside the outer walls
+const fn outline() -> Room {
+    let (width, height) = APARTMENT_SIZE;
+    Room {
+        min: IVec2::ONE,
+        max: IVec2::new(width as i32 - 2, height as i32 - 2),
+    }
+}
+
+fn try_generate(rng: &mut StdRng) -> Option<Apartment> {
+    let (width, height) = APARTMENT_SIZE;
+    let mut map = walled_map(width, height);
+    let mut rooms = Vec::new();
+    let mut doors = Vec::new();
+    split(&mut map, outline(), rng, &mut rooms, &mut doors);
+
+    furnish_rooms(map, rooms, &doors, rng)
+}
+
+/// Places the devices and the crew in the rooms of the map
+fn furnish_rooms(
+    map: ShipMap,
+    rooms: Vec<Room>,
+    doors: &[Door],
+    rng: &mut StdRng,
+) -> Option<Apartment> {
+    let devices = place_devices(&rooms, doors, rng)?;
+    let blocked: Vec<IVec2> = devices.iter().map(|(_, tile, _)| *tile).collect();
+
+    // Crew starts in the biggest room, away from the devices
+    let start_room = rooms
+        .iter()
+        .max_by_key(|room| room.size().x * room.size().y)?;
+    let mut free: Vec<IVec2> = start_room
+        .tiles()
+        .filter(|tile| !blocked.contains(tile))
+        .collect();
+    let center = start_room.center();
+    free.sort_by(|a, b| {
+        let a = a.as_vec2().distance_squared(center);
+        let b = b.as_vec2().distance_squared(center);
+        a.total_cmp(&b)
+    });
+    if free.len() < CREW.len() {
+        return None;
+    }
+    let crew: Vec<IVec2> = free[..CREW.len()].to_vec();
+
+    if !is_connected(&map, &blocked, crew[0]) {
+        return None;
+    }
+
+    let level = furnish(&rooms, crew, devices);
+    Some(Apartment { map, level })
+}
+
+/// Binary space partition of `rect`. Each split adds a wall with one door,
+/// joining the two halves, so every room ends up connected to the others.
+fn split(
+    map: &mut ShipMap,
+    rect: Room,
+    rng: &mut StdRng,
+    rooms: &mut Vec<Room>,
+    doors: &mut Vec<Door>,
 ) {
-    let ship_scene = "models/my_ship.glb#Scene0";
+    let size = rect.size();
+    let can_split_x = size.x > 2 * MIN_ROOM;
+    let can_split_y = size.y > 2 * MIN_ROOM;
+    let too_big = size.x > MAX_ROOM || size.y > MAX_ROOM;
+    if !too_big || !(can_split_x || can_split_y) {
+        rooms.push(rect);
+        return;
+    }
+
+    let along_x = match (can_split_x, can_split_y) {
+        (true, true) if size.x == size.y => rng.gen_bool(0.5),
+        (true, true) => size.x > size.y,
+        (can_split_x, _) => can_split_x,
+    };
+
+    // `axis` is the coordinate the wall is placed at
+    let (axis, other) = if along_x { (0, 1) } else { (1, 0) };
+    let at = rng.gen_range(rect.min[axis] + MIN_ROOM..=rect.max[axis] - MIN_ROOM);
 
-    commands.spawn(SceneBundle {
-        scene: scene_map.get(&SceneKey::Ship).unwrap().clone_weak(),
-        transform: Transform::from_translation(Vec3::new(3.5, -0.2, 3.5)),
-        ..default()
+    let mut first = rect;
+    first.max[axis] = at - 1;
+    let mut second = rect;
+    second.min[axis] = at + 1;
+
+    for i in rect.min[other]..=rect.max[other] {
+        let mut tile = IVec2::ZERO;
+        tile[axis] = at;
+        tile[other] = i;
+        map.set(tile.x as usize, tile.y as usize, Tile::Wall);
+    }
+
+    split(map, first, rng, rooms, doors);
+    split(map, second, rng, rooms, doors);
+
+    // The door needs a room on both sides, walls of the halves may end there
+    let mut step = IVec2::ZERO;
+    step[axis] = 1;
+    let candidates: Vec<Door> = (rect.min[other]..=rect.max[other])
+        .filter_map(|i| {
+            let mut tile = IVec2::ZERO;
+            tile[axis] = at;
+            tile[other] = i;
+            let rooms = (room_at(rooms, tile - step)?, room_at(rooms, tile + step)?);
+            Some(Door { tile, rooms })
+        })
+        .collect();
+    let door = *candidates
+        .choose(rng)
+        .expect("rooms are at least MIN_ROOM wide");
+    map.set(
+        door.tile.x as usize,
+        door.tile.y as usize,
+        Tile::Door { open: true },
+    );
+    doors.push(door);
+}
+
+fn room_at(rooms: &[Room], tile: IVec2) -> Option<usize> {
+    rooms.iter().position(|room| room.contains(tile))
+}
+
+/// Floor tiles against a wall where a device doesn't get in the way.
+/// Corners are skipped so the device can always be reached from the room.
+fn wall_spots(room: &Room, doors: &[Door]) -> Vec<(IVec2, IVec2)> {
+    room.tiles()
+        .filter_map(|tile| {
+            let on_x = tile.x == room.min.x || tile.x == room.max.x;
+            let on_y = tile.y == room.min.y || tile.y == room.max.y;
+            if on_x == on_y {
+                return None;
+            }
+            let near_door = doors
+                .iter()
+                .any(|door| (door.tile - tile).abs().element_sum() <= 1);
+            if near_door {
+                return None;
+            }
+            // Direction the device faces, towards the middle of the room
+            let facing = if on_x {
+                IVec2::new(if tile.x == room.min.x { 1 } else { -1 }, 0)
+            } else {
+                IVec2::new(0, if tile.y == room.min.y { 1 } else { -1 })
+            };
+            Some((tile, facing))
+        })
+        .collect()
+}
+
+type Placed = (DeviceKind, IVec2, IVec2);
+
+fn place_devices(rooms: &[Room], doors: &[Door], rng: &mut StdRng) -> Option<Vec<Placed>> {
+    let mut spots: Vec<Vec<(IVec2, IVec2)>> = rooms
+        .iter()
+        .map(|room| {
+            let mut spots = wall_spots(room, doors);
+            spots.shuffle(rng);
+            spots
+        })
+        .collect();
+    let neighbours = |room: usize| -> Vec<usize> {
+        doors
+            .iter()
+            .filter_map(|door| match door.rooms {
+                (a, b) if a == room => Some(b),
+                (a, b) if b == room => Some(a),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let mut placed = Vec::new();
+    let mut order: Vec<usize> = (0..rooms.len()).collect();
+
+    for (first, second) in NEIGHBOURS {
+        order.shuffle(rng);
+        // Same room first, then a room behind one of its doors
+        let same_room = order.iter().copied().find(|&i| spots[i].len() >= 2);
+        let (room_a, room_b) = match same_room {
+            Some(room) => (room, room),
+            None => order.iter().copied().find_map(|i| {
+                let other = neighbours(i).into_iter().find(|&j| !spots[j].is_empty())?;
+                (!spots[i].is_empty()).then_some((i, other))
+            })?,
+        };
+        let (tile, facing) = spots[room_a].pop()?;
+        placed.push((first, tile, facing));
+        let (tile, facing) = spots[room_b].pop()?;
+        placed.push((second, tile, facing));
+    }
+
+    for kind in SINGLES {
+        // Spread the remaining devices over the emptiest rooms
+        order.shuffle(rng);
+        let room = order
+            .iter()
+            .copied()
+            .filter(|&i| !spots[i].is_empty())
+            .min_by_key(|&i| {
+                placed
+                    .iter()
+                    .filter(|(_, tile, _)| rooms[i].contains(*tile))
+                    .count()
+            })?;
+        let (tile, facing) = spots[room].pop()?;
+        placed.push((kind, tile, facing));
+    }
+
+    Some(placed)
+}
+
+/// All the free floor can be reached from `start`, and every device has free
+/// floor next to it to be used from
+fn is_connected(map: &ShipMap, blocked: &[IVec2], start: IVec2) -> bool {
+    let free = |tile: IVec2| map.is_walkable(tile) && !blocked.contains(&tile);
+    let directions = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+    let mut reached = vec![false; map.width() * map.height()];
+    let index = |tile: IVec2| tile.y as usize * map.width() + tile.x as usize;
+    let mut stack = vec![start];
+    reached[index(start)] = true;
+    while let Some(tile) = stack.pop() {
+        for direction in directions {
+            let next = tile + direction;
+            if free(next) && !reached[index(next)] {
+                reached[index(next)] = true;
+                stack.push(next);
+            }
+        }
+    }
+
+    let all_floor = (0..map.height() as i32)
+        .flat_map(|y| (0..map.width() as i32).map(move |x| IVec2::new(x, y)))
+        .filter(|tile| free(*tile))
+        .all(|tile| reached[index(tile)]);
+    let usable = blocked.iter().all(|device| {
+        directions.iter().any(|direction| {
+            let next = *device + *direction;
+            free(next) && reached[index(next)]
+        })
     });
 
-    // let mut map = ShipMap::new(10, 10);
-
-    // for x in 0..map.width() {
-    //     for y in 0..map.height() {
-    //         map.set(x, y, Tile::Floor);
-    //     }
-    // }
-
-    // //fill edges with wall
-    // for x in 0..map.width() {
-    //     map.set(x, 0, Tile::Wall);
-    //     map.set(x, map.height() - 1, Tile::Wall);
-    // }
-    // for y in 0..map.height() {
-    //     map.set(0, y, Tile::Wall);
-    //     map.set(map.width() - 1, y, Tile::Wall);
-    // }
-
-    // commands.spawn((SpatialBundle::default(), map));
+    all_floor && usable
+}
+
+/// Level description of the generated apartment
+fn furnish(rooms: &[Room], crew: Vec<IVec2>, devices: Vec<Placed>) -> Level {
+    let on_floor = |tile: IVec2, height: f32| [tile.x as f32, height, tile.y as f32];
+
+    let lights = rooms
+        .iter()
+        .map(|room| {
+            let center = room.center();
+            let radius = room.size().max_element() as f32 / 2.0;
+            let outer_angle = (radius / 15.0).atan().to_degrees();
+            LightPlacement {
+                position: [center.x, 15.0, center.y],
+                inner_angle: outer_angle * 0.7,
+                outer_angle,
+            }
+        })
+        .collect();
+
+    let crew = crew
+        .into_iter()
+        .zip(CREW)
+        .map(|(tile, name)| CrewPlacement {
+            name: name.to_string(),
+            position: on_floor(tile, 0.0),
+        })
+        .collect();
+
+    let devices = devices
+        .into_iter()
+        .map(|(kind, tile, facing)| DevicePlacement {
+            kind,
+            position: on_floor(tile, kind.floor_height()),
+            rotation: (facing.x as f32).atan2(facing.y as f32).to_degrees(),
+            scale: None,
+            state: StartState::Working,
+            components: default(),
+        })
+        .collect();
+
+    Level {
+        ship: None,
+        map_size: APARTMENT_SIZE,
+        ambient_light: Some(AmbientPlacement {
+            brightness: 80.0,
+            color: [0.9, 0.9, 1.0],
+        }),
+        lights,
+        crew,
+        devices,
+        tanks: Vec::new(),
+        metal: STARTING_METAL,
+        stock: default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: std::ops::Range<u64> = 0..64;
+
+    fn tile_of(position: [f32; 3]) -> IVec2 {
+        IVec2::new(position[0].round() as i32, position[2].round() as i32)
+    }
+
+    fn walkable(map: &ShipMap) -> Vec<bool> {
+        (0..map.height() as i32)
+            .flat_map(|y| (0..map.width() as i32).map(move |x| IVec2::new(x, y)))
+            .map(|tile| map.is_walkable(tile))
+            .collect()
+    }
+
+    fn assert_furnished(level: &Level) {
+        let kinds: Vec<DeviceKind> = level.devices.iter().map(|device| device.kind).collect();
+        let required = NEIGHBOURS
+            .iter()
+            .flat_map(|(first, second)| [*first, *second])
+            .chain(SINGLES);
+        for kind in required {
+            assert!(kinds.contains(&kind), "{:?} is missing", kind);
+        }
+        assert_eq!(level.crew.len(), CREW.len());
+    }
+
+    #[test]
+    fn same_seed_gives_same_apartment() {
+        for seed in SEEDS {
+            let first = generate_apartment(seed).expect("apartment");
+            let second = generate_apartment(seed).expect("apartment");
+            assert_eq!(format!("{:?}", first.level), format!("{:?}", second.level));
+            assert_eq!(walkable(&first.map), walkable(&second.map));
+        }
+    }
+
+    #[test]
+    fn every_required_device_is_placed() {
+        for seed in SEEDS {
+            let apartment = generate_apartment(seed).expect("apartment");
+            assert_furnished(&apartment.level);
+        }
+    }
+
+    #[test]
+    fn apartment_is_connected() {
+        for seed in SEEDS {
+            let apartment = generate_apartment(seed).expect("apartment");
+            let blocked: Vec<IVec2> = apartment
+                .level
+                .devices
+                .iter()
+                .map(|device| tile_of(device.position))
+                .collect();
+            for crew in &apartment.level.crew {
+                let start = tile_of(crew.position);
+                assert!(is_connected(&apartment.map, &blocked, start));
+            }
+        }
+    }
+
+    #[test]
+    fn single_room_fallback_is_playable() {
+        let (width, height) = APARTMENT_SIZE;
+        let mut rng = StdRng::seed_from_u64(0);
+        let apartment = furnish_rooms(walled_map(width, height), vec![outline()], &[], &mut rng)
+            .expect("one big room fits everything");
+        assert_furnished(&apartment.level);
+    }
 }
@@ -1,24 +1,65 @@
 use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+
+use navigation::Path;
 
 pub mod map_generator;
+pub mod navigation;
 
 pub(crate) fn plugin(app: &mut App) {
     app.add_systems(Update, update_map);
-    app.add_plugins(map_generator::plugin);
+    app.add_plugins(navigation::plugin);
 }
 
 #[derive(Clone)]
 pub enum Tile {
     Wall,
     Floor,
+    /// Splits rooms, closed doors stop characters and air
+    Door {
+        open: bool,
+    },
+    /// Shaft linking the same tile on every floor
+    Elevator,
     Nothing,
 }
 
+/// Height between two floors of the apartment
+pub const STOREY_HEIGHT: f32 = 3.0;
+
+/// Floor of the apartment a position is on, the ground floor is `0`
+pub fn storey_of(position: Vec3) -> usize {
+    (position.y / STOREY_HEIGHT).round().max(0.0) as usize
+}
+
+/// Height of the ground of a floor
+pub const fn storey_height(storey: usize) -> f32 {
+    storey as f32 * STOREY_HEIGHT
+}
+
+/// Map of the floor among all the maps
+pub fn on_storey<'a>(
+    maps: impl IntoIterator<Item = &'a ShipMap>,
+    storey: usize,
+) -> Option<&'a ShipMap> {
+    maps.into_iter().find(|map| map.storey == storey)
+}
+
+/// Door of a drawn map, clicking it opens or closes it
+#[derive(Component)]
+struct DoorTile(IVec2);
+
+/// Map that is only used for navigation, the level model draws the ship.
+/// Only the elevator, which isn't part of the model, is drawn
+#[derive(Component)]
+pub struct HiddenMap;
+
 #[derive(Component)]
 pub struct ShipMap {
     tiles: Vec<Tile>,
     width: usize,
     height: usize,
+    storey: usize,
 }
 
 impl ShipMap {
@@ -27,9 +68,20 @@ impl ShipMap {
             tiles: vec![Tile::Nothing; width * height],
             width,
             height,
+            storey: 0,
         }
     }
 
+    /// Same map on another floor
+    pub const fn on_storey(mut self, storey: usize) -> Self {
+        self.storey = storey;
+        self
+    }
+
+    pub const fn storey(&self) -> usize {
+        self.storey
+    }
+
     pub const fn width(&self) -> usize {
         self.width
     }
@@ -45,16 +97,77 @@ impl ShipMap {
     pub fn set(&mut self, x: usize, y: usize, tile: Tile) {
         self.tiles[y * self.width + x] = tile
     }
+
+    /// Tile at the grid position, `None` outside of the map
+    pub fn get(&self, tile: IVec2) -> Option<&Tile> {
+        if tile.x < 0 || tile.y < 0 {
+            return None;
+        }
+        let (x, y) = (tile.x as usize, tile.y as usize);
+        (x < self.width && y < self.height).then(|| self.tile(x, y))
+    }
+
+    /// Characters can stand on the tile. Tiles outside of the map are not walkable.
+    pub fn is_walkable(&self, tile: IVec2) -> bool {
+        matches!(
+            self.get(tile),
+            Some(Tile::Floor | Tile::Door { open: true } | Tile::Elevator)
+        )
+    }
+
+    /// Tile of the elevator shaft, if the floor has one
+    pub fn elevator(&self) -> Option<IVec2> {
+        let index = self
+            .tiles
+            .iter()
+            .position(|tile| matches!(tile, Tile::Elevator))?;
+        Some(IVec2::new(
+            (index % self.width) as i32,
+            (index / self.width) as i32,
+        ))
+    }
+
+    /// Opens a closed door and closes an open one
+    pub fn toggle_door(&mut self, tile: IVec2) {
+        if let Some(Tile::Door { open }) = self.get(tile) {
+            let open = !*open;
+            self.set(tile.x as usize, tile.y as usize, Tile::Door { open });
+        }
+    }
+}
+
+fn toggle_door(
+    event: Listener<Pointer<Click>>,
+    mut commands: Commands,
+    q_doors: Query<(&DoorTile, &Parent)>,
+    mut q_maps: Query<&mut ShipMap, Without<HiddenMap>>,
+    q_travelers: Query<Entity, With<Path>>,
+) {
+    if event.button != PointerButton::Primary {
+        return;
+    }
+    let Ok((DoorTile(tile), parent)) = q_doors.get(event.listener()) else {
+        return;
+    };
+    let Ok(mut map) = q_maps.get_mut(parent.get()) else {
+        return;
+    };
+
+    map.toggle_door(*tile);
+    // Paths may go through a door that just closed
+    for entity in q_travelers.iter() {
+        commands.entity(entity).remove::<Path>();
+    }
 }
 
 fn update_map(
     mut commands: Commands,
-    mut q_maps: Query<(Entity, &ShipMap, Option<&Children>), Changed<ShipMap>>,
+    mut q_maps: Query<(Entity, &ShipMap, Option<&Children>, Has<HiddenMap>), Changed<ShipMap>>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     asset_server: Res<AssetServer>,
 ) {
-    for (entity, map, children) in &mut q_maps {
+    for (entity, map, children, hidden) in &mut q_maps {
         info!("Updating map {}", entity);
 
         let cube = meshes.add(Cuboid::new(1.0, 2.0, 1.0));
@@ -84,6 +197,35 @@ fn update_map(
             ..default()
         };
 
+        let elevator_pbr = PbrBundle {
+            mesh: meshes.add(Cuboid::new(0.9, 0.1, 0.9)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::linear_rgb(0.3, 0.4, 0.5),
+                metallic: 1.0,
+                ..default()
+            }),
+            ..default()
+        };
+
+        let shaft_pbr = PbrBundle {
+            mesh: meshes.add(Cuboid::new(1.0, STOREY_HEIGHT, 1.0)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::linear_rgba(0.5, 0.6, 0.7, 0.3),
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            }),
+            ..default()
+        };
+
+        let door_pbr = PbrBundle {
+            mesh: meshes.add(Cuboid::new(1.0, 1.9, 0.9)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::linear_rgb(0.4, 0.25, 0.1),
+                ..default()
+            }),
+            ..default()
+        };
+
         // Despawn old children
         if let Some(children) = children {
             for child in children.iter() {
@@ -95,6 +237,9 @@ fn update_map(
         for y in 0..map.height {
             for x in 0..map.width {
                 let tile = &map.tiles[y * map.width + x];
+                if hidden && !matches!(tile, Tile::Elevator) {
+                    continue;
+                }
                 match tile {
                     Tile::Wall => {
                         let id = commands
@@ -115,6 +260,48 @@ fn update_map(
 
                         commands.entity(entity).add_child(id);
                     }
+                    Tile::Door { open } => {
+                        let (pbr, height) = if *open {
+                            (floor_pbr.clone(), 0.0)
+                        } else {
+                            (door_pbr.clone(), 0.95)
+                        };
+                        let id = commands
+                            .spawn(pbr)
+                            .insert(Transform::from_translation(Vec3::new(
+                                x as f32, height, y as f32,
+                            )))
+                            .insert((
+                                DoorTile(IVec2::new(x as i32, y as i32)),
+                                On::<Pointer<Click>>::run(toggle_door),
+                            ))
+                            .id();
+
+                        commands.entity(entity).add_child(id);
+                    }
+                    Tile::Elevator => {
+                        let id = commands
+                            .spawn(elevator_pbr.clone())
+                            .insert(Transform::from_translation(Vec3::new(
+                                x as f32, 0.0, y as f32,
+                            )))
+                            .id();
+                        commands.entity(entity).add_child(id);
+
+                        // The shaft goes down to the floor below
+                        if map.storey > 0 {
+                            let id = commands
+                                .spawn(shaft_pbr.clone())
+                                .insert(Transform::from_translation(Vec3::new(
+                                    x as f32,
+                                    -STOREY_HEIGHT / 2.0,
+                                    y as f32,
+                                )))
+                                .insert(Pickable::IGNORE)
+                                .id();
+                            commands.entity(entity).add_child(id);
+                        }
+                    }
                     Tile::Nothing => {}
                 }
             }
@@ -0,0 +1,366 @@
+//! A* pathfinding over the [`ShipMap`] tile grid.
+//! Every [`Selectable`] device blocks the tile it stands on. Characters with a
+//! [`DestinationTarget`] get a [`Path`] to the closest free tile next to the
+//! target, which is recomputed whenever a device is built or destroyed.
+//! Targets on another floor are reached through the elevator shaft.
+
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::game::{character::DestinationTarget, selectable::Selectable, sequence::ClearSequence};
+
+use super::{on_storey, storey_height, storey_of, ShipMap};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<NavBlockers>();
+    app.add_systems(
+        Update,
+        (update_blockers, plan_paths)
+            .chain()
+            .before(crate::game::character::move_player_to_target),
+    );
+}
+
+/// Distance to a waypoint at which it counts as reached
+const WAYPOINT_RADIUS: f32 = 0.05;
+/// Step used to check the line of sight between two points, in tiles
+const SIGHT_STEP: f32 = 0.25;
+
+const NEIGHBOURS: [IVec2; 8] = [
+    IVec2::new(1, 0),
+    IVec2::new(-1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(0, -1),
+    IVec2::new(1, 1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, 1),
+    IVec2::new(-1, -1),
+];
+
+/// Tiles occupied by devices, with their floor
+#[derive(Resource, Default)]
+pub struct NavBlockers {
+    tiles: HashMap<Entity, (usize, IVec2)>,
+    /// Devices on every blocked tile, several can share one
+    blocked: HashMap<(usize, IVec2), usize>,
+}
+
+impl NavBlockers {
+    pub fn is_blocked(&self, storey: usize, tile: IVec2) -> bool {
+        self.blocked.contains_key(&(storey, tile))
+    }
+
+    /// Moves the device to the tile, `false` if it was already there
+    fn insert(&mut self, entity: Entity, tile: (usize, IVec2)) -> bool {
+        let old = self.tiles.insert(entity, tile);
+        if old == Some(tile) {
+            return false;
+        }
+        if let Some(old) = old {
+            self.unblock(old);
+        }
+        *self.blocked.entry(tile).or_default() += 1;
+        true
+    }
+
+    /// Forgets the device, `false` if it wasn't blocking anything
+    fn remove(&mut self, entity: Entity) -> bool {
+        let Some(tile) = self.tiles.remove(&entity) else {
+            return false;
+        };
+        self.unblock(tile);
+        true
+    }
+
+    fn unblock(&mut self, tile: (usize, IVec2)) {
+        if let Some(count) = self.blocked.get_mut(&tile) {
+            *count -= 1;
+            if *count == 0 {
+                self.blocked.remove(&tile);
+            }
+        }
+    }
+}
+
+/// Waypoints left to reach the [`DestinationTarget`]
+#[derive(Component, Default)]
+pub struct Path {
+    waypoints: Vec<Vec3>,
+}
+
+impl Path {
+    /// Next point to walk to
+    pub fn next(&self) -> Option<Vec3> {
+        self.waypoints.first().copied()
+    }
+
+    /// Drops the next waypoint once the character stands on it
+    pub fn advance(&mut self, position: Vec3) {
+        if let Some(next) = self.next() {
+            if position.distance(next) <= WAYPOINT_RADIUS {
+                self.waypoints.remove(0);
+            }
+        }
+    }
+
+    /// The character reached an interaction tile of its target
+    pub fn is_finished(&self) -> bool {
+        self.waypoints.is_empty()
+    }
+}
+
+pub fn world_to_tile(pos: Vec3) -> IVec2 {
+    IVec2::new(pos.x.round() as i32, pos.z.round() as i32)
+}
+
+fn update_blockers(
+    mut commands: Commands,
+    mut blockers: ResMut<NavBlockers>,
+    q_moved: Query<(Entity, &GlobalTransform), (With<Selectable>, Changed<GlobalTransform>)>,
+    mut removed: RemovedComponents<Selectable>,
+    q_travelers: Query<Entity, With<DestinationTarget>>,
+) {
+    let mut changed = false;
+
+    for entity in removed.read() {
+        changed |= blockers.remove(entity);
+    }
+
+    // Devices from glTF scenes only know their place once the transforms propagated
+    for (entity, transform) in q_moved.iter() {
+        let position = transform.translation();
+        let tile = (storey_of(position), world_to_tile(position));
+        changed |= blockers.insert(entity, tile);
+    }
+
+    // Paths through the old layout may be blocked or not the shortest anymore
+    if changed {
+        for entity in q_travelers.iter() {
+            commands.entity(entity).remove::<Path>();
+        }
+    }
+}
+
+fn plan_paths(
+    mut commands: Commands,
+    q_maps: Query<&ShipMap>,
+    blockers: Res<NavBlockers>,
+    q_travelers: Query<(Entity, &Transform, &DestinationTarget), Without<Path>>,
+) {
+    for (entity, transform, target) in q_travelers.iter() {
+        let start = transform.translation;
+        let tiles = route(&q_maps, &blockers, start, target.target_pos);
+
+        let Some(waypoints) = tiles else {
+            // Walking straight would go through walls, the order can't be done
+            warn!("No path to {}, giving up", target.target);
+            commands.entity(entity).remove::<DestinationTarget>();
+            commands.trigger_targets(ClearSequence, entity);
+            continue;
+        };
+
+        commands.entity(entity).insert(Path { waypoints });
+    }
+}
+
+/// Waypoints from `start` to a free tile next to `target`, riding the
+/// elevator when the target is on another floor
+fn route(
+    q_maps: &Query<&ShipMap>,
+    blockers: &NavBlockers,
+    start: Vec3,
+    target: Vec3,
+) -> Option<Vec<Vec3>> {
+    let start_map = on_storey(q_maps.iter(), storey_of(start))?;
+    let target_map = on_storey(q_maps.iter(), storey_of(target))?;
+    let goals = interaction_tiles(target_map, blockers, world_to_tile(target));
+
+    if start_map.storey() == target_map.storey() {
+        return find_path(start_map, blockers, world_to_tile(start), &goals)
+            .map(|tiles| smooth_path(start_map, blockers, start, &tiles));
+    }
+
+    // Both floors share the same shaft
+    let shaft = start_map
+        .elevator()
+        .filter(|shaft| target_map.elevator() == Some(*shaft))?;
+    let mut waypoints = find_path(start_map, blockers, world_to_tile(start), &[shaft])
+        .map(|tiles| smooth_path(start_map, blockers, start, &tiles))?;
+
+    let arrival = Vec3::new(
+        shaft.x as f32,
+        start.y - storey_height(start_map.storey()) + storey_height(target_map.storey()),
+        shaft.y as f32,
+    );
+    waypoints.push(arrival);
+    waypoints.extend(
+        find_path(target_map, blockers, shaft, &goals)
+            .map(|tiles| smooth_path(target_map, blockers, arrival, &tiles))?,
+    );
+
+    Some(waypoints)
+}
+
+/// Free tiles next to `target` from which a device can be used
+pub fn interaction_tiles(map: &ShipMap, blockers: &NavBlockers, target: IVec2) -> Vec<IVec2> {
+    if is_free(map, blockers, target) {
+        return vec![target];
+    }
+
+    NEIGHBOURS
+        .iter()
+        .map(|offset| target + *offset)
+        .filter(|tile| is_free(map, blockers, *tile))
+        .collect()
+}
+
+pub fn is_free(map: &ShipMap, blockers: &NavBlockers, tile: IVec2) -> bool {
+    map.is_walkable(tile) && !blockers.is_blocked(map.storey(), tile)
+}
+
+fn octile(a: IVec2, b: IVec2) -> f32 {
+    let d = (a - b).abs();
+    let (min, max) = (d.x.min(d.y) as f32, d.x.max(d.y) as f32);
+    max - min + min * std::f32::consts::SQRT_2
+}
+
+#[derive(PartialEq)]
+struct OpenTile {
+    cost: f32,
+    tile: IVec2,
+}
+
+impl Eq for OpenTile {}
+
+impl Ord for OpenTile {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so the heap pops the cheapest tile first
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for OpenTile {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A* from `start` to the closest of `goals`. Diagonal moves can't cut corners.
+pub fn find_path(
+    map: &ShipMap,
+    blockers: &NavBlockers,
+    start: IVec2,
+    goals: &[IVec2],
+) -> Option<Vec<IVec2>> {
+    if goals.is_empty() {
+        return None;
+    }
+
+    let heuristic = |tile: IVec2| {
+        goals
+            .iter()
+            .map(|goal| octile(tile, *goal))
+            .fold(f32::INFINITY, f32::min)
+    };
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<IVec2, IVec2> = HashMap::default();
+    let mut costs: HashMap<IVec2, f32> = HashMap::default();
+    let mut closed: HashSet<IVec2> = HashSet::default();
+
+    costs.insert(start, 0.0);
+    open.push(OpenTile {
+        cost: heuristic(start),
+        tile: start,
+    });
+
+    while let Some(OpenTile { tile, .. }) = open.pop() {
+        if goals.contains(&tile) {
+            let mut path = vec![tile];
+            let mut current = tile;
+            while let Some(previous) = came_from.get(&current) {
+                current = *previous;
+                path.push(current);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        if !closed.insert(tile) {
+            continue;
+        }
+
+        for offset in NEIGHBOURS {
+            let next = tile + offset;
+            if !is_free(map, blockers, next) {
+                continue;
+            }
+
+            let diagonal = offset.x != 0 && offset.y != 0;
+            if diagonal
+                && (!is_free(map, blockers, tile + IVec2::new(offset.x, 0))
+                    || !is_free(map, blockers, tile + IVec2::new(0, offset.y)))
+            {
+                continue;
+            }
+
+            let step = if diagonal {
+                std::f32::consts::SQRT_2
+            } else {
+                1.0
+            };
+            let cost = costs[&tile] + step;
+            if costs.get(&next).is_some_and(|old| *old <= cost) {
+                continue;
+            }
+
+            costs.insert(next, cost);
+            came_from.insert(next, tile);
+            open.push(OpenTile {
+                cost: cost + heuristic(next),
+                tile: next,
+            });
+        }
+    }
+
+    None
+}
+
+/// Drops the tiles that can be skipped by walking in a straight line
+fn smooth_path(map: &ShipMap, blockers: &NavBlockers, start: Vec3, tiles: &[IVec2]) -> Vec<Vec3> {
+    let points: Vec<Vec3> = tiles
+        .iter()
+        .map(|tile| Vec3::new(tile.x as f32, start.y, tile.y as f32))
+        .collect();
+
+    let mut waypoints = Vec::new();
+    let mut from = start;
+    let mut i = 0;
+    while i < points.len() {
+        // Farthest point visible from the current one
+        let mut j = points.len() - 1;
+        while j > i && !line_of_sight(map, blockers, from, points[j]) {
+            j -= 1;
+        }
+
+        waypoints.push(points[j]);
+        from = points[j];
+        i = j + 1;
+    }
+
+    waypoints
+}
+
+fn line_of_sight(map: &ShipMap, blockers: &NavBlockers, from: Vec3, to: Vec3) -> bool {
+    let distance = from.xz().distance(to.xz());
+    let steps = (distance / SIGHT_STEP).ceil() as usize;
+
+    (1..steps).all(|step| {
+        let point = from.lerp(to, step as f32 / steps as f32);
+        is_free(map, blockers, world_to_tile(point))
+    })
+}
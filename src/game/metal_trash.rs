@@ -12,6 +12,7 @@ use super::{
 };
 
 pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<TrashLeft>();
     app.insert_resource(GatherMetalTrashWorkConfig {
         work_time: 0.25,
         amount_after_work: 5.0,
@@ -29,23 +30,37 @@ pub struct GatherMetalTrashWorkConfig {
     pub last_updated: f32,
 }
 
-#[derive(Component, Default)]
+#[derive(Component)]
 pub struct GatherMetalTrashWork {
+    pub pile: Entity,
     pub work_time: f32,
 }
 
-pub struct GatherMetalWorkAction;
+/// Metal trash left in the pile of a destroyed device. The pile is gone once
+/// it is gathered up, piles without it never run out.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct TrashLeft(pub f32);
+
+pub struct GatherMetalWorkAction {
+    pub pile: Entity,
+}
 
 impl CharacterAction for GatherMetalWorkAction {
     fn trigger_start(&self, commands: &mut Commands, target: Entity) {
-        commands
-            .entity(target)
-            .insert(GatherMetalTrashWork::default());
+        commands.entity(target).insert(GatherMetalTrashWork {
+            pile: self.pile,
+            work_time: 0.0,
+        });
     }
 
     fn terminate(&self, commands: &mut Commands, target: Entity) {
         commands.entity(target).remove::<GatherMetalTrashWork>();
     }
+
+    fn target(&self) -> Option<Entity> {
+        Some(self.pile)
+    }
 }
 
 fn update_gather_metal_work(
@@ -54,9 +69,15 @@ fn update_gather_metal_work(
     mut metal_config: ResMut<GatherMetalTrashWorkConfig>,
     mut q_gather_metal_work: Query<(Entity, &mut GatherMetalTrashWork, &mut CharacterStates)>,
     mut metal_trash: ResMut<MetalTrash>,
-    q_metal_trash: Query<&GlobalTransform, With<MetalTrashPile>>,
+    mut q_metal_trash: Query<(&GlobalTransform, Option<&mut TrashLeft>), With<MetalTrashPile>>,
 ) {
     for (entity, mut gather_metal_work, mut states) in q_gather_metal_work.iter_mut() {
+        let Ok((pile_transform, trash_left)) = q_metal_trash.get_mut(gather_metal_work.pile) else {
+            // Someone else gathered the last of it
+            commands.entity(entity).remove::<GatherMetalTrashWork>();
+            commands.trigger_targets(NextAction, entity);
+            continue;
+        };
         states.add(CharState::Working);
 
         gather_metal_work.work_time += time.delta_seconds();
@@ -73,31 +94,37 @@ fn update_gather_metal_work(
                 "Metal trash increased by {}",
                 metal_config.amount_after_work
             );
-            let metal_trash_collected =
+            let mut metal_trash_collected =
                 metal_config.amount_after_work * metal_config.multiplier as f32;
+            if let Some(mut trash_left) = trash_left {
+                metal_trash_collected = metal_trash_collected.min(trash_left.0);
+                trash_left.0 -= metal_trash_collected;
+                if trash_left.0 <= 0.0 {
+                    info!("Metal trash pile gathered up");
+                    commands.entity(gather_metal_work.pile).despawn_recursive();
+                }
+            }
             metal_trash.amount += metal_trash_collected;
 
             commands.entity(entity).remove::<GatherMetalTrashWork>();
             commands.trigger_targets(NextAction, entity);
 
-            if let Ok(pc_transform) = q_metal_trash.get_single() {
-                let text_style = TextStyle {
-                    color: Color::linear_rgb(0.0, 1.0, 0.0),
-                    font_size: 94.0,
+            let text_style = TextStyle {
+                color: Color::linear_rgb(0.0, 1.0, 0.0),
+                font_size: 94.0,
+                ..default()
+            };
+            commands
+                .spawn(BillboardTextBundle {
+                    transform: Transform::from_translation(pile_transform.translation())
+                        .with_scale(Vec3::splat(0.01)),
+                    text: Text::from_section(
+                        format!("+{} Metal Trash", metal_trash_collected),
+                        text_style,
+                    ),
                     ..default()
-                };
-                commands
-                    .spawn(BillboardTextBundle {
-                        transform: Transform::from_translation(pc_transform.translation())
-                            .with_scale(Vec3::splat(0.01)),
-                        text: Text::from_section(
-                            format!("+{} Metal Trash", metal_trash_collected),
-                            text_style,
-                        ),
-                        ..default()
-                    })
-                    .insert(FlowUpText { lifetime: 1.0 });
-            }
+                })
+                .insert(FlowUpText { lifetime: 1.0 });
         }
     }
 }
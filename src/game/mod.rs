@@ -4,28 +4,46 @@ use bevy::prelude::*;
 
 mod animation;
 pub mod assets;
+mod atmosphere;
 pub mod audio;
+pub mod autopilot;
 pub mod auto_anim;
 pub mod billboard_state;
+mod build;
 pub mod character;
 pub mod components;
+mod controls;
+mod crew;
 mod daycycle;
 mod debt;
+mod device;
 pub mod device_state;
 pub mod difficult;
+mod durability;
+#[cfg(feature = "dev")]
+mod editor;
+mod firefighting;
+mod focus;
+mod forecast;
+mod hazard;
 mod highlight;
-mod kitchen_work;
+pub mod input;
+pub mod jobs;
 mod map;
 pub mod metal_trash;
 mod movement;
-mod pc_work;
+mod needs;
 pub mod render;
+mod reservation;
 mod resource_flow;
 pub mod resources;
+pub mod rng;
 mod selectable;
 mod sequence;
 pub mod spawn;
 pub mod sprite_material;
+mod steering;
+mod storeys;
 mod trouble_planner;
 pub mod ui;
 
@@ -36,7 +54,16 @@ pub(super) fn plugin(app: &mut App) {
         animation::plugin,
         audio::plugin,
         assets::plugin,
+        autopilot::plugin,
+        controls::plugin,
+        crew::plugin,
+        focus::plugin,
+        input::plugin,
+        jobs::plugin,
         movement::plugin,
+        needs::plugin,
+        reservation::plugin,
+        steering::plugin,
         spawn::plugin,
         ui::plugin,
     ));
@@ -52,8 +79,6 @@ pub(super) fn plugin(app: &mut App) {
         metal_trash::plugin,
         components::plugin,
         resources::plugin,
-        pc_work::plugin,
-        kitchen_work::plugin,
         sequence::plugin,
         resource_flow::plugin,
         billboard_state::plugin,
@@ -61,5 +86,19 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_plugins(sprite_material::SpriteMaterialPlugin);
 
-    app.add_plugins((render::plugin,));
+    app.add_plugins((
+        render::plugin,
+        build::plugin,
+        atmosphere::plugin,
+        storeys::plugin,
+        hazard::plugin,
+        durability::plugin,
+        rng::plugin,
+        firefighting::plugin,
+        forecast::plugin,
+        device::plugin,
+    ));
+
+    #[cfg(feature = "dev")]
+    app.add_plugins(editor::plugin);
 }
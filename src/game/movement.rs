@@ -7,15 +7,25 @@ use bevy::{prelude::*, window::PrimaryWindow};
 
 use crate::AppSet;
 
-use super::daycycle::GameTime;
+use super::{
+    controls::ControlScheme,
+    crew::SelectedCrew,
+    daycycle::GameTime,
+    input::ActionState,
+    map::{
+        navigation::{is_free, world_to_tile, NavBlockers},
+        on_storey, storey_of, ShipMap,
+    },
+    sequence::{ClearSequence, Sequence},
+};
 
 pub(super) fn plugin(app: &mut App) {
     // Record directional input as movement controls.
     app.register_type::<MovementController>();
-    // app.add_systems(
-    //     Update,
-    //     record_movement_controller.in_set(AppSet::RecordInput),
-    // );
+    app.add_systems(
+        Update,
+        record_movement_controller.in_set(AppSet::RecordInput),
+    );
 
     // Apply movement based on controls.
     app.register_type::<(Movement, WrapWithinWindow)>();
@@ -32,31 +42,32 @@ pub(super) fn plugin(app: &mut App) {
 pub struct MovementController(pub Vec2);
 
 fn record_movement_controller(
-    input: Res<ButtonInput<KeyCode>>,
-    mut controller_query: Query<&mut MovementController>,
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    scheme: Res<ControlScheme>,
+    mut controller_query: Query<(
+        Entity,
+        &mut MovementController,
+        Has<SelectedCrew>,
+        Option<&Sequence>,
+    )>,
 ) {
-    // Collect directional input.
-    let mut intent = Vec2::ZERO;
-    if input.pressed(KeyCode::KeyW) || input.pressed(KeyCode::ArrowUp) {
-        intent.y += 1.0;
-    }
-    if input.pressed(KeyCode::KeyS) || input.pressed(KeyCode::ArrowDown) {
-        intent.y -= 1.0;
-    }
-    if input.pressed(KeyCode::KeyA) || input.pressed(KeyCode::ArrowLeft) {
-        intent.x -= 1.0;
-    }
-    if input.pressed(KeyCode::KeyD) || input.pressed(KeyCode::ArrowRight) {
-        intent.x += 1.0;
-    }
+    // Directional input from any device.
+    let intent = actions.movement();
 
-    // Normalize so that diagonal movement has the same speed as
-    // horizontal and vertical movement.
-    let intent = intent.normalize_or_zero();
+    // Apply movement intent to the selected crew only.
+    for (entity, mut controller, selected, sequence) in &mut controller_query {
+        if !selected || !scheme.allows_direct() {
+            controller.0 = Vec2::ZERO;
+            continue;
+        }
 
-    // Apply movement intent to controllers.
-    for mut controller in &mut controller_query {
         controller.0 = intent;
+
+        // Walking by hand cancels what the crew member was told to do by clicking
+        if intent != Vec2::ZERO && sequence.is_some_and(|sequence| !sequence.actions.is_empty()) {
+            commands.trigger_targets(ClearSequence, entity);
+        }
     }
 }
 
@@ -70,7 +81,15 @@ fn apply_movement(
     time: Res<GameTime>,
     mut movement_query: Query<(&MovementController, &Movement, &mut Transform)>,
     q_camera: Query<&Transform, (With<Camera>, Without<Movement>)>,
+    q_maps: Query<&ShipMap>,
+    blockers: Res<NavBlockers>,
 ) {
+    // Everyone walks on the map of their own floor
+    let can_stand = |position: Vec3| {
+        on_storey(q_maps.iter(), storey_of(position))
+            .map_or(true, |map| is_free(map, &blockers, world_to_tile(position)))
+    };
+
     let Ok(cam_transform) = q_camera.get_single() else {
         return;
     };
@@ -86,7 +105,20 @@ fn apply_movement(
 
         let velocity = right * velocity.x + up * velocity.y;
 
-        transform.translation += velocity * time.delta_seconds();
+        let step = velocity * time.delta_seconds();
+        if step == Vec3::ZERO {
+            continue;
+        }
+
+        // Slide along walls and devices instead of walking through them
+        let position = transform.translation;
+        if !can_stand(position) || can_stand(position + step) {
+            transform.translation += step;
+        } else if can_stand(position + Vec3::new(step.x, 0.0, 0.0)) {
+            transform.translation.x += step.x;
+        } else if can_stand(position + Vec3::new(0.0, 0.0, step.z)) {
+            transform.translation.z += step.z;
+        }
     }
 }
 
@@ -0,0 +1,87 @@
+//! Per-character needs.
+//! Every crew member carries its own copy of the need resources
+//! ([`Thirst`], [`Hungry`], [`Pee`], [`Toilet`]) as a [`Need`] component.
+//! The global resource of the same type mirrors the neediest crew member, so
+//! the HUD and the death conditions of [`GameResourcePlugin`](super::resources::GameResourcePlugin)
+//! keep working on it. Shared life support (oxygen, water tanks) stays global.
+
+use bevy::prelude::*;
+
+use super::{
+    daycycle::GameTime,
+    resources::{collect_generations, GameResInfo, GameResource, Hungry, Pee, Thirst, Toilet},
+    spawn::player::Player,
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_plugins((
+        NeedPlugin::<Thirst>::default(),
+        NeedPlugin::<Hungry>::default(),
+        NeedPlugin::<Toilet>::default(),
+        NeedPlugin::<Pee>::default(),
+    ));
+}
+
+/// Need of a single character. Wraps the same type as the global resource,
+/// so limits, thresholds and death reasons are shared.
+#[derive(Component, Deref, DerefMut, Default, Clone, Copy)]
+pub struct Need<T: GameResource + Copy>(pub T);
+
+/// All needs a crew member is spawned with
+pub fn needs_bundle() -> impl Bundle {
+    (
+        Need::<Thirst>::default(),
+        Need::<Hungry>::default(),
+        Need::<Toilet>::default(),
+        Need::<Pee>::default(),
+    )
+}
+
+pub struct NeedPlugin<T: GameResource> {
+    _type: std::marker::PhantomData<T>,
+}
+
+impl<T: GameResource> Default for NeedPlugin<T> {
+    fn default() -> Self {
+        Self {
+            _type: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T: GameResource + Copy + Default> Plugin for NeedPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (reset_need::<T>, mirror_need::<T>)
+                .chain()
+                .after(collect_generations::<T>),
+        );
+    }
+}
+
+fn reset_need<T: GameResource + Copy + Default>(
+    mut resets: EventReader<ResetGame>,
+    mut q_needs: Query<&mut Need<T>>,
+) {
+    for _ in resets.read() {
+        for mut need in q_needs.iter_mut() {
+            need.reset();
+        }
+    }
+}
+
+fn mirror_need<T: GameResource + Copy>(
+    crew: Query<&Need<T>, With<Player>>,
+    mut resource: ResMut<T>,
+    mut info: ResMut<GameResInfo<T>>,
+    time: Res<GameTime>,
+) {
+    let amount = crew.iter().map(|need| need.amount()).fold(0.0, f32::max);
+
+    if time.delta_seconds() > 0.0 {
+        info.generation_rate = (amount - resource.amount()) / time.delta_seconds();
+    }
+    resource.set_amount(amount);
+}
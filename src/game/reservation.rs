@@ -0,0 +1,205 @@
+//! Reservation of devices.
+//! A device is used by one crew member at a time. A [`RequestInteract`] on a
+//! busy device is redirected to a free device of the same [`DeviceKind`], or
+//! waits in the device queue until the current user is done.
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{
+    components::{device_kind::DeviceKind, fire::InFire},
+    durability::Broken,
+    hazard::Hazard,
+    selectable::{OnInteract, RequestInteract},
+    sequence::Sequence,
+    spawn::player::Player,
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<Reservations>();
+    app.observe(request_interact);
+    app.add_systems(Update, release_reservations);
+    app.add_systems(PostUpdate, reset_reservations);
+}
+
+struct Reservation {
+    crew: Entity,
+    /// Crew members waiting for the device, first come first served
+    queue: Vec<Entity>,
+}
+
+#[derive(Resource, Default)]
+pub struct Reservations {
+    devices: HashMap<Entity, Reservation>,
+}
+
+impl Reservations {
+    /// Crew member using the device, if any
+    pub fn user(&self, device: Entity) -> Option<Entity> {
+        self.devices
+            .get(&device)
+            .map(|reservation| reservation.crew)
+    }
+
+    fn reserve(&mut self, device: Entity, crew: Entity) {
+        let queue = self
+            .devices
+            .remove(&device)
+            .map(|reservation| reservation.queue)
+            .unwrap_or_default();
+        self.devices.insert(device, Reservation { crew, queue });
+    }
+
+    /// Forgets everything the crew member asked for before
+    fn cancel(&mut self, crew: Entity) {
+        for reservation in self.devices.values_mut() {
+            reservation.queue.retain(|queued| *queued != crew);
+        }
+        self.devices
+            .retain(|_, reservation| reservation.crew != crew);
+    }
+}
+
+fn request_interact(
+    trigger: Trigger<RequestInteract>,
+    mut commands: Commands,
+    mut reservations: ResMut<Reservations>,
+    q_devices: Query<(Entity, &DeviceKind, Has<InFire>, Has<Hazard>, Has<Broken>)>,
+) {
+    let device = trigger.entity();
+    let RequestInteract {
+        actor,
+        direct,
+        append,
+    } = *trigger.event();
+
+    // Queued work is done later, the device is only taken once the crew member gets there
+    if append {
+        commands.trigger_targets(
+            OnInteract {
+                actor,
+                direct,
+                append,
+            },
+            device,
+        );
+        return;
+    }
+
+    reservations.cancel(actor);
+
+    // Anyone can fight a fire, fix a hazard or repair a device, and things
+    // that aren't devices can't be reserved
+    let Ok((_, kind, false, false, false)) = q_devices.get(device) else {
+        commands.trigger_targets(
+            OnInteract {
+                actor,
+                direct,
+                append: false,
+            },
+            device,
+        );
+        return;
+    };
+
+    if reservations.user(device).is_none() {
+        reservations.reserve(device, actor);
+        commands.trigger_targets(
+            OnInteract {
+                actor,
+                direct,
+                append: false,
+            },
+            device,
+        );
+        return;
+    }
+
+    let alternative = q_devices
+        .iter()
+        .find(|(other, other_kind, in_fire, hazard, broken)| {
+            *other_kind == kind
+                && !in_fire
+                && !hazard
+                && !broken
+                && reservations.user(*other).is_none()
+        })
+        .map(|(other, _, _, _, _)| other);
+
+    if let Some(other) = alternative {
+        info!("{} is busy, {} uses {} instead", device, actor, other);
+        reservations.reserve(other, actor);
+        // The other device is somewhere else, so walk there
+        commands.trigger_targets(
+            OnInteract {
+                actor,
+                direct: false,
+                append: false,
+            },
+            other,
+        );
+    } else if let Some(reservation) = reservations.devices.get_mut(&device) {
+        info!("{} is busy, {} waits in line", device, actor);
+        reservation.queue.push(actor);
+    }
+}
+
+/// Frees devices whose user stopped working on them and hands them over to
+/// the next crew member in line
+fn release_reservations(
+    mut commands: Commands,
+    mut reservations: ResMut<Reservations>,
+    q_crew: Query<&Sequence, With<Player>>,
+    q_devices: Query<(), With<DeviceKind>>,
+) {
+    let mut released = Vec::new();
+
+    for (device, reservation) in reservations.devices.iter() {
+        let current_target = q_crew
+            .get(reservation.crew)
+            .ok()
+            .and_then(|sequence| sequence.current_target());
+
+        // The next job may be on another device of the same kind, with the
+        // same group name, so only the target tells whether this one is in use
+        let done = !q_devices.contains(*device) || current_target != Some(*device);
+
+        if done {
+            released.push(*device);
+        }
+    }
+
+    for device in released {
+        let Some(reservation) = reservations.devices.remove(&device) else {
+            continue;
+        };
+        if !q_devices.contains(device) {
+            continue;
+        }
+
+        let mut queue = reservation
+            .queue
+            .into_iter()
+            .filter(|crew| q_crew.contains(*crew));
+        if let Some(next) = queue.next() {
+            reservations.reserve(device, next);
+            if let Some(reservation) = reservations.devices.get_mut(&device) {
+                reservation.queue = queue.collect();
+            }
+            commands.trigger_targets(
+                OnInteract {
+                    actor: next,
+                    direct: false,
+                    append: false,
+                },
+                device,
+            );
+        }
+    }
+}
+
+fn reset_reservations(mut resets: EventReader<ResetGame>, mut reservations: ResMut<Reservations>) {
+    for _ in resets.read() {
+        reservations.devices.clear();
+    }
+}
@@ -3,13 +3,18 @@
 use bevy::prelude::*;
 
 use super::{
+    atmosphere::AirExchange,
     components::fire::InFire,
     daycycle::{DeathCause, GameOver, GameTime, TimeSpeed},
+    device::Usable,
     difficult::{BREATH_RATE, FIRE_RATE, HUNGRY_RATE, THIRST_RATE, TOILET_K},
+    durability::{efficiency_of, Durability},
+    needs::Need,
     resources::{
         CarbonDioxide, Food, FoodGeneration, GameResource, Generate, Hungry, Oxygen,
         OxygenRecycling, Pee, Thirst, Toilet, Water,
     },
+    spawn::spawn_commands::OxygenRecyler,
 };
 
 pub(super) fn plugin(app: &mut App) {
@@ -26,14 +31,16 @@ pub(super) fn plugin(app: &mut App) {
     // app.add_systems(PostUpdate, (bad_air_death, too_many_oxigen_death));
 }
 
-fn update_hungry(mut hungry: EventWriter<Generate<Hungry>>) {
-    hungry.send(Generate::new(HUNGRY_RATE));
+fn update_hungry(mut q_hungry: Query<&mut Need<Hungry>>, gametime: Res<GameTime>) {
+    for mut hungry in q_hungry.iter_mut() {
+        hungry.increase(HUNGRY_RATE * gametime.delta_seconds());
+    }
 }
 
 fn update_oxygen_and_co2(
     oxygen_recycling: ResMut<OxygenRecycling>,
-    mut oxygen: EventWriter<Generate<Oxygen>>,
-    mut co2: EventWriter<Generate<CarbonDioxide>>,
+    mut exchanges: EventWriter<AirExchange>,
+    q_recyclers: Query<(&GlobalTransform, Option<&Durability>), (With<OxygenRecyler>, Usable)>,
     gametime: Res<GameTime>,
 ) {
     let recycling = oxygen_recycling.working;
@@ -45,12 +52,18 @@ fn update_oxygen_and_co2(
         0.0
     };
 
-    oxygen.send(Generate::new(oxygen_generation));
-    co2.send(Generate::new(-oxygen_generation));
+    // Every recycler works on the air of its own room, at its own rate
+    for (transform, durability) in q_recyclers.iter() {
+        // Worn recyclers make less oxygen
+        let generation = oxygen_generation * efficiency_of(durability);
+        exchanges.send(AirExchange {
+            position: transform.translation(),
+            oxygen: generation,
+            co2: -generation,
+        });
+    }
 
-    //breate
-    oxygen.send(Generate::new(-BREATH_RATE));
-    co2.send(Generate::new(BREATH_RATE));
+    // Breathing is done by every crew member, see `atmosphere`
 }
 
 fn calculate_new_amount(
@@ -66,24 +79,27 @@ fn calculate_new_amount(
 }
 
 fn fire_oxigen(
-    mut oxigen: EventWriter<Generate<Oxygen>>,
-    mut co2: EventWriter<Generate<CarbonDioxide>>,
+    mut exchanges: EventWriter<AirExchange>,
     gametime: Res<GameTime>,
-    q_in_fire: Query<Entity, With<InFire>>,
+    q_in_fire: Query<&GlobalTransform, With<InFire>>,
 ) {
-    let count = q_in_fire.iter().count();
-    if count > 0 {
-        let consuming = count as f32 * FIRE_RATE;
-
-        oxigen.send(Generate::new(-consuming));
-        co2.send(Generate::new(consuming));
+    for transform in q_in_fire.iter() {
+        exchanges.send(AirExchange {
+            position: transform.translation(),
+            oxygen: -FIRE_RATE,
+            co2: FIRE_RATE,
+        });
     }
 }
 
-fn update_thirst(mut thirst: EventWriter<Generate<Thirst>>) {
-    thirst.send(Generate::new(THIRST_RATE));
+fn update_thirst(mut q_thirst: Query<&mut Need<Thirst>>, gametime: Res<GameTime>) {
+    for mut thirst in q_thirst.iter_mut() {
+        thirst.increase(THIRST_RATE * gametime.delta_seconds());
+    }
 }
 
-fn update_toilet(mut toilet: EventWriter<Generate<Toilet>>, pee: Res<Pee>) {
-    toilet.send(Generate::new(pee.amount() * TOILET_K));
+fn update_toilet(mut q_needs: Query<(&mut Need<Toilet>, &Need<Pee>)>, gametime: Res<GameTime>) {
+    for (mut toilet, pee) in q_needs.iter_mut() {
+        toilet.increase(pee.amount() * TOILET_K * gametime.delta_seconds());
+    }
 }
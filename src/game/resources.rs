@@ -26,9 +26,11 @@ pub(crate) fn plugin(app: &mut App) {
         GameResourcePlugin::<BadWater>::default(),
         GameResourcePlugin::<Pee>::default(),
         GameResourcePlugin::<Food>::default(),
+        GameResourcePlugin::<Herbs>::default(),
         GameResourcePlugin::<Hydrogen>::default(),
         GameResourcePlugin::<Metal>::default(),
         GameResourcePlugin::<MetalTrash>::default(),
+        GameResourcePlugin::<Extinguisher>::default(),
         GameResourcePlugin::<Thirst>::default(),
         GameResourcePlugin::<Hungry>::default(),
         GameResourcePlugin::<Toilet>::default(),
@@ -270,7 +272,7 @@ simple_game_resource!(
     0.0,
     100.0,
     None,
-    None,
+    Some(80.0),
     ResourceThreshold::Waste,
     "You suffocated in carbon dioxide. Your last breath was used to inflate a balloon saying 'For Sale: Almost Paid Off Mortgage'."
 );
@@ -287,6 +289,8 @@ simple_game_resource!(
 
 impl_limitless_resource!(MetalTrash);
 impl_limitless_resource!(Metal);
+impl_limitless_resource!(Extinguisher);
+impl_limitless_resource!(Herbs);
 
 // #[derive(Resource, Default)]
 // pub struct AllResourcesGetter {
@@ -296,6 +300,7 @@ impl_limitless_resource!(Metal);
 /// Resource for OxygenRecycling configuration
 #[derive(Resource)]
 pub struct OxygenRecycling {
+    /// Oxygen made by each recycler, per second
     pub oxygen_generation_rate: f32,
     pub co2_consumption_rate: f32,
     pub working: bool,
@@ -430,7 +435,7 @@ impl<T: GameResource + Clone + Default> Plugin for GameResourcePlugin<T> {
         app.add_event::<Generate<T>>();
         app.add_systems(PostUpdate, collect_generations::<T>);
         app.add_systems(PostUpdate, check_death_conditions::<T>);
-        app.add_systems(PostUpdate, reset_resource::<T>);
+        app.add_systems(PostUpdate, reset_resource::<T>.in_set(ResetResources));
 
         // app.world_mut()
         //     .resource_mut::<AllResourcesGetter>()
@@ -452,6 +457,10 @@ impl<T: GameResource + Clone + Default> Plugin for GameResourcePlugin<T> {
     }
 }
 
+/// Systems putting every [`GameResource`] back to its default on [`ResetGame`]
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResetResources;
+
 fn reset_resource<T: GameResource>(mut resource: ResMut<T>, mut resets: EventReader<ResetGame>) {
     for _ in resets.read() {
         resource.reset();
@@ -557,7 +566,7 @@ fn check_death_conditions<T: GameResource + Clone>(
     }
 }
 
-fn collect_generations<T: GameResource>(
+pub(crate) fn collect_generations<T: GameResource>(
     mut ev_gens: EventReader<Generate<T>>,
     mut info: ResMut<GameResInfo<T>>,
     mut resource: ResMut<T>,
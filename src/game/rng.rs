@@ -0,0 +1,97 @@
+//! Randomness of a run.
+//! Every random draw of the game goes through [`GameRng`], seeded when the
+//! level is spawned, so a run can be played again from its seed. Cosmetic
+//! randomness, like the step sounds, has its own stream so that it doesn't
+//! shift what happens in the game.
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::{
+    spawn::level::{CurrentLevel, NewRun},
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<ChosenSeed>();
+    app.insert_resource(GameRng::new(rand::random()));
+    app.observe(seed_run);
+    app.add_systems(PostUpdate, reseed_on_reset);
+}
+
+/// Mixed into the seed of the cosmetic stream, so both streams differ
+const COSMETIC_STREAM: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Seed typed on the title screen. Runs get a random seed when it isn't set.
+#[derive(Resource, Default)]
+pub struct ChosenSeed(pub Option<u64>);
+
+impl ChosenSeed {
+    pub fn next_seed(&self) -> u64 {
+        self.0.unwrap_or_else(rand::random)
+    }
+}
+
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    gameplay: StdRng,
+    cosmetic: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            gameplay: StdRng::seed_from_u64(seed),
+            cosmetic: StdRng::seed_from_u64(seed ^ COSMETIC_STREAM),
+        }
+    }
+
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Stream for everything that changes the outcome of the run
+    pub const fn gameplay(&mut self) -> &mut StdRng {
+        &mut self.gameplay
+    }
+
+    /// Stream for sounds and visuals
+    pub const fn cosmetic(&mut self) -> &mut StdRng {
+        &mut self.cosmetic
+    }
+}
+
+fn seed_run(
+    _trigger: Trigger<NewRun>,
+    chosen: Res<ChosenSeed>,
+    current_level: Res<CurrentLevel>,
+    mut rng: ResMut<GameRng>,
+) {
+    let seed = run_seed(&chosen, &current_level);
+    info!("Run seed {}", seed);
+    *rng = GameRng::new(seed);
+}
+
+/// A generated apartment comes from the seed of the run, so it keeps it.
+/// Otherwise a chosen seed plays the same run again, or a new one starts.
+fn run_seed(chosen: &ChosenSeed, current_level: &CurrentLevel) -> u64 {
+    match *current_level {
+        CurrentLevel::Generated { seed } => seed,
+        CurrentLevel::File(_) => chosen.next_seed(),
+    }
+}
+
+fn reseed_on_reset(
+    mut resets: EventReader<ResetGame>,
+    chosen: Res<ChosenSeed>,
+    current_level: Res<CurrentLevel>,
+    mut rng: ResMut<GameRng>,
+) {
+    for _ in resets.read() {
+        let seed = run_seed(&chosen, &current_level);
+        info!("Run seed {}", seed);
+        *rng = GameRng::new(seed);
+    }
+}
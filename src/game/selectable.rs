@@ -1,11 +1,15 @@
 use bevy::prelude::*;
 use bevy_mod_picking::prelude::*;
 
+use super::sequence::NewMode;
+
 pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<Selectable>();
     app.add_systems(Update, selectable_add);
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct Selectable;
 
 #[derive(Component)]
@@ -26,6 +30,36 @@ pub struct OnMouseOut;
 #[derive(Event)]
 pub struct OnMouseClick(pub MouseButton);
 
+/// A crew member was ordered to use the target entity
+#[derive(Event)]
+pub struct OnInteract {
+    pub actor: Entity,
+    /// The crew member already stands next to the target, skip the [`GoToAction`](super::character::GoToAction)
+    pub direct: bool,
+    /// Queue the work after what the crew member is already doing
+    pub append: bool,
+}
+
+impl OnInteract {
+    /// How the resulting actions should be added to the crew member sequence
+    pub const fn mode(&self, mode: NewMode) -> NewMode {
+        if self.append {
+            NewMode::Append
+        } else {
+            mode
+        }
+    }
+}
+
+/// A crew member wants to use the target entity. Becomes an [`OnInteract`]
+/// once the device is free, see [`reservation`](super::reservation).
+#[derive(Event, Clone, Copy)]
+pub struct RequestInteract {
+    pub actor: Entity,
+    pub direct: bool,
+    pub append: bool,
+}
+
 #[derive(Event)]
 pub struct OpenContextMenu;
 
@@ -8,18 +8,24 @@ use rand::seq;
 pub(crate) fn plugin(app: &mut App) {
     app.observe(on_next_action);
     app.observe(new_sequence);
+    app.observe(clear_sequence);
 }
 
 /// Must do next action (and if we have current active action)
 #[derive(Event)]
 pub struct NextAction;
 
+/// Drop every action of the character, terminating the active one
+#[derive(Event)]
+pub struct ClearSequence;
+
 #[derive(Event)]
 pub struct NewActionSequence {
     pub actions: ActionGroup,
     pub mode: NewMode,
 }
 
+#[derive(Clone, Copy)]
 pub enum NewMode {
     Replace,
     SoftReplace, //replace only if work group is not same
@@ -29,6 +35,10 @@ pub enum NewMode {
 pub trait CharacterAction {
     fn trigger_start(&self, commands: &mut Commands, target: Entity); // Start the action with trigger
     fn terminate(&self, commands: &mut Commands, target: Entity);
+    /// Device or other entity the action is done at, if any
+    fn target(&self) -> Option<Entity> {
+        None
+    }
 }
 #[derive(Clone)]
 pub struct ActionHolder {
@@ -54,6 +64,20 @@ pub struct Sequence {
 }
 
 impl Sequence {
+    /// Name of the group the character is working on
+    pub fn current_group(&self) -> Option<&str> {
+        self.actions.first().map(|group| group.name.as_str())
+    }
+
+    /// Entity the character is working at or walking to
+    pub fn current_target(&self) -> Option<Entity> {
+        self.actions
+            .first()?
+            .actions
+            .iter()
+            .find_map(|action| action.target())
+    }
+
     pub fn push<T: IntoActionGroup + Send + Sync + 'static>(&mut self, action: T) {
         self.actions.push(action.into_action_group());
         if self.actions.len() == 1 {
@@ -86,6 +110,21 @@ fn on_next_action(
     }
 }
 
+fn clear_sequence(
+    trigger: Trigger<ClearSequence>,
+    mut commands: Commands,
+    mut q_players: Query<&mut Sequence>,
+) {
+    let target = trigger.entity();
+    if let Ok(mut sequence) = q_players.get_mut(target) {
+        if !sequence.actions.is_empty() && sequence.active {
+            sequence.actions[0].terminate(&mut commands, target);
+        }
+        sequence.actions.clear();
+        sequence.active = false;
+    }
+}
+
 fn new_sequence(
     trigger: Trigger<NewActionSequence>,
     mut commands: Commands,
@@ -0,0 +1,66 @@
+//! Devices placed in Blender.
+//! Nodes of a glTF scene with a custom property named after a registered
+//! component (`Pc`, `Kitchen`, `Toilet`, `Selectable`, `NightLight`, ...)
+//! get that component through `bevy_gltf_components` when the scene spawns.
+//! Here they also get the companion components the `Spawn*` commands add,
+//! so a device from the ship model works like one from a level layout.
+
+use bevy::prelude::*;
+use bevy_gltf_components::ComponentsFromGltfPlugin;
+
+use crate::game::{
+    character::IgnoreJustMoving,
+    components::{
+        hydroponic::{Hydroponic, HydroponicState},
+        kitchen::Kitchen,
+        pc::Pc,
+    },
+    selectable::Selectable,
+};
+
+use super::spawn_commands::{MetalTrashPile, OxygenRecyler, Toilet, WaterCleaner, WaterDispenser};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(ComponentsFromGltfPlugin::default());
+    app.add_systems(Update, complete_gltf_devices);
+}
+
+/// Any gameplay device marker
+type AnyDevice = Or<(
+    With<Pc>,
+    With<Kitchen>,
+    With<Hydroponic>,
+    With<OxygenRecyler>,
+    With<Toilet>,
+    With<WaterDispenser>,
+    With<WaterCleaner>,
+    With<MetalTrashPile>,
+)>;
+
+/// Devices whose action group walks there on its own. The pc, oxygen
+/// recycler and trash piles get their [`IgnoreJustMoving`] from `pc.rs`.
+type OwnGoTo = Or<(
+    With<Hydroponic>,
+    With<Toilet>,
+    With<WaterDispenser>,
+    With<WaterCleaner>,
+)>;
+
+fn complete_gltf_devices(
+    mut commands: Commands,
+    q_unselectable: Query<Entity, (AnyDevice, Without<Selectable>)>,
+    q_moving: Query<Entity, (OwnGoTo, Without<IgnoreJustMoving>)>,
+    q_plants: Query<Entity, (With<Hydroponic>, Without<HydroponicState>)>,
+) {
+    for entity in q_unselectable.iter() {
+        commands.entity(entity).insert(Selectable);
+    }
+
+    for entity in q_moving.iter() {
+        commands.entity(entity).insert(IgnoreJustMoving);
+    }
+
+    for entity in q_plants.iter() {
+        commands.entity(entity).insert(HydroponicState::Growing);
+    }
+}
@@ -0,0 +1,262 @@
+//! Level layout files.
+//! A level is a RON asset (`*.level.ron`) listing the ship scene, the lights,
+//! the crew and every device with its placement and initial state.
+//! Generated apartments build the same description, without a ship scene,
+//! and the dev editor saves the edited state in the same format.
+//! [`spawn_level`](super::level) instantiates it through the `Spawn*`
+//! commands of [`spawn_commands`](super::spawn_commands).
+
+use std::{collections::BTreeMap, fmt};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::world::Command,
+    prelude::*,
+    reflect::serde::TypedReflectDeserializer,
+};
+use serde::{de::DeserializeSeed, Deserialize, Serialize};
+
+use crate::game::{
+    assets::SceneKey,
+    components::{device_kind::DeviceKind, fire::InFire, hydroponic::Hydroponic},
+    daycycle::GameTime,
+};
+
+use super::spawn_commands::{
+    SpawnHydroponic, SpawnKitchen, SpawnMetalTrashPile, SpawnOxygenGenerator, SpawnPc, SpawnSensor,
+    SpawnToilet, SpawnWaterCleaner, SpawnWaterDispenser,
+};
+
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone, Debug)]
+pub struct Level {
+    /// Ship model, the map tiles are drawn instead when missing
+    #[serde(default)]
+    pub ship: Option<ShipPlacement>,
+    /// Size of the walkable grid, in tiles
+    pub map_size: (usize, usize),
+    #[serde(default)]
+    pub ambient_light: Option<AmbientPlacement>,
+    #[serde(default)]
+    pub lights: Vec<LightPlacement>,
+    pub crew: Vec<CrewPlacement>,
+    #[serde(default)]
+    pub devices: Vec<DevicePlacement>,
+    #[serde(default)]
+    pub tanks: Vec<TankPlacement>,
+    /// Metal available for building at the start
+    #[serde(default)]
+    pub metal: f32,
+    /// Amount of the other ship resources, by label. Written by the editor,
+    /// the defaults are kept for the missing ones
+    #[serde(default)]
+    pub stock: BTreeMap<String, f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShipPlacement {
+    pub position: [f32; 3],
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AmbientPlacement {
+    pub brightness: f32,
+    pub color: [f32; 3],
+}
+
+/// Spot light pointing down, switched on at night
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LightPlacement {
+    pub position: [f32; 3],
+    /// Angles in degrees
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CrewPlacement {
+    pub name: String,
+    pub position: [f32; 3],
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DevicePlacement {
+    pub kind: DeviceKind,
+    pub position: [f32; 3],
+    /// Rotation around the vertical axis, in degrees
+    #[serde(default)]
+    pub rotation: f32,
+    /// Uniform scale, the device default when missing
+    #[serde(default)]
+    pub scale: Option<f32>,
+    #[serde(default)]
+    pub state: StartState,
+    /// Reflected components of the game by type path, as RON, applied over
+    /// the spawned ones. The editor saves them to keep the inspector edits
+    #[serde(default)]
+    pub components: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TankPlacement {
+    pub scene: SceneKey,
+    pub position: [f32; 3],
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    pub scale: Option<f32>,
+}
+
+/// State a device starts the level in
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StartState {
+    #[default]
+    Working,
+    OnFire,
+    /// Hydroponics only, the plants can be harvested
+    Ripe,
+    /// Hydroponics only, the plants are dead
+    Withered,
+}
+
+/// Spawns a device of any [`DeviceKind`] and applies its [`StartState`]
+pub struct SpawnDevice(pub DevicePlacement);
+
+impl Command for SpawnDevice {
+    fn apply(self, world: &mut World) {
+        let placement = self.0;
+        let pos = Vec3::from_array(placement.position);
+        let rot = Some(Quat::from_rotation_y(placement.rotation.to_radians()));
+        let scale = placement.scale;
+
+        let entity = match placement.kind {
+            DeviceKind::Pc => SpawnPc { pos, rot, scale }.spawn(world),
+            DeviceKind::Kitchen => SpawnKitchen { pos, rot, scale }.spawn(world),
+            DeviceKind::Hydroponic => SpawnHydroponic { pos, rot, scale }.spawn(world),
+            DeviceKind::OxygenRecycler => SpawnOxygenGenerator { pos, rot, scale }.spawn(world),
+            DeviceKind::Toilet => SpawnToilet { pos, rot, scale }.spawn(world),
+            DeviceKind::WaterDispenser => SpawnWaterDispenser { pos, rot, scale }.spawn(world),
+            DeviceKind::WaterCleaner => SpawnWaterCleaner { pos, rot, scale }.spawn(world),
+            DeviceKind::Sensor => SpawnSensor { pos, rot, scale }.spawn(world),
+            DeviceKind::MetalTrashPile => SpawnMetalTrashPile { pos, rot, scale }.spawn(world),
+        };
+
+        match placement.state {
+            StartState::Working => {}
+            StartState::OnFire => {
+                let started_at = world.resource::<GameTime>().elapsed_seconds();
+                world.entity_mut(entity).insert(InFire {
+                    fire_created: false,
+                    started_at,
+                });
+            }
+            StartState::Ripe | StartState::Withered if placement.kind != DeviceKind::Hydroponic => {
+                warn!("{:?} can't start {:?}", placement.kind, placement.state);
+            }
+            // The hydroponic state follows the plant, so change the plant itself
+            StartState::Ripe => {
+                if let Some(mut hydroponic) = world.get_mut::<Hydroponic>(entity) {
+                    hydroponic.time_to_food = 0.0;
+                }
+            }
+            StartState::Withered => {
+                if let Some(mut hydroponic) = world.get_mut::<Hydroponic>(entity) {
+                    hydroponic.dead = true;
+                }
+            }
+        }
+
+        apply_components(world, entity, &placement.components);
+    }
+}
+
+/// Inserts or overwrites the saved components of a device
+fn apply_components(world: &mut World, entity: Entity, components: &BTreeMap<String, String>) {
+    if components.is_empty() {
+        return;
+    }
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    for (type_path, ron) in components {
+        let Some((registration, reflect_component)) = registry
+            .get_with_type_path(type_path)
+            .and_then(|registration| {
+                Some((registration, registration.data::<ReflectComponent>()?))
+            })
+        else {
+            warn!("Unknown component {} in level", type_path);
+            continue;
+        };
+        let value = ron::Deserializer::from_str(ron)
+            .map_err(|err| err.to_string())
+            .and_then(|mut deserializer| {
+                TypedReflectDeserializer::new(registration, &registry)
+                    .deserialize(&mut deserializer)
+                    .map_err(|err| err.to_string())
+            });
+        match value {
+            Ok(value) => {
+                reflect_component.apply_or_insert(&mut world.entity_mut(entity), &*value, &registry)
+            }
+            Err(err) => warn!("Invalid component {} in level: {}", type_path, err),
+        }
+    }
+
+    // The fire mesh of the saved game is gone, the new one is spawned again
+    if let Some(mut fire) = world.get_mut::<InFire>(entity) {
+        fire.fire_created = false;
+    }
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+#[derive(Debug)]
+pub enum LevelLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for LevelLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelLoaderError::Io(err) => write!(f, "can't read level: {}", err),
+            LevelLoaderError::Ron(err) => write!(f, "invalid level: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for LevelLoaderError {}
+
+impl From<std::io::Error> for LevelLoaderError {
+    fn from(err: std::io::Error) -> Self {
+        LevelLoaderError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for LevelLoaderError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        LevelLoaderError::Ron(err)
+    }
+}
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = LevelLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Level, LevelLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
@@ -1,12 +1,23 @@
 //! Spawn the main level by triggering other observers.
+//! What gets spawned where comes from the [`Level`] layout of the [`CurrentLevel`],
+//! either a level file or an apartment generated from a seed.
 
 use bevy::prelude::*;
+use bevy_mod_billboard::Billboard;
 
 use crate::{
     game::{
-        assets::{HandleMap, SceneKey},
-        components::pc::Pc,
+        assets::{HandleMap, LevelKey, SceneKey},
+        components::earth::Earth,
         daycycle::{NightLight, TimeSpeed},
+        firefighting::STARTING_EXTINGUISHERS,
+        input::{update_action_state, ActionState, InputAction},
+        map::{
+            map_generator::{generate_apartment, walled_map},
+            HiddenMap, ShipMap,
+        },
+        resources::{Extinguisher, Metal, ResetResources},
+        rng::ChosenSeed,
         selectable::Selectable,
         ui::game_over::ResetGame,
     },
@@ -14,25 +25,35 @@ use crate::{
 };
 
 use super::{
+    layout::{Level, SpawnDevice},
     player::{Player, SpawnPlayer},
-    spawn_commands::{
-        SpawnEarth, SpawnHydroponic, SpawnKitchen, SpawnOxygenGenerator,
-        SpawnToilet, SpawnWaterCleaner, SpawnWaterDispenser,
-    },
+    spawn_commands::{SpawnEarth, SpawnTank},
 };
 
 pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CurrentLevel>();
+    app.init_resource::<StartingStock>();
     app.observe(spawn_level);
     app.observe(setup_camera);
-    app.add_systems(PreUpdate, listen_reset.run_if(in_state(Screen::Playing)));
+    app.add_systems(
+        PreUpdate,
+        listen_reset
+            .after(update_action_state)
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(PostUpdate, restore_starting_stock.after(ResetResources));
+    app.add_systems(
+        Last,
+        spawn_pending_level.run_if(resource_exists::<PendingLevel>),
+    );
 }
 
 fn listen_reset(
     mut commands: Commands,
-    keys: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     mut reset: EventWriter<ResetGame>,
 ) {
-    if keys.just_pressed(KeyCode::KeyR) {
+    if actions.just_pressed(InputAction::Reset) {
         reset.send(ResetGame {
             target: commands.spawn_empty().id(),
         });
@@ -42,155 +63,212 @@ fn listen_reset(
 #[derive(Event, Debug)]
 pub struct SpawnLevel;
 
+/// A freshly spawned level starts a new run
+#[derive(Event, Debug)]
+pub struct NewRun;
+
+/// Layout used by the next [`SpawnLevel`]
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CurrentLevel {
+    File(LevelKey),
+    Generated { seed: u64 },
+}
+
+impl Default for CurrentLevel {
+    fn default() -> Self {
+        CurrentLevel::File(LevelKey::default())
+    }
+}
+
+/// Stock the level starts with, given back on every [`ResetGame`]
+#[derive(Resource, Default, Clone, Copy, Debug)]
+struct StartingStock {
+    metal: f32,
+    extinguishers: f32,
+}
+
+/// Level standing in the world. Spawning it again only resumes the run.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+struct SpawnedLevel {
+    level: CurrentLevel,
+    seed: Option<u64>,
+}
+
+/// Another level was picked, it is spawned once the old run is reset
+#[derive(Resource)]
+struct PendingLevel;
+
+/// Ship model of a level file
+#[derive(Component)]
+struct ShipScene;
+
+/// Everything a level spawns, children go along with their parents
+type LevelEntities = (
+    Or<(
+        With<ShipMap>,
+        With<Selectable>,
+        With<Player>,
+        With<NightLight>,
+        With<Earth>,
+        With<ShipScene>,
+        With<Billboard>,
+    )>,
+    Without<Parent>,
+);
+
+fn spawn_pending_level(mut commands: Commands) {
+    commands.remove_resource::<PendingLevel>();
+    commands.trigger(SpawnLevel);
+}
+
+fn restore_starting_stock(
+    mut resets: EventReader<ResetGame>,
+    stock: Res<StartingStock>,
+    mut metal: ResMut<Metal>,
+    mut extinguishers: ResMut<Extinguisher>,
+) {
+    for _ in resets.read() {
+        *metal = Metal::new(stock.metal);
+        *extinguishers = Extinguisher::new(stock.extinguishers);
+    }
+}
+
 fn spawn_level(
     _trigger: Trigger<SpawnLevel>,
     mut commands: Commands,
     scene_handler: Res<HandleMap<SceneKey>>,
-    player: Query<Entity, With<Player>>,
+    level_handler: Res<HandleMap<LevelKey>>,
+    levels: Res<Assets<Level>>,
+    current_level: Res<CurrentLevel>,
+    chosen: Res<ChosenSeed>,
+    spawned: Option<Res<SpawnedLevel>>,
+    q_level: Query<Entity, LevelEntities>,
+    mut reset: EventWriter<ResetGame>,
 ) {
-    if let Ok(_player) = player.get_single() {
-        return;
-    }
-    // The only thing we have in our level is a player,
-    // but add things like walls etc. here.
-    commands.trigger(SpawnPlayer);
-
-    commands.insert_resource(AmbientLight {
-        brightness: 80.0,
-        color: Color::srgb(0.9, 0.9, 1.0),
-    });
-
-    let light_grid_size = 3;
-    let map_size = 9.0;
-    let light_dist = map_size / (light_grid_size as f32 + 1.0);
-    let h = 15.0;
-    let outer_angle = (light_dist * 2.0_f32.sqrt() / h / 2.0).atan();
-    let inner_angle = (light_dist / h / 2.0).atan();
-
-    for x in 0..light_grid_size {
-        for y in 0..light_grid_size {
-            let x_pos = (x as f32 + 1.0) * light_dist;
-            let y_pos = (y as f32 + 1.0) * light_dist;
-
-            commands.spawn(NightLight).insert(SpotLightBundle {
-                transform: Transform::from_translation(Vec3::new(x_pos, h, y_pos)),
-                spot_light: SpotLight {
-                    inner_angle,
-                    outer_angle,
-                    ..default()
-                },
-                ..default()
+    let wanted = SpawnedLevel {
+        level: *current_level,
+        seed: chosen.0,
+    };
+    match spawned.as_deref() {
+        // Back from the title screen, the run goes on
+        Some(spawned) if *spawned == wanted => return,
+        // The old level goes away and its run is reset before the new one
+        // is spawned, so that the resets don't touch the new level
+        Some(_) => {
+            for entity in q_level.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            commands.remove_resource::<SpawnedLevel>();
+            commands.insert_resource(PendingLevel);
+            reset.send(ResetGame {
+                target: commands.spawn_empty().id(),
             });
+            return;
         }
+        None => {}
     }
 
-    // let sun_id = commands.spawn(DirectionalLightBundle {
-    //     transform: Transform::from_translation(Vec3::new(2.0, 5.0, 0.0))
-    //         .looking_at(Vec3::ZERO, Vec3::Y),
-    //     directional_light: DirectionalLight {
-    //         shadows_enabled: true,
-    //         illuminance: 2000.0,
-    //         ..default()
-    //     },
-    //     ..default()
-    // }).id();
+    let generated = match *current_level {
+        CurrentLevel::File(_) => None,
+        CurrentLevel::Generated { seed } => {
+            info!("Generating apartment with seed {}", seed);
+            let apartment = generate_apartment(seed);
+            if apartment.is_none() {
+                error!("No apartment with seed {}, loading the default level", seed);
+            }
+            apartment
+        }
+    };
+
+    let (level, map) = match generated {
+        Some(apartment) => (apartment.level, apartment.map),
+        None => {
+            let key = match *current_level {
+                CurrentLevel::File(key) => key,
+                CurrentLevel::Generated { .. } => LevelKey::default(),
+            };
+            let Some(level) = levels.get(&level_handler[&key]) else {
+                error!("Level {:?} isn't loaded", key);
+                return;
+            };
+            // Navigation grid matching the floor of the ship model
+            let (width, height) = level.map_size;
+            (level.clone(), walled_map(width, height))
+        }
+    };
+
+    let mut ship_map = commands.spawn((Name::new("Ship map"), SpatialBundle::default(), map));
+    if level.ship.is_some() {
+        ship_map.insert(HiddenMap);
+    }
+
+    for (i, crew) in level.crew.iter().enumerate() {
+        commands.trigger(SpawnPlayer {
+            name: crew.name.clone(),
+            pos: Vec3::from_array(crew.position),
+            selected: i == 0,
+        });
+    }
+
+    spawn_lights(&mut commands, &level);
 
     commands.insert_resource(TimeSpeed::Normal);
+    let stock = StartingStock {
+        metal: level.metal,
+        extinguishers: STARTING_EXTINGUISHERS,
+    };
+    commands.insert_resource(stock);
+    commands.insert_resource(Metal::new(stock.metal));
+    commands.insert_resource(Extinguisher::new(stock.extinguishers));
 
-    commands
-        .spawn(SceneBundle {
-            scene: scene_handler[&SceneKey::Pc].clone_weak(),
-            transform: Transform::from_translation(Vec3::new(1.0, 0.5, 6.5))
-                .with_scale(Vec3::splat(0.5)),
-            ..default()
-        })
-        .insert(Selectable)
-        .insert(Pc);
-
-    // Light Blue Tank
-    commands
-        .spawn(SceneBundle {
-            scene: scene_handler[&SceneKey::OxygenTank].clone_weak(),
-            transform: Transform::from_translation(Vec3::new(1.0, 0.1, 2.0))
-                .with_scale(Vec3::splat(1.0)),
-            ..default()
-        })
-        .insert(Selectable);
-
-    // Dark Blue Tank
-    commands
-        .spawn(SceneBundle {
-            scene: scene_handler[&SceneKey::WaterTank].clone_weak(),
-            transform: Transform::from_translation(Vec3::new(1.0, 0.1, 1.0))
-                .with_scale(Vec3::splat(1.0)),
+    if let Some(ship) = &level.ship {
+        commands.spawn(ShipScene).insert(SceneBundle {
+            scene: scene_handler[&SceneKey::Ship].clone_weak(),
+            transform: Transform::from_translation(Vec3::from_array(ship.position)),
             ..default()
-        })
-        .insert(Selectable);
-
-    // Yellow Tank
-    commands
-        .spawn(SceneBundle {
-            scene: scene_handler[&SceneKey::PeeWaterTank].clone_weak(),
-            transform: Transform::from_translation(Vec3::new(2.0, 0.1, 1.0))
-                .with_scale(Vec3::splat(1.0)),
-            ..default()
-        })
-        .insert(Selectable);
-
-    // Brown Tank
-    commands
-        .spawn(SceneBundle {
-            scene: scene_handler[&SceneKey::BadWaterTank].clone_weak(),
-            transform: Transform::from_translation(Vec3::new(3.0, 0.1, 1.0))
-                .with_scale(Vec3::splat(1.0)),
-            ..default()
-        })
-        .insert(Selectable);
-
-    // Red Tank
-    commands
-        .spawn(SceneBundle {
-            scene: scene_handler[&SceneKey::HydrogenTank].clone_weak(),
-            transform: Transform::from_translation(Vec3::new(4.0, 0.1, 1.0))
-                .with_scale(Vec3::splat(1.0)),
-            ..default()
-        })
-        .insert(Selectable);
-
-    commands.add(SpawnOxygenGenerator {
-        pos: Vec3::new(8.0, 0.0, 6.0),
-    });
-
-    commands.add(SpawnKitchen {
-        pos: Vec3::new(1.0, 0.0, 4.5),
-    });
-
-    commands.add(SpawnHydroponic {
-        pos: Vec3::new(1.0, 0.1, 8.0),
-    });
-
-    /*we don't want to have metal trash pile from beginning
-        commands.add(SpawnMetalTrashPile {
-        pos: Vec3::new(6.0, 0.1, 6.0),
-    });
-     */
-
-    commands.add(SpawnToilet {
-        pos: Vec3::new(8.0, 0.0, 7.5),
-        rot: Some(Quat::from_rotation_y((90.0_f32).to_radians())),
-    });
-
-    commands.add(SpawnWaterDispenser {
-        pos: Vec3::new(5.0, 0.0, 1.0),
-        rot: Some(Quat::from_rotation_y((0.0_f32).to_radians())),
-    });
-
-    commands.add(SpawnWaterCleaner {
-        pos: Vec3::new(7.0, 0.0, 1.0),
-        rot: Some(Quat::from_rotation_y((0.0_f32).to_radians())),
-    });
+        });
+    }
+
+    for tank in &level.tanks {
+        commands.add(SpawnTank {
+            scene: tank.scene,
+            pos: Vec3::from_array(tank.position),
+            rot: Some(Quat::from_rotation_y(tank.rotation.to_radians())),
+            scale: tank.scale,
+        });
+    }
+
+    for device in &level.devices {
+        commands.add(SpawnDevice(device.clone()));
+    }
 
     commands.add(SpawnEarth);
+
+    commands.insert_resource(wanted);
+    commands.trigger(NewRun);
+}
+
+/// Sets the ambient light and spawns the night lights of a level
+pub(crate) fn spawn_lights(commands: &mut Commands, level: &Level) {
+    if let Some(ambient) = &level.ambient_light {
+        let [r, g, b] = ambient.color;
+        commands.insert_resource(AmbientLight {
+            brightness: ambient.brightness,
+            color: Color::srgb(r, g, b),
+        });
+    }
+
+    for light in &level.lights {
+        commands.spawn(NightLight).insert(SpotLightBundle {
+            transform: Transform::from_translation(Vec3::from_array(light.position)),
+            spot_light: SpotLight {
+                inner_angle: light.inner_angle.to_radians(),
+                outer_angle: light.outer_angle.to_radians(),
+                ..default()
+            },
+            ..default()
+        });
+    }
 }
 
 fn setup_camera(_: Trigger<SpawnLevel>, mut q_cameras: Query<&mut Transform, With<Camera>>) {
@@ -4,10 +4,12 @@
 
 use bevy::prelude::*;
 
+mod gltf_devices;
+pub mod layout;
 pub mod level;
 pub mod player;
 pub mod spawn_commands;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((level::plugin, player::plugin));
+    app.add_plugins((gltf_devices::plugin, level::plugin, player::plugin));
 }
@@ -6,8 +6,12 @@ use crate::game::{
     assets::{HandleMap, SceneKey},
     billboard_state::{BillboardContent, BillboardSpawner},
     character::CharacterStates,
+    crew::SelectedCrew,
+    jobs::WorkPriorities,
     movement::{Movement, MovementController},
+    needs::needs_bundle,
     sequence::Sequence,
+    steering::Steering,
 };
 
 pub(super) fn plugin(app: &mut App) {
@@ -15,15 +19,23 @@ pub(super) fn plugin(app: &mut App) {
     app.register_type::<Player>();
 }
 
+/// Scale of the crew model
+pub const PLAYER_SCALE: f32 = 0.2;
+
 #[derive(Event, Debug)]
-pub struct SpawnPlayer;
+pub struct SpawnPlayer {
+    pub name: String,
+    pub pos: Vec3,
+    /// Start with this crew member selected
+    pub selected: bool,
+}
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Player;
 
 fn spawn_player(
-    _trigger: Trigger<SpawnPlayer>,
+    trigger: Trigger<SpawnPlayer>,
     mut commands: Commands,
     // mut meshes: ResMut<Assets<Mesh>>,
     // mut materials: ResMut<Assets<StandardMaterial>>,
@@ -35,13 +47,14 @@ fn spawn_player(
     //     ..default()
     // };
 
-    commands.spawn((
-        Name::new("Player"),
+    let event = trigger.event();
+    let mut player = commands.spawn((
+        Name::new(event.name.clone()),
         Player,
         SceneBundle {
             scene: scene_handler[&SceneKey::Player].clone_weak(),
-            transform: Transform::from_translation(Vec3::new(5.0, 0.0, 5.0))
-                .with_scale(Vec3::splat(0.2)),
+            transform: Transform::from_translation(event.pos)
+                .with_scale(Vec3::splat(PLAYER_SCALE)),
             ..default()
         },
         MovementController::default(),
@@ -52,5 +65,12 @@ fn spawn_player(
             size: Vec2::new(1.0, 1.0),
         },
         CharacterStates::default(),
+        needs_bundle(),
+        WorkPriorities::default(),
+        Steering::default(),
     ));
+
+    if event.selected {
+        player.insert(SelectedCrew);
+    }
 }
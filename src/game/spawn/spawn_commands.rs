@@ -9,97 +9,230 @@ use crate::game::{
         earth::Earth,
         hydroponic::{Hydroponic, HydroponicState},
         kitchen::Kitchen,
+        pc::Pc,
     },
     selectable::Selectable, ui::components::hex2color,
 };
 
+/// Scene placed with the given rotation and scale, falling back to the
+/// defaults of the spawner
+fn placed_scene(
+    world: &World,
+    key: SceneKey,
+    pos: Vec3,
+    rot: Option<Quat>,
+    scale: Option<f32>,
+    default_scale: f32,
+) -> SceneBundle {
+    let scene = world.resource::<HandleMap<SceneKey>>()[&key].clone_weak();
+
+    SceneBundle {
+        scene,
+        transform: Transform::from_translation(pos)
+            .with_rotation(rot.unwrap_or_default())
+            .with_scale(Vec3::splat(scale.unwrap_or(default_scale))),
+        ..default()
+    }
+}
+
+pub struct SpawnPc {
+    pub pos: Vec3,
+    pub rot: Option<Quat>,
+    pub scale: Option<f32>,
+}
+
+impl SpawnPc {
+    pub fn spawn(self, world: &mut World) -> Entity {
+        let bundle = placed_scene(world, SceneKey::Pc, self.pos, self.rot, self.scale, 0.5);
+
+        world.spawn(bundle).insert((Selectable, Pc)).id()
+    }
+}
+
+impl Command for SpawnPc {
+    fn apply(self, world: &mut World) {
+        self.spawn(world);
+    }
+}
+
+/// Tanks only show how much of a resource is stored
+pub struct SpawnTank {
+    pub scene: SceneKey,
+    pub pos: Vec3,
+    pub rot: Option<Quat>,
+    pub scale: Option<f32>,
+}
+
+impl Command for SpawnTank {
+    fn apply(self, world: &mut World) {
+        let bundle = placed_scene(world, self.scene, self.pos, self.rot, self.scale, 1.0);
+
+        world.spawn(bundle).insert(Selectable);
+    }
+}
+
 pub struct SpawnOxygenGenerator {
     pub pos: Vec3,
+    pub rot: Option<Quat>,
+    pub scale: Option<f32>,
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct OxygenRecyler;
 
+impl SpawnOxygenGenerator {
+    pub fn spawn(self, world: &mut World) -> Entity {
+        let bundle = placed_scene(
+            world,
+            SceneKey::OxygenGenerator,
+            self.pos,
+            self.rot,
+            self.scale,
+            0.5,
+        );
+
+        world.spawn(bundle).insert(Selectable).insert(OxygenRecyler).id()
+    }
+}
+
 impl Command for SpawnOxygenGenerator {
     fn apply(self, world: &mut World) {
-        let scene =
-            world.resource::<HandleMap<SceneKey>>()[&SceneKey::OxygenGenerator].clone_weak();
-
-        let bundle = SceneBundle {
-            scene,
-            transform: Transform::from_translation(self.pos).with_scale(Vec3::splat(0.5))
-                .with_rotation(Quat::from_axis_angle(Vec3::Y, 0.0 / 2.0)),
-            ..default()
-        };
-
-        world.spawn(bundle).insert(Selectable).insert(OxygenRecyler);
+        self.spawn(world);
     }
 }
 
 pub struct SpawnKitchen {
     pub pos: Vec3,
+    pub rot: Option<Quat>,
+    pub scale: Option<f32>,
 }
 
-impl Command for SpawnKitchen {
-    fn apply(self, world: &mut World) {
-        let scene = world.resource::<HandleMap<SceneKey>>()[&SceneKey::Kitchen].clone_weak();
+impl SpawnKitchen {
+    pub fn spawn(self, world: &mut World) -> Entity {
+        let bundle = placed_scene(world, SceneKey::Kitchen, self.pos, self.rot, self.scale, 0.15);
 
-        let bundle = SceneBundle {
-            scene,
-            transform: Transform::from_translation(self.pos)
-                .with_scale(Vec3::splat(0.15))
-                .with_rotation(Quat::from_rotation_y(0.0 / 2.0)),
-            ..default()
-        };
+        world.spawn(bundle).insert(Selectable).insert(Kitchen::default()).id()
+    }
+}
 
-        world.spawn(bundle).insert(Selectable).insert(Kitchen);
+impl Command for SpawnKitchen {
+    fn apply(self, world: &mut World) {
+        self.spawn(world);
     }
 }
 
 pub struct SpawnHydroponic {
     pub pos: Vec3,
+    pub rot: Option<Quat>,
+    pub scale: Option<f32>,
 }
 
-impl Command for SpawnHydroponic {
-    fn apply(self, world: &mut World) {
-        let scene = world.resource::<HandleMap<SceneKey>>()[&SceneKey::Hydroponic].clone_weak();
-
-        let bundle = SceneBundle {
-            scene,
-            transform: Transform::from_translation(self.pos).with_scale(Vec3::splat(0.5)),
-            ..default()
-        };
+impl SpawnHydroponic {
+    pub fn spawn(self, world: &mut World) -> Entity {
+        let bundle = placed_scene(
+            world,
+            SceneKey::Hydroponic,
+            self.pos,
+            self.rot,
+            self.scale,
+            0.5,
+        );
 
         world
             .spawn(bundle)
             .insert(Selectable)
             .insert(IgnoreJustMoving)
             .insert(Hydroponic::default())
-            .insert(HydroponicState::Growing);
+            .insert(HydroponicState::Growing)
+            .id()
     }
 }
 
-#[derive(Component)]
+impl Command for SpawnHydroponic {
+    fn apply(self, world: &mut World) {
+        self.spawn(world);
+    }
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct MetalTrashPile;
 
 pub struct SpawnMetalTrashPile {
     pub pos: Vec3,
+    pub rot: Option<Quat>,
+    pub scale: Option<f32>,
+}
+
+impl SpawnMetalTrashPile {
+    pub fn spawn(self, world: &mut World) -> Entity {
+        let bundle = placed_scene(
+            world,
+            SceneKey::MetalTrash,
+            self.pos,
+            self.rot,
+            self.scale,
+            0.5,
+        );
+
+        world
+            .spawn(bundle)
+            .insert(Selectable)
+            .insert(MetalTrashPile)
+            .id()
+    }
 }
 
 impl Command for SpawnMetalTrashPile {
     fn apply(self, world: &mut World) {
-        let scene = world.resource::<HandleMap<SceneKey>>()[&SceneKey::MetalTrash].clone_weak();
+        self.spawn(world);
+    }
+}
 
-        let bundle = SceneBundle {
-            scene,
-            transform: Transform::from_translation(self.pos).with_scale(Vec3::splat(0.5)),
-            ..default()
-        };
+/// Smoke and heat sensor, see [`forecast`](crate::game::forecast)
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct Sensor;
+
+pub struct SpawnSensor {
+    pub pos: Vec3,
+    pub rot: Option<Quat>,
+    pub scale: Option<f32>,
+}
+
+impl SpawnSensor {
+    pub fn spawn(self, world: &mut World) -> Entity {
+        // No model for it, a small round box does the job
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Cylinder::new(0.15, 0.1));
+        let material = world
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(StandardMaterial {
+                base_color: Color::srgb(0.9, 0.9, 0.9),
+                emissive: LinearRgba::rgb(0.8, 0.1, 0.1),
+                ..default()
+            });
 
         world
-            .spawn(bundle)
+            .spawn(PbrBundle {
+                mesh,
+                material,
+                transform: Transform::from_translation(self.pos)
+                    .with_rotation(self.rot.unwrap_or_default())
+                    .with_scale(Vec3::splat(self.scale.unwrap_or(1.0))),
+                ..default()
+            })
             .insert(Selectable)
-            .insert(MetalTrashPile);
+            .insert(Sensor)
+            .id()
+    }
+}
+
+impl Command for SpawnSensor {
+    fn apply(self, world: &mut World) {
+        self.spawn(world);
     }
 }
 
@@ -144,76 +277,97 @@ impl Command for SpawnEarth {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct Toilet;
 
 pub struct SpawnToilet {
     pub pos: Vec3,
     pub rot: Option<Quat>,
+    pub scale: Option<f32>,
 }
 
-impl Command for SpawnToilet {
-    fn apply(self, world: &mut World) {
-        let scene = world.resource::<HandleMap<SceneKey>>()[&SceneKey::Toilet].clone_weak();
-        let bundle = SceneBundle {
-            scene,
-            transform: Transform::from_translation(self.pos)
-                .with_rotation(self.rot.unwrap_or_default())
-                .with_scale(Vec3::splat(0.35)),
-            ..default()
-        };
+impl SpawnToilet {
+    pub fn spawn(self, world: &mut World) -> Entity {
+        let bundle = placed_scene(world, SceneKey::Toilet, self.pos, self.rot, self.scale, 0.35);
 
         world
             .spawn(bundle)
-            .insert((Selectable, Toilet, IgnoreJustMoving));
+            .insert((Selectable, Toilet, IgnoreJustMoving))
+            .id()
+    }
+}
+
+impl Command for SpawnToilet {
+    fn apply(self, world: &mut World) {
+        self.spawn(world);
     }
 }
-#[derive(Component)]
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct WaterDispenser;
 
 pub struct SpawnWaterDispenser {
     pub pos: Vec3,
     pub rot: Option<Quat>,
+    pub scale: Option<f32>,
 }
 
-impl Command for SpawnWaterDispenser {
-    fn apply(self, world: &mut World) {
-        let scene = world.resource::<HandleMap<SceneKey>>()[&SceneKey::WaterDispenser].clone_weak();
-        let bundle = SceneBundle {
-            scene,
-            transform: Transform::from_translation(self.pos)
-                .with_rotation(self.rot.unwrap_or_default())
-                .with_scale(Vec3::splat(0.25)),
-            ..default()
-        };
+impl SpawnWaterDispenser {
+    pub fn spawn(self, world: &mut World) -> Entity {
+        let bundle = placed_scene(
+            world,
+            SceneKey::WaterDispenser,
+            self.pos,
+            self.rot,
+            self.scale,
+            0.25,
+        );
 
         world
             .spawn(bundle)
-            .insert((Selectable, WaterDispenser, IgnoreJustMoving));
+            .insert((Selectable, WaterDispenser, IgnoreJustMoving))
+            .id()
     }
 }
 
-#[derive(Component)]
+impl Command for SpawnWaterDispenser {
+    fn apply(self, world: &mut World) {
+        self.spawn(world);
+    }
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct WaterCleaner;
 
 pub struct SpawnWaterCleaner {
     pub pos: Vec3,
     pub rot: Option<Quat>,
+    pub scale: Option<f32>,
 }
 
-impl Command for SpawnWaterCleaner {
-    fn apply(self, world: &mut World) {
-        let scene = world.resource::<HandleMap<SceneKey>>()[&SceneKey::WaterCleaner].clone_weak();
-        let bundle = SceneBundle {
-            scene,
-            transform: Transform::from_translation(self.pos)
-                .with_rotation(self.rot.unwrap_or_default())
-                .with_scale(Vec3::splat(0.30)),
-            ..default()
-        };
+impl SpawnWaterCleaner {
+    pub fn spawn(self, world: &mut World) -> Entity {
+        let bundle = placed_scene(
+            world,
+            SceneKey::WaterCleaner,
+            self.pos,
+            self.rot,
+            self.scale,
+            0.30,
+        );
 
         world
             .spawn(bundle)
-            .insert((Selectable, WaterCleaner, IgnoreJustMoving));
+            .insert((Selectable, WaterCleaner, IgnoreJustMoving))
+            .id()
+    }
+}
+
+impl Command for SpawnWaterCleaner {
+    fn apply(self, world: &mut World) {
+        self.spawn(world);
     }
 }
@@ -0,0 +1,136 @@
+//! Local steering between crew members.
+//! Characters push each other apart when they get too close, and in narrow
+//! spots the one without right of way waits until the other has passed.
+
+use bevy::prelude::*;
+
+use super::{
+    character::{move_player_to_target, DestinationTarget},
+    map::{
+        navigation::{is_free, world_to_tile, NavBlockers, Path},
+        on_storey, storey_of, ShipMap,
+    },
+    spawn::player::Player,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            yield_in_narrow_spots.before(move_player_to_target),
+            separate_crew.after(move_player_to_target),
+        ),
+    );
+}
+
+/// Characters closer than this push each other away
+const SEPARATION_RADIUS: f32 = 0.5;
+/// Distance at which a character waits for another one walking towards it
+const YIELD_DISTANCE: f32 = 1.2;
+
+#[derive(Component, Default)]
+pub struct Steering {
+    /// Waiting for another character to pass
+    pub yielding: bool,
+}
+
+fn flat(v: Vec3) -> Vec3 {
+    Vec3::new(v.x, 0.0, v.z)
+}
+
+/// A tile with at most two free sides, like a corridor or a door
+fn is_narrow(map: &ShipMap, blockers: &NavBlockers, tile: IVec2) -> bool {
+    [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y]
+        .iter()
+        .filter(|offset| is_free(map, blockers, tile + **offset))
+        .count()
+        <= 2
+}
+
+fn yield_in_narrow_spots(
+    q_maps: Query<&ShipMap>,
+    blockers: Res<NavBlockers>,
+    mut q_crew: Query<(Entity, &Transform, &mut Steering, Option<&Path>), With<Player>>,
+) {
+    // Position and walking direction of everyone
+    let walkers: Vec<(Entity, Vec3, Option<Vec3>)> = q_crew
+        .iter()
+        .map(|(entity, transform, _, path)| {
+            let direction = path
+                .and_then(|path| path.next())
+                .map(|next| flat(next - transform.translation).normalize_or_zero());
+            (entity, transform.translation, direction)
+        })
+        .collect();
+
+    for (entity, transform, mut steering, _) in q_crew.iter_mut() {
+        let Some(&(_, position, Some(direction))) = walkers.iter().find(|w| w.0 == entity) else {
+            steering.yielding = false;
+            continue;
+        };
+
+        let Some(map) = on_storey(q_maps.iter(), storey_of(position)) else {
+            steering.yielding = false;
+            continue;
+        };
+
+        steering.yielding = walkers
+            .iter()
+            .any(|(other, other_position, other_direction)| {
+                let Some(other_direction) = other_direction else {
+                    return false;
+                };
+                let offset = flat(*other_position - position);
+
+                // Lower entities have right of way, so two characters never wait for each other
+                *other < entity
+                    && storey_of(*other_position) == map.storey()
+                    && offset.length() < YIELD_DISTANCE
+                    && offset.normalize_or_zero().dot(direction) > 0.5
+                    && other_direction.dot(direction) < 0.0
+                    && (is_narrow(map, &blockers, world_to_tile(transform.translation))
+                        || is_narrow(map, &blockers, world_to_tile(*other_position)))
+            });
+    }
+}
+
+fn separate_crew(
+    q_maps: Query<&ShipMap>,
+    blockers: Res<NavBlockers>,
+    mut q_crew: Query<(&mut Transform, Has<DestinationTarget>), With<Player>>,
+) {
+    let mut combinations = q_crew.iter_combinations_mut();
+    while let Some([(mut a, a_walking), (mut b, b_walking)]) = combinations.fetch_next() {
+        let offset = flat(b.translation - a.translation);
+        let distance = offset.length();
+        let storey = storey_of(a.translation);
+        if distance >= SEPARATION_RADIUS || storey != storey_of(b.translation) {
+            continue;
+        }
+
+        // Standing on the same spot, pick any direction
+        let direction = offset.try_normalize().unwrap_or(Vec3::X);
+        let overlap = SEPARATION_RADIUS - distance;
+
+        // Walking characters move out of the way of the ones using a device
+        let (a_share, b_share) = match (a_walking, b_walking) {
+            (true, false) => (1.0, 0.0),
+            (false, true) => (0.0, 1.0),
+            _ => (0.5, 0.5),
+        };
+
+        let map = on_storey(q_maps.iter(), storey);
+        let can_stand = |position: Vec3| {
+            map.map_or(true, |map| is_free(map, &blockers, world_to_tile(position)))
+        };
+
+        let a_target = a.translation - direction * overlap * a_share;
+        if can_stand(a_target) {
+            a.translation = a_target;
+        }
+        let b_target = b.translation + direction * overlap * b_share;
+        if can_stand(b_target) {
+            b.translation = b_target;
+        }
+    }
+}
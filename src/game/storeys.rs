@@ -0,0 +1,295 @@
+//! Floors of the apartment.
+//! Every floor has its own [`ShipMap`], stacked on top of each other. New
+//! floors are bought with more debt, and an elevator shaft on the same tile
+//! of every floor lets the crew walk between them. The floor selector moves
+//! the camera and hides everything above the viewed floor.
+
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+
+use crate::{screen::Screen, ui::prelude::*};
+
+use super::{
+    debt::Debt,
+    input::{ActionState, InputAction},
+    map::{
+        map_generator::walled_map,
+        navigation::{NavBlockers, Path},
+        on_storey, storey_height, storey_of, ShipMap, Tile,
+    },
+    selectable::Selectable,
+    spawn::{level::SpawnLevel, player::Player},
+    ui::game_over::ResetGame,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<ViewedStorey>();
+    app.observe(spawn_storey_panel);
+    app.add_systems(
+        Update,
+        (
+            select_storey,
+            move_camera,
+            hide_upper_storeys,
+            update_storey_panel,
+            reset_storeys,
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// Floors the apartment can grow to, the ground floor included
+const MAX_STOREYS: usize = 4;
+/// Debt taken for the first floor bought, each next one costs as much more
+const STOREY_PRICE: f32 = 5000.0;
+
+/// Floor shown by the camera
+#[derive(Resource, Default)]
+pub struct ViewedStorey {
+    pub storey: usize,
+    /// Floor the camera is placed for
+    shown: usize,
+}
+
+#[derive(Component)]
+struct StoreyPanel;
+
+#[derive(Component)]
+struct StoreyLabel;
+
+#[derive(Component)]
+struct BuyStoreyButton;
+
+const fn storey_price(storey: usize) -> f32 {
+    STOREY_PRICE * storey as f32
+}
+
+fn top_storey<'a>(maps: impl IntoIterator<Item = &'a ShipMap>) -> usize {
+    maps.into_iter().map(ShipMap::storey).max().unwrap_or(0)
+}
+
+/// Floor tile for the elevator: a free room corner, away from doors
+fn shaft_tile(map: &ShipMap, blockers: &NavBlockers) -> Option<IVec2> {
+    let sides = [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y];
+
+    (0..map.height() as i32)
+        .flat_map(|y| (0..map.width() as i32).map(move |x| IVec2::new(x, y)))
+        .filter(|tile| {
+            matches!(map.get(*tile), Some(Tile::Floor))
+                && !blockers.is_blocked(map.storey(), *tile)
+                && !sides
+                    .iter()
+                    .any(|side| matches!(map.get(*tile + *side), Some(Tile::Door { .. })))
+        })
+        .max_by_key(|tile| {
+            // Most walls around first, then the tile closest to the origin
+            let walls = sides
+                .iter()
+                .filter(|side| matches!(map.get(*tile + **side), Some(Tile::Wall)))
+                .count();
+            (walls, -(tile.x + tile.y))
+        })
+}
+
+fn buy_storey(
+    mut commands: Commands,
+    mut q_maps: Query<&mut ShipMap>,
+    blockers: Res<NavBlockers>,
+    mut debt: ResMut<Debt>,
+) {
+    let storey = top_storey(q_maps.iter()) + 1;
+    if storey >= MAX_STOREYS {
+        return;
+    }
+    let Some(mut ground) = q_maps.iter_mut().find(|map| map.storey() == 0) else {
+        return;
+    };
+
+    let shaft = match ground.elevator() {
+        Some(shaft) => shaft,
+        None => {
+            let Some(shaft) = shaft_tile(&ground, &blockers) else {
+                warn!("No room for an elevator");
+                return;
+            };
+            ground.set(shaft.x as usize, shaft.y as usize, Tile::Elevator);
+            shaft
+        }
+    };
+
+    let mut map = walled_map(ground.width(), ground.height()).on_storey(storey);
+    map.set(shaft.x as usize, shaft.y as usize, Tile::Elevator);
+
+    debt.amount += storey_price(storey);
+    info!("Floor {} bought for {}", storey + 1, storey_price(storey));
+
+    commands.spawn((
+        Name::new(format!("Floor {}", storey + 1)),
+        SpatialBundle::from_transform(Transform::from_xyz(0.0, storey_height(storey), 0.0)),
+        map,
+    ));
+}
+
+fn spawn_storey_panel(
+    _trigger: Trigger<SpawnLevel>,
+    mut commands: Commands,
+    mut viewed: ResMut<ViewedStorey>,
+    q_panel: Query<(), With<StoreyPanel>>,
+) {
+    // The camera is placed back on the ground floor
+    *viewed = ViewedStorey::default();
+    if !q_panel.is_empty() {
+        return;
+    }
+
+    commands
+        .panel(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(4.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        })
+        .insert((Name::new("Floor selector"), StoreyPanel))
+        .with_children(|builder| {
+            for (label, step) in [("Down", -1), ("Up", 1)] {
+                builder
+                    .small_button(label)
+                    .insert(On::<Pointer<Click>>::run(
+                        move |mut viewed: ResMut<ViewedStorey>, q_maps: Query<&ShipMap>| {
+                            viewed.storey = viewed
+                                .storey
+                                .saturating_add_signed(step)
+                                .min(top_storey(q_maps.iter()));
+                        },
+                    ));
+            }
+            builder.small_label("").insert(StoreyLabel);
+            builder
+                .small_button("")
+                .insert((BuyStoreyButton, On::<Pointer<Click>>::run(buy_storey)));
+        });
+}
+
+fn select_storey(
+    actions: Res<ActionState>,
+    mut viewed: ResMut<ViewedStorey>,
+    q_maps: Query<&ShipMap>,
+) {
+    let top = top_storey(q_maps.iter());
+    if actions.just_pressed(InputAction::StoreyUp) {
+        viewed.storey = (viewed.storey + 1).min(top);
+    }
+    if actions.just_pressed(InputAction::StoreyDown) {
+        viewed.storey = viewed.storey.saturating_sub(1);
+    }
+    // The floor was taken away
+    if viewed.storey > top {
+        viewed.storey = top;
+    }
+}
+
+fn move_camera(
+    mut viewed: ResMut<ViewedStorey>,
+    mut q_cameras: Query<&mut Transform, With<IsDefaultUiCamera>>,
+) {
+    if viewed.storey == viewed.shown {
+        return;
+    }
+
+    let rise = storey_height(viewed.storey) - storey_height(viewed.shown);
+    for mut transform in q_cameras.iter_mut() {
+        transform.translation.y += rise;
+    }
+    viewed.shown = viewed.storey;
+}
+
+/// Floors above the viewed one are hidden, with everything on them
+fn hide_upper_storeys(
+    viewed: Res<ViewedStorey>,
+    mut q_visible: Query<
+        (&GlobalTransform, &mut Visibility),
+        Or<(With<ShipMap>, With<Selectable>, With<Player>)>,
+    >,
+) {
+    for (transform, mut visibility) in q_visible.iter_mut() {
+        let shown = if storey_of(transform.translation()) > viewed.storey {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        visibility.set_if_neq(shown);
+    }
+}
+
+fn update_storey_panel(
+    viewed: Res<ViewedStorey>,
+    q_maps: Query<&ShipMap>,
+    mut q_floor: Query<&mut Text, With<StoreyLabel>>,
+    q_buy: Query<&Children, With<BuyStoreyButton>>,
+    mut q_text: Query<&mut Text, Without<StoreyLabel>>,
+) {
+    let top = top_storey(q_maps.iter());
+    for mut text in q_floor.iter_mut() {
+        text.sections[0].value = format!("Floor {}/{}", viewed.storey + 1, top + 1);
+    }
+    let buy = if top + 1 >= MAX_STOREYS {
+        "Top floor".to_string()
+    } else {
+        format!("Buy floor ({} debt)", storey_price(top + 1))
+    };
+    for children in q_buy.iter() {
+        let mut texts = q_text.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value.clone_from(&buy);
+        }
+    }
+}
+
+/// Bought floors are given back with the elevator, crew up there takes it
+/// down first
+fn reset_storeys(
+    mut commands: Commands,
+    mut resets: EventReader<ResetGame>,
+    mut viewed: ResMut<ViewedStorey>,
+    mut q_maps: Query<(Entity, &mut ShipMap)>,
+    q_devices: Query<(Entity, &GlobalTransform), With<Selectable>>,
+    mut q_crew: Query<(Entity, &mut Transform), With<Player>>,
+) {
+    for _ in resets.read() {
+        viewed.storey = 0;
+
+        let shaft = on_storey(q_maps.iter().map(|(_, map)| map), 0).and_then(ShipMap::elevator);
+        for (entity, mut map) in q_maps.iter_mut() {
+            if map.storey() > 0 {
+                commands.entity(entity).despawn_recursive();
+            } else if let Some(shaft) = shaft {
+                map.set(shaft.x as usize, shaft.y as usize, Tile::Floor);
+            }
+        }
+        for (entity, transform) in q_devices.iter() {
+            if storey_of(transform.translation()) > 0 {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        let Some(shaft) = shaft else {
+            continue;
+        };
+        for (entity, mut transform) in q_crew.iter_mut() {
+            let storey = storey_of(transform.translation);
+            if storey == 0 {
+                continue;
+            }
+            transform.translation = Vec3::new(
+                shaft.x as f32,
+                transform.translation.y - storey_height(storey),
+                shaft.y as f32,
+            );
+            commands.entity(entity).remove::<Path>();
+        }
+    }
+}
@@ -1,15 +1,20 @@
 use crate::game::daycycle::GameTime;
 use bevy::prelude::*;
-use rand::prelude::*;
+use rand::{distributions::WeightedIndex, prelude::*, rngs::StdRng};
 use rand_distr::{Distribution, Poisson};
 
 use super::{
     assets::{HandleMap, SceneKey},
-    character::DestinationTarget,
-    components::fire::InFire,
+    build::build_cost,
+    components::{device_kind::DeviceKind, fire::InFire},
+    daycycle::DayPassed,
     difficult::FIRE_MEAN_PERIOD,
+    durability::{Durability, MAX_CONDITION},
+    hazard::{Hazard, TroubleKind},
+    metal_trash::TrashLeft,
+    rng::GameRng,
     selectable::Selectable,
-    spawn::{player::Player, spawn_commands::MetalTrashPile},
+    spawn::spawn_commands::MetalTrashPile,
     ui::game_over::ResetGame,
 };
 
@@ -17,18 +22,32 @@ use super::{
 pub struct TroublePlanner {
     pub peace_time: f32,
     pub distribution: f32,
+    /// Trouble picked [`FORECAST_TIME`] before it happens
+    pub upcoming: Option<PlannedTrouble>,
 }
 pub const DEFAULT_PEACE_TIME: f32 = FIRE_MEAN_PERIOD / 3.0;
 pub const DEFAULT_DISTRIBUTION: f32 = FIRE_MEAN_PERIOD;
+/// Seconds before a trouble happens that its kind and target are known
+pub const FORECAST_TIME: f32 = 5.0;
+/// How much more likely a worn out device is hit than a new one
+const WEAR_BIAS: f32 = 3.0;
+/// Part of the building cost left as metal trash by a destroyed device
+const WRECK_TRASH: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug)]
+pub struct PlannedTrouble {
+    pub kind: TroubleKind,
+    pub target: Entity,
+}
 
 pub(crate) fn plugin(app: &mut App) {
     app.insert_resource(TroublePlanner {
         peace_time: DEFAULT_PEACE_TIME,
         distribution: DEFAULT_DISTRIBUTION,
+        upcoming: None,
     });
 
     app.add_systems(Update, (reset_earth, plan_trouble));
-    app.add_systems(Update, fix_trouble);
     app.add_systems(PostUpdate, tick_fire);
 }
 
@@ -37,79 +56,151 @@ fn reset_earth(mut commands: Commands, mut reset: EventReader<ResetGame>) {
         commands.insert_resource(TroublePlanner {
             peace_time: DEFAULT_PEACE_TIME,
             distribution: DEFAULT_DISTRIBUTION,
+            upcoming: None,
         });
     }
 }
 
+/// Chance of every trouble on every device to be the next one.
+/// Only the kinds that have a device to hit can happen, and worn devices are
+/// more likely to be hit.
+pub fn trouble_odds<'a>(
+    days: u32,
+    devices: impl IntoIterator<Item = (Entity, &'a DeviceKind, Option<&'a Durability>)>,
+) -> Vec<(PlannedTrouble, f32)> {
+    let devices = devices.into_iter().collect::<Vec<_>>();
+    let device_weight = |durability: Option<&Durability>| {
+        let wear = 1.0 - durability.map_or(MAX_CONDITION, |d| d.condition) / MAX_CONDITION;
+        1.0 + WEAR_BIAS * wear
+    };
+
+    let kinds = TroubleKind::ALL.map(|kind| {
+        let targets = devices
+            .iter()
+            .filter(|(_, device, _)| kind.can_hit(**device))
+            .map(|(_, _, durability)| device_weight(*durability))
+            .sum::<f32>();
+        let weight = if targets > 0.0 {
+            kind.weight(days)
+        } else {
+            0.0
+        };
+        (weight, targets)
+    });
+    let total = kinds.iter().map(|(weight, _)| weight).sum::<f32>();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut odds = Vec::new();
+    for (kind, (weight, targets)) in TroubleKind::ALL.into_iter().zip(kinds) {
+        if weight <= 0.0 {
+            continue;
+        }
+        for (target, device, durability) in devices.iter() {
+            if kind.can_hit(**device) {
+                let chance = weight / total * device_weight(*durability) / targets;
+                odds.push((
+                    PlannedTrouble {
+                        kind,
+                        target: *target,
+                    },
+                    chance,
+                ));
+            }
+        }
+    }
+    odds
+}
+
 fn plan_trouble(
     mut commands: Commands,
     mut trouble_planner: ResMut<TroublePlanner>,
     time: Res<GameTime>,
-    q_selectable: Query<Entity, (With<Selectable>, Without<InFire>, Without<MetalTrashPile>)>,
+    days: Res<DayPassed>,
+    mut rng: ResMut<GameRng>,
+    q_devices: Query<
+        (Entity, &DeviceKind, Option<&Durability>),
+        (Without<InFire>, Without<Hazard>),
+    >,
 ) {
     trouble_planner.peace_time -= time.delta_seconds();
+    let rng = rng.gameplay();
+
+    let pick = |rng: &mut StdRng| {
+        let odds = trouble_odds(days.days(), q_devices.iter());
+        let index = WeightedIndex::new(odds.iter().map(|(_, chance)| *chance)).ok()?;
+        Some(odds[index.sample(rng)].0)
+    };
+
+    if trouble_planner.upcoming.is_none() && trouble_planner.peace_time <= FORECAST_TIME {
+        trouble_planner.upcoming = pick(rng);
+    }
 
     if trouble_planner.peace_time <= 0.0 {
-        let items = q_selectable.iter().collect::<Vec<_>>();
-        if !items.is_empty() {
-            let mut rng = rand::thread_rng();
-            let index = rng.gen_range(0..items.len());
-
-            commands.entity(items[index]).insert(InFire {
-                fire_created: false,
-                started_at: time.elapsed_seconds(),
-            });
+        // Something else may have happened to the target in the meantime
+        let trouble = trouble_planner
+            .upcoming
+            .take()
+            .filter(|trouble| q_devices.contains(trouble.target))
+            .or_else(|| pick(rng));
+        if let Some(trouble) = trouble {
+            trouble
+                .kind
+                .start(&mut commands, trouble.target, time.elapsed_seconds());
         }
 
         let poi = Poisson::new(trouble_planner.distribution).unwrap();
-        let v = poi.sample(&mut rand::thread_rng());
+        let v = poi.sample(rng);
         trouble_planner.peace_time = v;
         trouble_planner.distribution *= 0.99;
     }
 }
 
-fn fix_trouble(
-    mut commands: Commands,
-    mut query: Query<(&mut Transform, &DestinationTarget), With<Player>>,
-    q_items_in_fire: Query<(Entity, &InFire)>,
-    time: Res<GameTime>,
-) {
-    for (transform, target) in query.iter_mut() {
-        let player_position = transform.translation;
-        let target_position = target.target_pos;
-
-        let distance = Vec3::new(player_position.x, 0.0, player_position.z).distance(Vec3::new(target_position.x, 0.0, target_position.z));
-
-        if distance <= target.accept_radius {
-            for (items_in_fire, fire) in q_items_in_fire.iter() {
-                if target.target == items_in_fire {
-                    let alive_time = time.elapsed_seconds() - fire.started_at;
-                    info!("Fire was alive for {} seconds", alive_time);
-                    commands.entity(target.target).remove::<InFire>();
-                }
-            }
-        }
-    }
-}
-
 // Fire will destroy things if they are burning for X amount of time
 fn tick_fire(
     mut commands: Commands,
     scene_handler: Res<HandleMap<SceneKey>>,
-    q_items_in_fire: Query<(Entity, &InFire, &Transform)>,
+    q_items_in_fire: Query<(Entity, &InFire, &Transform, Option<&DeviceKind>)>,
     gametime: Res<GameTime>,
 ) {
-    for (entity, fire, transform) in q_items_in_fire.iter() {
+    for (entity, fire, transform, kind) in q_items_in_fire.iter() {
         if fire.time_ended(gametime.elapsed_seconds()) {
-            commands
-                .spawn(SceneBundle {
-                    scene: scene_handler[&SceneKey::MetalTrash].clone_weak(),
-                    transform: *transform,
-                    ..default()
-                })
-                .insert(Selectable)
-                .insert(MetalTrashPile);
-            commands.entity(entity).despawn_recursive();
+            wreck(&mut commands, &scene_handler, entity, kind, transform);
         }
     }
 }
+
+/// What stood where a trash pile lies, so that it can be rebuilt
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Wreckage {
+    pub kind: DeviceKind,
+    pub transform: Transform,
+}
+
+/// Replaces a destroyed device with a pile of metal trash
+pub fn wreck(
+    commands: &mut Commands,
+    scene_handler: &HandleMap<SceneKey>,
+    device: Entity,
+    kind: Option<&DeviceKind>,
+    transform: &Transform,
+) {
+    let mut pile = commands.spawn(SceneBundle {
+        scene: scene_handler[&SceneKey::MetalTrash].clone_weak(),
+        transform: *transform,
+        ..default()
+    });
+    // A burned pile leaves nothing more to gather
+    let trash = kind.map_or(0.0, |kind| build_cost(*kind) * WRECK_TRASH);
+    pile.insert(Selectable)
+        .insert(MetalTrashPile)
+        .insert(TrashLeft(trash));
+    if let Some(&kind) = kind.filter(|kind| **kind != DeviceKind::MetalTrashPile) {
+        pile.insert(Wreckage {
+            kind,
+            transform: *transform,
+        });
+    }
+    commands.entity(device).despawn_recursive();
+}
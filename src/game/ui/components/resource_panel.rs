@@ -339,6 +339,22 @@ unsafe fn resources(cell: &mut UnsafeWorldCell, style: &ResourcePanelStyle) -> N
                 ..default()
             }),
         )
+        .with_child(
+            TextBundle::from_section("Herbs", style.text.clone()).with_style(Style {
+                align_self: AlignSelf::Center,
+                ..default()
+            }),
+        )
+        .with_child(
+            TextBundle::from_section(
+                format!("{}", cell.world().resource::<Herbs>().amount()),
+                style.text.clone(),
+            )
+            .with_style(Style {
+                align_self: AlignSelf::Center,
+                ..default()
+            }),
+        )
 }
 
 /// Resource bar <-----------------------------------------------------------------------
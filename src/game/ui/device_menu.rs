@@ -0,0 +1,114 @@
+//! Context menu of a device, opened with a right click on it.
+//! Every entry is a choice applied to the world when clicked, which also
+//! closes the menu.
+
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+
+use crate::{
+    game::selectable::CloseContextMenu,
+    ui::{
+        palette::{BUTTON_HOVERED_BACKGROUND, BUTTON_PRESSED_BACKGROUND, NODE_BACKGROUND},
+        prelude::*,
+    },
+};
+
+use super::game_over::ResetGame;
+
+pub(super) fn plugin(app: &mut App) {
+    app.observe(close_device_menu);
+    app.add_systems(PostUpdate, reset_device_menu);
+}
+
+#[derive(Component)]
+pub struct DeviceMenu;
+
+/// Opens a menu over the device, in place of any other one. Entries are
+/// their label, whether they are the current choice, and the choice itself.
+pub fn spawn_device_menu<F>(
+    commands: &mut Commands,
+    q_cameras: &Query<(&Camera, &GlobalTransform), With<IsDefaultUiCamera>>,
+    q_menus: &Query<Entity, With<DeviceMenu>>,
+    device_position: Vec3,
+    title: &str,
+    entries: impl IntoIterator<Item = (String, bool, F)>,
+) where
+    F: Fn(&mut World) + Clone + Send + Sync + 'static,
+{
+    let Ok((camera, camera_transform)) = q_cameras.get_single() else {
+        return;
+    };
+    let Some(position) = camera.world_to_viewport(camera_transform, device_position) else {
+        return;
+    };
+
+    for menu in q_menus.iter() {
+        commands.entity(menu).despawn_recursive();
+    }
+
+    commands
+        .panel(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(position.x),
+            top: Val::Px(position.y),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        })
+        .insert((
+            Name::new(format!("{} menu", title)),
+            DeviceMenu,
+            ZIndex::Global(10),
+        ))
+        .with_children(|menu| {
+            menu.small_label(title);
+            for (label, current, choose) in entries {
+                // The current choice stays highlighted
+                let background = if current {
+                    BUTTON_PRESSED_BACKGROUND
+                } else {
+                    NODE_BACKGROUND
+                };
+                menu.small_button(label).insert((
+                    BackgroundColor(background),
+                    InteractionPalette {
+                        none: background,
+                        hovered: BUTTON_HOVERED_BACKGROUND,
+                        pressed: BUTTON_PRESSED_BACKGROUND,
+                    },
+                    On::<Pointer<Click>>::run(
+                        move |mut commands: Commands, q_menus: Query<Entity, With<DeviceMenu>>| {
+                            let choose = choose.clone();
+                            commands.add(move |world: &mut World| choose(world));
+                            for menu in q_menus.iter() {
+                                commands.entity(menu).despawn_recursive();
+                            }
+                        },
+                    ),
+                ));
+            }
+        });
+}
+
+fn close_device_menu(
+    _trigger: Trigger<CloseContextMenu>,
+    mut commands: Commands,
+    q_menus: Query<Entity, With<DeviceMenu>>,
+) {
+    for menu in q_menus.iter() {
+        commands.entity(menu).despawn_recursive();
+    }
+}
+
+fn reset_device_menu(
+    mut commands: Commands,
+    mut resets: EventReader<ResetGame>,
+    q_menus: Query<Entity, With<DeviceMenu>>,
+) {
+    for _ in resets.read() {
+        for menu in q_menus.iter() {
+            commands.entity(menu).despawn_recursive();
+        }
+    }
+}
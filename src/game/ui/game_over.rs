@@ -5,7 +5,7 @@ use bevy_mod_picking::{
 };
 
 use crate::{
-    game::daycycle::GameOver,
+    game::{daycycle::GameOver, rng::GameRng},
     ui::{
         palette::{
             BUTTON_HOVERED_BACKGROUND, BUTTON_PRESSED_BACKGROUND, BUTTON_TEXT, NODE_BACKGROUND,
@@ -52,7 +52,11 @@ fn remove_end_screen_on_reset(
 #[derive(Component)]
 pub(crate) struct GameOverScreen;
 
-fn spawn_game_over_screen(mut commands: Commands, mut death_event: EventReader<GameOver>) {
+fn spawn_game_over_screen(
+    mut commands: Commands,
+    mut death_event: EventReader<GameOver>,
+    rng: Res<GameRng>,
+) {
     let Some(event) = death_event.read().next() else {
         return;
     };
@@ -88,12 +92,19 @@ fn spawn_game_over_screen(mut commands: Commands, mut death_event: EventReader<G
                         },
                     ),
                     TextSection::new(
-                        event.tagline.clone(),
+                        format!("{}\n", event.tagline),
                         TextStyle {
                             font_size: 40.0,
                             ..default()
                         },
                     ),
+                    TextSection::new(
+                        format!("Seed: {}", rng.seed()),
+                        TextStyle {
+                            font_size: 24.0,
+                            ..default()
+                        },
+                    ),
                 ])
                 .with_text_justify(JustifyText::Center),
             );
@@ -10,8 +10,10 @@ pub mod components;
 mod computer_menu;
 pub mod constants;
 mod context_menu;
+pub mod device_menu;
 pub mod game_over;
 mod root;
+mod work_priorities;
 
 pub(super) fn plugin(app: &mut App) {
     app.init_resource::<SelectedItem>();
@@ -24,6 +26,8 @@ pub(super) fn plugin(app: &mut App) {
     app.add_plugins(components::resource_panel::plugin);
     app.add_plugins(components::debt::plugin);
     app.add_plugins(game_over::plugin);
+    app.add_plugins(work_priorities::plugin);
+    app.add_plugins(device_menu::plugin);
 }
 
 #[derive(Event, Debug)]
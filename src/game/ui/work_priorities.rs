@@ -0,0 +1,165 @@
+//! Work priority grid.
+//! One row per crew member and one column per [`WorkType`]. Clicking a cell
+//! cycles the priority the crew member gives to that work.
+
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+
+use crate::{
+    game::{
+        input::{ActionState, InputAction},
+        jobs::{WorkPriorities, WorkType},
+        spawn::player::Player,
+    },
+    screen::Screen,
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (toggle_work_priorities, update_cells)
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+#[derive(Component)]
+struct WorkPrioritiesPanel;
+
+#[derive(Component)]
+struct PriorityCell {
+    crew: Entity,
+    work: WorkType,
+}
+
+const CELL_WIDTH: f32 = 80.0;
+const CELL_HEIGHT: f32 = 30.0;
+
+fn toggle_work_priorities(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    q_panel: Query<Entity, With<WorkPrioritiesPanel>>,
+    q_crew: Query<(Entity, &Name), (With<Player>, With<WorkPriorities>)>,
+) {
+    if !actions.just_pressed(InputAction::ToggleWorkPriorities) {
+        return;
+    }
+
+    if let Ok(panel) = q_panel.get_single() {
+        commands.entity(panel).despawn_recursive();
+        return;
+    }
+
+    let cell_style = Style {
+        width: Val::Px(CELL_WIDTH),
+        height: Val::Px(CELL_HEIGHT),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let row_style = Style {
+        flex_direction: FlexDirection::Row,
+        column_gap: Val::Px(4.0),
+        ..default()
+    };
+
+    commands
+        .panel(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            padding: UiRect::all(Val::Px(10.0)),
+            ..default()
+        })
+        .insert((Name::new("Work priorities"), WorkPrioritiesPanel))
+        .with_children(|builder| {
+            builder
+                .spawn(NodeBundle {
+                    style: row_style.clone(),
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn(NodeBundle {
+                        style: cell_style.clone(),
+                        ..default()
+                    })
+                    .with_children(|cell| {
+                        cell.small_label("Work (J)");
+                    });
+                    for work in WorkType::ALL {
+                        row.spawn(NodeBundle {
+                            style: cell_style.clone(),
+                            ..default()
+                        })
+                        .with_children(|cell| {
+                            cell.small_label(work.label());
+                        });
+                    }
+                });
+
+            for (crew, name) in q_crew.iter() {
+                builder
+                    .spawn(NodeBundle {
+                        style: row_style.clone(),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn(NodeBundle {
+                            style: cell_style.clone(),
+                            ..default()
+                        })
+                        .with_children(|cell| {
+                            cell.small_label(name.as_str());
+                        });
+
+                        for work in WorkType::ALL {
+                            row.small_button("").insert((
+                                PriorityCell { crew, work },
+                                cell_style.clone(),
+                                On::<Pointer<Click>>::run(
+                                    |event: Listener<Pointer<Click>>,
+                                     q_cells: Query<&PriorityCell>,
+                                     mut q_priorities: Query<&mut WorkPriorities>| {
+                                        let Ok(cell) = q_cells.get(event.listener()) else {
+                                            return;
+                                        };
+                                        if let Ok(mut priorities) = q_priorities.get_mut(cell.crew)
+                                        {
+                                            priorities.cycle(cell.work);
+                                        }
+                                    },
+                                ),
+                            ));
+                        }
+                    });
+            }
+        });
+}
+
+fn update_cells(
+    q_cells: Query<(&PriorityCell, &Children)>,
+    q_priorities: Query<&WorkPriorities>,
+    mut q_text: Query<&mut Text>,
+) {
+    for (cell, children) in q_cells.iter() {
+        let Ok(priorities) = q_priorities.get(cell.crew) else {
+            continue;
+        };
+
+        let value = match priorities.get(cell.work) {
+            0 => "-".to_string(),
+            priority => priority.to_string(),
+        };
+
+        for child in children.iter() {
+            if let Ok(mut text) = q_text.get_mut(*child) {
+                if text.sections[0].value != value {
+                    text.sections[0].value.clone_from(&value);
+                }
+            }
+        }
+    }
+}
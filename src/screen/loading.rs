@@ -5,7 +5,7 @@ use bevy::prelude::*;
 
 use super::Screen;
 use crate::{
-    game::assets::{HandleMap, ImageKey, SceneKey, SfxKey, SoundtrackKey},
+    game::assets::{HandleMap, ImageKey, LevelKey, SceneKey, SfxKey, SoundtrackKey},
     ui::prelude::*,
 };
 
@@ -32,11 +32,13 @@ fn all_assets_loaded(
     sfx_handles: Res<HandleMap<SfxKey>>,
     soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
     scene_handles: Res<HandleMap<SceneKey>>,
+    level_handles: Res<HandleMap<LevelKey>>,
 ) -> bool {
     image_handles.all_loaded(&asset_server)
         && sfx_handles.all_loaded(&asset_server)
         && soundtrack_handles.all_loaded(&asset_server)
         && scene_handles.all_loaded(&asset_server)
+        && level_handles.all_loaded(&asset_server)
 }
 
 fn continue_to_title(mut next_screen: ResMut<NextState<Screen>>) {
@@ -0,0 +1,236 @@
+//! A settings screen to rebind the keys of every input action.
+
+use bevy::prelude::*;
+
+use super::Screen;
+use crate::{
+    game::input::{action_just_pressed, InputAction, InputBinding, InputMap},
+    ui::{
+        palette::{BUTTON_TEXT, LABEL_TEXT},
+        prelude::*,
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Rebinding>();
+    app.add_systems(OnEnter(Screen::Settings), enter_settings);
+    app.add_systems(OnExit(Screen::Settings), exit_settings);
+
+    app.add_systems(
+        Update,
+        (
+            handle_settings_action,
+            leave_settings.run_if(action_just_pressed(InputAction::Menu)),
+            capture_key,
+            update_binding_texts,
+        )
+            .chain()
+            .run_if(in_state(Screen::Settings)),
+    );
+    app.register_type::<SettingsAction>();
+}
+
+const CONFLICT_TEXT: Color = Color::srgb(0.9, 0.3, 0.3);
+const ROW_HEIGHT: f32 = 26.0;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum SettingsAction {
+    Rebind(InputAction),
+    Defaults,
+    Back,
+}
+
+/// Action waiting for the player to press its new key
+#[derive(Resource, Default)]
+struct Rebinding(Option<InputAction>);
+
+/// Button showing the keyboard bindings of the action
+#[derive(Component)]
+struct KeyButton(InputAction);
+
+/// Gamepad bindings of the action
+#[derive(Component)]
+struct GamepadText(InputAction);
+
+#[derive(Component)]
+struct StatusText;
+
+fn enter_settings(mut commands: Commands) {
+    let cell_style = |width: f32| Style {
+        width: Val::Px(width),
+        height: Val::Px(ROW_HEIGHT),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Settings))
+        .with_children(|children| {
+            children.header("Controls");
+
+            for action in InputAction::ALL {
+                children
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: Val::Px(8.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn(NodeBundle {
+                            style: cell_style(180.0),
+                            ..default()
+                        })
+                        .with_children(|cell| {
+                            cell.small_label(action.label());
+                        });
+
+                        row.small_button("").insert((
+                            cell_style(260.0),
+                            SettingsAction::Rebind(action),
+                            KeyButton(action),
+                        ));
+
+                        row.spawn(NodeBundle {
+                            style: cell_style(180.0),
+                            ..default()
+                        })
+                        .with_children(|cell| {
+                            cell.small_label("").insert(GamepadText(action));
+                        });
+                    });
+            }
+
+            children.small_label("").insert(StatusText);
+
+            children.button("Defaults").insert(SettingsAction::Defaults);
+            children.button("Back").insert(SettingsAction::Back);
+        });
+}
+
+fn exit_settings(mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+}
+
+fn handle_settings_action(
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut button_query: InteractionQuery<&SettingsAction>,
+    mut rebinding: ResMut<Rebinding>,
+    mut input_map: ResMut<InputMap>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                SettingsAction::Rebind(action) => rebinding.0 = Some(*action),
+                SettingsAction::Defaults => {
+                    *input_map = InputMap::default();
+                    rebinding.0 = None;
+                }
+                SettingsAction::Back => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
+
+fn leave_settings(rebinding: Res<Rebinding>, mut next_screen: ResMut<NextState<Screen>>) {
+    // While rebinding the menu key only cancels, see `capture_key`
+    if rebinding.0.is_none() {
+        next_screen.set(Screen::Title);
+    }
+}
+
+fn capture_key(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut input_map: ResMut<InputMap>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+    let Some(key) = keys.get_just_pressed().next() else {
+        return;
+    };
+
+    if *key != KeyCode::Escape {
+        input_map.rebind_key(action, *key);
+        info!("{} bound to {:?}", action.name(), key);
+    }
+    rebinding.0 = None;
+}
+
+fn update_binding_texts(
+    input_map: Res<InputMap>,
+    rebinding: Res<Rebinding>,
+    q_keys: Query<(&KeyButton, &Children)>,
+    mut q_key_texts: Query<&mut Text, (Without<GamepadText>, Without<StatusText>)>,
+    mut q_gamepad: Query<(&GamepadText, &mut Text), Without<StatusText>>,
+    mut q_status: Query<&mut Text, (With<StatusText>, Without<GamepadText>)>,
+    q_new: Query<(), Added<StatusText>>,
+) {
+    if !input_map.is_changed() && !rebinding.is_changed() && q_new.is_empty() {
+        return;
+    }
+
+    let labels = |action: InputAction, gamepad: bool| {
+        let labels: Vec<&str> = input_map
+            .bindings(action)
+            .iter()
+            .filter(|binding| binding.is_gamepad() == gamepad)
+            .map(InputBinding::label)
+            .collect();
+        if labels.is_empty() {
+            "-".to_string()
+        } else {
+            labels.join(" / ")
+        }
+    };
+
+    let mut conflicts = Vec::new();
+    for (KeyButton(action), children) in q_keys.iter() {
+        let action_conflicts = input_map.conflicts(*action);
+        let mut texts = q_key_texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = if rebinding.0 == Some(*action) {
+                "Press a key...".to_string()
+            } else {
+                labels(*action, false)
+            };
+            text.sections[0].style.color = if action_conflicts.is_empty() {
+                BUTTON_TEXT
+            } else {
+                CONFLICT_TEXT
+            };
+        }
+        conflicts.extend(
+            action_conflicts
+                .into_iter()
+                .filter(|other| (*other as usize) > (*action as usize))
+                .map(|other| (*action, other)),
+        );
+    }
+
+    for (GamepadText(action), mut text) in q_gamepad.iter_mut() {
+        text.sections[0].value = labels(*action, true);
+    }
+
+    let Ok(mut status) = q_status.get_single_mut() else {
+        return;
+    };
+    status.sections[0].value = if let Some(action) = rebinding.0 {
+        format!("Press the new key for {}, Escape cancels", action.label())
+    } else if let Some((action, other)) = conflicts.first() {
+        format!("{} and {} share a key", action.label(), other.label())
+    } else {
+        String::new()
+    };
+    status.sections[0].style.color = if rebinding.0.is_none() && !conflicts.is_empty() {
+        CONFLICT_TEXT
+    } else {
+        LABEL_TEXT
+    };
+}
@@ -3,19 +3,47 @@
 use bevy::prelude::*;
 
 use super::Screen;
-use crate::ui::prelude::*;
+use crate::{
+    game::{autopilot::Autopilot, rng::ChosenSeed, spawn::level::CurrentLevel},
+    ui::prelude::*,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Title), enter_title);
 
     app.register_type::<TitleAction>();
-    app.add_systems(Update, handle_title_action.run_if(in_state(Screen::Title)));
+    app.add_systems(
+        Update,
+        (handle_title_action, type_seed, show_seed).run_if(in_state(Screen::Title)),
+    );
 }
 
+/// Label showing the [`ChosenSeed`]
+#[derive(Component)]
+struct SeedLabel;
+
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
 #[reflect(Component)]
 enum TitleAction {
     Play,
+    /// Play in an apartment generated from a random seed.
+    Generated,
+    /// Let the autopilot play, as an attract mode.
+    Demo,
+    Settings,
     Credits,
     /// Exit doesn't work well with embedded applications.
     #[cfg(not(target_family = "wasm"))]
@@ -28,7 +56,13 @@ fn enter_title(mut commands: Commands) {
         .insert(StateScoped(Screen::Title))
         .with_children(|children| {
             children.button("Play").insert(TitleAction::Play);
+            children
+                .button("Random apartment")
+                .insert(TitleAction::Generated);
+            children.button("Demo").insert(TitleAction::Demo);
+            children.button("Settings").insert(TitleAction::Settings);
             children.button("Credits").insert(TitleAction::Credits);
+            children.label("").insert(SeedLabel);
 
             #[cfg(not(target_family = "wasm"))]
             children.button("Exit").insert(TitleAction::Exit);
@@ -38,12 +72,32 @@ fn enter_title(mut commands: Commands) {
 fn handle_title_action(
     mut next_screen: ResMut<NextState<Screen>>,
     mut button_query: InteractionQuery<&TitleAction>,
+    mut autopilot: ResMut<Autopilot>,
+    mut current_level: ResMut<CurrentLevel>,
+    chosen_seed: Res<ChosenSeed>,
     #[cfg(not(target_family = "wasm"))] mut app_exit: EventWriter<AppExit>,
 ) {
     for (interaction, action) in &mut button_query {
         if matches!(interaction, Interaction::Pressed) {
             match action {
-                TitleAction::Play => next_screen.set(Screen::Playing),
+                TitleAction::Play => {
+                    autopilot.enabled = false;
+                    *current_level = CurrentLevel::default();
+                    next_screen.set(Screen::Playing);
+                }
+                TitleAction::Generated => {
+                    autopilot.enabled = false;
+                    *current_level = CurrentLevel::Generated {
+                        seed: chosen_seed.next_seed(),
+                    };
+                    next_screen.set(Screen::Playing);
+                }
+                TitleAction::Demo => {
+                    autopilot.enabled = true;
+                    *current_level = CurrentLevel::default();
+                    next_screen.set(Screen::Playing);
+                }
+                TitleAction::Settings => next_screen.set(Screen::Settings),
                 TitleAction::Credits => next_screen.set(Screen::Credits),
 
                 #[cfg(not(target_family = "wasm"))]
@@ -54,3 +108,41 @@ fn handle_title_action(
         }
     }
 }
+
+/// Digits typed on the title screen set the seed of the next runs, backspace
+/// takes them back
+fn type_seed(keys: Res<ButtonInput<KeyCode>>, mut chosen_seed: ResMut<ChosenSeed>) {
+    for (digit, key) in DIGIT_KEYS.iter().enumerate() {
+        if keys.just_pressed(*key) {
+            let seed = chosen_seed.0.unwrap_or(0);
+            if let Some(seed) = seed
+                .checked_mul(10)
+                .and_then(|seed| seed.checked_add(digit as u64))
+            {
+                chosen_seed.0 = Some(seed);
+            }
+        }
+    }
+    if keys.just_pressed(KeyCode::Backspace) {
+        chosen_seed.0 = chosen_seed.0.map(|seed| seed / 10).filter(|seed| *seed > 0);
+    }
+}
+
+fn show_seed(
+    chosen_seed: Res<ChosenSeed>,
+    q_labels: Query<&Children, With<SeedLabel>>,
+    mut q_texts: Query<&mut Text>,
+) {
+    let value = match chosen_seed.0 {
+        Some(seed) => format!("Seed: {}", seed),
+        None => "Seed: random (type digits to choose one)".to_string(),
+    };
+    for children in q_labels.iter() {
+        let mut texts = q_texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            if text.sections[0].value != value {
+                text.sections[0].value.clone_from(&value);
+            }
+        }
+    }
+}
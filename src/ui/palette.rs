@@ -8,3 +8,4 @@ pub const LABEL_TEXT: Color = Color::srgb(0.867, 0.827, 0.412);
 pub const HEADER_TEXT: Color = Color::srgb(0.867, 0.827, 0.412);
 
 pub const NODE_BACKGROUND: Color = Color::srgb(0.286, 0.478, 0.773);
+pub const PANEL_BACKGROUND: Color = Color::srgba(0.0, 0.0, 0.0, 0.8);
@@ -14,6 +14,13 @@ pub trait Widgets {
 
     /// Spawn a simple text label.
     fn label(&mut self, text: impl Into<String>) -> EntityCommands;
+
+    /// Spawn a button sized to its text, for the panels over the game.
+    fn small_button(&mut self, text: impl Into<String>) -> EntityCommands;
+
+    /// Spawn a bare text, for the panels over the game.
+    /// Smaller than [`Widgets::label`].
+    fn small_label(&mut self, text: impl Into<String>) -> EntityCommands;
 }
 
 impl<T: Spawn> Widgets for T {
@@ -112,6 +119,56 @@ impl<T: Spawn> Widgets for T {
         });
         entity
     }
+
+    fn small_button(&mut self, text: impl Into<String>) -> EntityCommands {
+        let mut entity = self.spawn((
+            Name::new("Button"),
+            ButtonBundle {
+                style: Style {
+                    height: Px(30.0),
+                    padding: UiRect::horizontal(Px(8.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BackgroundColor(NODE_BACKGROUND),
+                ..default()
+            },
+            InteractionPalette {
+                none: NODE_BACKGROUND,
+                hovered: BUTTON_HOVERED_BACKGROUND,
+                pressed: BUTTON_PRESSED_BACKGROUND,
+            },
+        ));
+        entity.with_children(|children| {
+            children.spawn((
+                Name::new("Button Text"),
+                TextBundle::from_section(
+                    text,
+                    TextStyle {
+                        font_size: 18.0,
+                        color: BUTTON_TEXT,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+        entity
+    }
+
+    fn small_label(&mut self, text: impl Into<String>) -> EntityCommands {
+        self.spawn((
+            Name::new("Label Text"),
+            TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size: 18.0,
+                    color: LABEL_TEXT,
+                    ..default()
+                },
+            ),
+        ))
+    }
 }
 
 /// An extension trait for spawning UI containers.
@@ -119,9 +176,12 @@ pub trait Containers {
     /// Spawns a root node that covers the full screen
     /// and centers its content horizontally and vertically.
     fn ui_root(&mut self) -> EntityCommands;
+
+    /// Spawns a dark see-through panel over the game, laid out by `style`.
+    fn panel(&mut self, style: Style) -> EntityCommands;
 }
 
-impl Containers for Commands<'_, '_> {
+impl<T: Spawn> Containers for T {
     fn ui_root(&mut self) -> EntityCommands {
         self.spawn((
             Name::new("UI Root"),
@@ -140,6 +200,18 @@ impl Containers for Commands<'_, '_> {
             },
         ))
     }
+
+    fn panel(&mut self, style: Style) -> EntityCommands {
+        self.spawn((
+            Name::new("Panel"),
+            NodeBundle {
+                z_index: ZIndex::Global(5),
+                style,
+                background_color: BackgroundColor(PANEL_BACKGROUND),
+                ..default()
+            },
+        ))
+    }
 }
 
 /// An internal trait for types that can spawn entities.